@@ -0,0 +1,205 @@
+use crate::todo::TodoPage;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+// Urgency buckets of the agenda, in display order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bucket {
+    Overdue,
+    Today,
+    Tomorrow,
+    Soon,
+    // Not a due date bucket, groups the todos of the review view
+    NeedsReview,
+}
+
+impl Bucket {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Bucket::Overdue => "Overdue",
+            Bucket::Today => "Today",
+            Bucket::Tomorrow => "Tomorrow",
+            Bucket::Soon => "Coming Up",
+            Bucket::NeedsReview => "Needs Review",
+        }
+    }
+
+    fn for_date(due: NaiveDate, today: NaiveDate) -> Self {
+        if due < today {
+            Bucket::Overdue
+        } else if due == today {
+            Bucket::Today
+        } else if due == today + Duration::days(1) {
+            Bucket::Tomorrow
+        } else {
+            Bucket::Soon
+        }
+    }
+}
+
+// An open todo shown in the agenda, pointing back into the page that owns it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AgendaEntry {
+    pub bucket: Bucket,
+    pub page: usize,
+    pub index: usize,
+}
+
+// Open todos of all pages due or scheduled within `horizon_days` of today,
+// sorted by bucket and then by date and time of day. Missed deadlines stay
+// overdue while missed scheduled dates roll forward to today. Snoozed todos
+// are left out until they wake up.
+pub fn agenda_entries(pages: &[TodoPage], today: NaiveDate, horizon_days: i64) -> Vec<AgendaEntry> {
+    let horizon = today + Duration::days(horizon_days);
+    let now = Local::now();
+    let mut entries: Vec<(NaiveDateTime, AgendaEntry)> = Vec::new();
+
+    for (page_index, page) in pages.iter().enumerate() {
+        for (index, todo) in page.todos.iter().enumerate() {
+            let deadline = todo.due_date.map(|d| d.naive_local());
+            let scheduled = todo.scheduled.map(|d| {
+                let rolled = d.date_naive().max(today);
+                rolled.and_time(d.time())
+            });
+            let due = match (deadline, scheduled) {
+                (Some(deadline), Some(scheduled)) => deadline.min(scheduled),
+                (Some(date), None) | (None, Some(date)) => date,
+                (None, None) => continue,
+            };
+            if todo.completed || todo.is_deferred(now) || due.date() > horizon {
+                continue;
+            }
+
+            let entry = AgendaEntry {
+                bucket: Bucket::for_date(due.date(), today),
+                page: page_index,
+                index,
+            };
+            entries.push((due, entry));
+        }
+    }
+
+    entries.sort_by_key(|(due, entry)| (entry.bucket, *due));
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+// Open todos of all pages due on `day`, for the calendar, in order of their
+// time of day
+pub fn day_entries(pages: &[TodoPage], day: NaiveDate, today: NaiveDate) -> Vec<AgendaEntry> {
+    let mut entries: Vec<(NaiveDateTime, AgendaEntry)> = Vec::new();
+
+    for (page_index, page) in pages.iter().enumerate() {
+        for (index, todo) in page.todos.iter().enumerate() {
+            let Some(due) = todo.due_date.map(|d| d.naive_local()) else {
+                continue;
+            };
+            if todo.completed || due.date() != day {
+                continue;
+            }
+
+            let entry = AgendaEntry {
+                bucket: Bucket::for_date(day, today),
+                page: page_index,
+                index,
+            };
+            entries.push((due, entry));
+        }
+    }
+
+    entries.sort_by_key(|(due, _)| *due);
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+// How many open todos of all pages are due on each day
+pub fn due_counts(pages: &[TodoPage]) -> BTreeMap<NaiveDate, usize> {
+    let mut counts = BTreeMap::new();
+    for todo in pages.iter().flat_map(|p| p.todos.iter()) {
+        if let (Some(due), false) = (todo.due_date, todo.completed) {
+            *counts.entry(due.date_naive()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+// Todos of all pages flagged for review, done or not, in page order
+pub fn review_entries(pages: &[TodoPage]) -> Vec<AgendaEntry> {
+    pages
+        .iter()
+        .enumerate()
+        .flat_map(|(page_index, page)| {
+            page.todos
+                .iter()
+                .enumerate()
+                .filter(|(_, todo)| todo.needs_review)
+                .map(move |(index, _)| AgendaEntry {
+                    bucket: Bucket::NeedsReview,
+                    page: page_index,
+                    index,
+                })
+        })
+        .collect()
+}
+
+// A due date changed by `postpone_overdue`, kept to undo the change
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Postponed {
+    pub id: Uuid,
+    pub from: DateTime<Local>,
+    pub to: DateTime<Local>,
+}
+
+// Move every open overdue todo to `date`, keeping the time of day of its due date
+pub fn postpone_overdue(
+    pages: &mut [TodoPage],
+    today: NaiveDate,
+    date: NaiveDate,
+) -> Vec<Postponed> {
+    let mut postponed = Vec::new();
+
+    for todo in pages.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+        let Some(from) = todo.due_date else {
+            continue;
+        };
+        if todo.completed || from.date_naive() >= today {
+            continue;
+        }
+
+        let Some(to) = date
+            .and_time(from.time())
+            .and_local_timezone(Local)
+            .earliest()
+        else {
+            continue;
+        };
+        todo.due_date = Some(to);
+        todo.touch();
+        postponed.push(Postponed {
+            id: todo.id,
+            from,
+            to,
+        });
+    }
+
+    postponed
+}
+
+// Restore the due dates changed by `postpone_overdue`, skipping todos whose due
+// date was changed again since. Returns how many were restored.
+pub fn undo_postpone(pages: &mut [TodoPage], postponed: &[Postponed]) -> usize {
+    let mut restored = 0;
+
+    for todo in pages.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+        let Some(change) = postponed.iter().find(|p| p.id == todo.id) else {
+            continue;
+        };
+        if todo.due_date == Some(change.to) {
+            todo.due_date = Some(change.from);
+            todo.touch();
+            restored += 1;
+        }
+    }
+
+    restored
+}
@@ -0,0 +1,70 @@
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use std::collections::BTreeSet;
+use std::{fs, io, path::Path};
+
+// Days off read from an iCalendar file, used to skip them when scheduling
+// onto the next workday. Weekends are always days off.
+#[derive(Clone, Debug, Default)]
+pub struct Holidays {
+    days: BTreeSet<NaiveDate>,
+}
+
+impl Holidays {
+    // Collect the days covered by every event of an .ics file. Only the dates
+    // of DTSTART and DTEND matter, times and recurrence rules are ignored.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("Cannot read holidays {}: {}", path.display(), err),
+            )
+        })?;
+
+        // Long lines are folded onto continuation lines starting with a space
+        let content = content.replace("\r\n", "\n").replace("\n ", "");
+
+        let mut days = BTreeSet::new();
+        let mut start = None;
+        let mut end = None;
+        for line in content.lines() {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            // Properties can carry parameters, e.g. DTSTART;VALUE=DATE:20251225
+            match name.split(';').next() {
+                Some("BEGIN") if value == "VEVENT" => (start, end) = (None, None),
+                Some("DTSTART") => start = parse_date(value),
+                Some("DTEND") => end = parse_date(value),
+                Some("END") if value == "VEVENT" => {
+                    let Some(start) = start else {
+                        continue;
+                    };
+                    // The end date is exclusive, one day events may leave it out
+                    let end = end
+                        .filter(|end| *end > start)
+                        .unwrap_or(start + Days::new(1));
+                    days.extend(start.iter_days().take_while(|day| *day < end));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self { days })
+    }
+
+    pub fn is_workday(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.days.contains(&date)
+    }
+
+    // The first workday on or after `date`
+    pub fn workday_from(&self, date: NaiveDate) -> NaiveDate {
+        date.iter_days()
+            .find(|day| self.is_workday(*day))
+            .unwrap_or(date)
+    }
+}
+
+// Date part of an iCalendar DATE or DATE-TIME value, e.g. 20251225 or 20251225T090000Z
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
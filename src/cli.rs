@@ -0,0 +1,765 @@
+use crate::agenda::{self, Postponed};
+use crate::config::{self, Config};
+use crate::diff;
+use crate::filter::Filter;
+use crate::merge;
+use crate::ops::{self, Operation};
+use crate::scan;
+use crate::session::Session;
+use crate::summary::{Counts, Summary};
+use crate::todo::{self, App};
+use crate::update;
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::process;
+
+// Exit codes for scripting: 0 on success, 1 when a query found nothing
+// (or `diff` found differences) and 2 on errors
+pub const EXIT_NO_MATCH: i32 = 1;
+pub const EXIT_ERROR: i32 = 2;
+
+// Print a usage message and exit, for malformed subcommand arguments
+fn usage(message: &str) -> ! {
+    eprintln!("Usage: {}", message);
+    process::exit(EXIT_ERROR);
+}
+
+// Read a todo file named on the command line, which has to exist
+fn read_store(path: &str) -> Result<Vec<todo::TodoPage>, Box<dyn Error>> {
+    let path = Path::new(path);
+    if !path.exists() {
+        return Err(format!("{} does not exist", path.display()).into());
+    }
+    // Fail on a file that isn't a todo file rather than merge nothing
+    let content = fs::read_to_string(path)?;
+    if let Err(err) = serde_json::from_str::<Vec<todo::TodoPage>>(&content) {
+        if serde_json::from_str::<Vec<todo::Todo>>(&content).is_err() {
+            return Err(format!("Invalid todo file {}: {}", path.display(), err).into());
+        }
+    }
+    Ok(todo::read_pages(path)?)
+}
+
+// Split the pages of another todo file by store, pages labelled "store/page"
+// as in the TUI go to that store, the others to the global one
+fn by_store(app: &App, pages: Vec<todo::TodoPage>) -> Vec<Vec<todo::TodoPage>> {
+    let mut stores = vec![Vec::new(); app.stores.len().max(1)];
+    for mut page in pages {
+        let labelled = page.name.split_once('/').and_then(|(store, name)| {
+            let index = app.stores.iter().position(|s| s.name == store)?;
+            Some((index, name.to_string()))
+        });
+        match labelled {
+            Some((index, name)) => {
+                page.name = name;
+                stores[index].push(page);
+            }
+            None => stores[0].push(page),
+        }
+    }
+    stores
+}
+
+// ratdo merge <other.json> [--base <base.json>]
+pub fn merge(app: &mut App, args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str = "ratdo merge <other.json> [--base <base.json>]";
+
+    let mut other = None;
+    let mut base = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--base" => match args.next() {
+                Some(path) => base = Some(read_store(path)?),
+                None => usage(USAGE),
+            },
+            path if other.is_none() => other = Some(read_store(path)?),
+            _ => usage(USAGE),
+        }
+    }
+
+    let Some(theirs) = other else { usage(USAGE) };
+    let base = base.map(|base| by_store(app, base));
+    let mut report = merge::MergeReport::default();
+    let mut pages = Vec::new();
+    for (store, theirs) in by_store(app, theirs).into_iter().enumerate() {
+        let mut mine: Vec<_> = app
+            .pages
+            .iter()
+            .filter(|p| p.store == store)
+            .cloned()
+            .collect();
+        let base = base.as_ref().map(|base| base[store].as_slice());
+        let merged = merge::merge_pages(&mut mine, theirs, base);
+        for page in &mut mine {
+            page.store = store;
+        }
+        pages.extend(mine);
+
+        report.added_pages += merged.added_pages;
+        report.added_todos += merged.added_todos;
+        report.updated_todos += merged.updated_todos;
+        report.removed_todos += merged.removed_todos;
+        report.conflicts.extend(merged.conflicts);
+        report.errors.extend(merged.errors);
+    }
+    app.pages = pages;
+    app.save_todos()?;
+
+    println!(
+        "Merged: {} pages added, {} todos added, {} updated, {} removed",
+        report.added_pages, report.added_todos, report.updated_todos, report.removed_todos
+    );
+    if !report.conflicts.is_empty() {
+        println!(
+            "{} fields were changed on both sides, the newest edit was kept",
+            report.conflicts.len()
+        );
+    }
+    for error in &report.errors {
+        eprintln!("Could not merge {}", error);
+    }
+    Ok(())
+}
+
+// ratdo config export [<file>] | ratdo config import <file>
+pub fn config(args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str = "ratdo config export [<file>] | ratdo config import <file>";
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["export"] => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&config::Bundle::export()?)?
+            );
+        }
+        ["export", path] => {
+            let bundle = config::Bundle::export()?;
+            fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+            let count = bundle.templates.len();
+            let templates = if count == 1 { "template" } else { "templates" };
+            let config = if bundle.config.is_some() {
+                "the config"
+            } else {
+                "no config file"
+            };
+            println!(
+                "Exported {} and {} {} to {}",
+                config, count, templates, path
+            );
+        }
+        ["import", path] => {
+            let content =
+                fs::read_to_string(path).map_err(|err| format!("Cannot read {}: {}", path, err))?;
+            let bundle: config::Bundle = serde_json::from_str(&content)
+                .map_err(|err| format!("Invalid bundle {}: {}", path, err))?;
+            let has_config = bundle.config.is_some();
+            let count = bundle.templates.len();
+            let replaced = bundle.import()?;
+
+            if has_config {
+                println!("Imported the config, the old one is kept as config.toml.bak");
+            }
+            let templates = if count == 1 { "template" } else { "templates" };
+            match replaced {
+                0 => println!("Imported {} {}", count, templates),
+                _ => println!(
+                    "Imported {} {}, {} replacing one with the same name",
+                    count, templates, replaced
+                ),
+            }
+        }
+        _ => usage(USAGE),
+    }
+    Ok(())
+}
+
+// ratdo insights [--json], the actions used in the TUI as counted in the session
+pub fn insights(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let json = match args {
+        [] => false,
+        [flag] if flag == "--json" => true,
+        _ => usage("ratdo insights [--json]"),
+    };
+
+    let usage = Session::load()?.usage;
+    if json {
+        let never_used: Vec<&str> = usage
+            .never_used()
+            .iter()
+            .map(|&(_, name, _)| name)
+            .collect();
+        let insights = serde_json::json!({
+            "since": usage.since,
+            "actions": usage.actions,
+            "never_used": never_used,
+        });
+        println!("{}", serde_json::to_string_pretty(&insights)?);
+    } else {
+        print!("{}", usage.report(&Config::load()?.keys));
+    }
+    Ok(())
+}
+
+// ratdo reset --all, asking for the word "reset" to be typed first
+pub fn reset(args: &[String]) -> Result<(), Box<dyn Error>> {
+    match args {
+        [flag] if flag == "--all" => {}
+        _ => usage("ratdo reset --all"),
+    }
+
+    let dir = config::config_dir()?;
+    if !dir.exists() {
+        println!("Nothing to reset, {} doesn't exist", dir.display());
+        return Ok(());
+    }
+    let mut files = fs::read_dir(&dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect::<io::Result<Vec<String>>>()?;
+    files.sort();
+
+    eprintln!(
+        "Everything in {} is moved to a backup and ratdo starts fresh:",
+        dir.display()
+    );
+    for file in &files {
+        eprintln!("  {}", file);
+    }
+    eprint!("Type reset to go ahead: ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if answer.trim() != "reset" {
+        eprintln!("Nothing was changed");
+        process::exit(EXIT_ERROR);
+    }
+    let backup = config::reset()?;
+    println!(
+        "Moved to {}, delete it once it isn't needed anymore",
+        backup.display()
+    );
+    Ok(())
+}
+
+// ratdo --version [--check-update], with the flag also whether a newer
+// release is out
+pub fn version(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let check = match args {
+        [] => false,
+        [flag] if flag == "--check-update" => true,
+        _ => usage("ratdo --version [--check-update]"),
+    };
+
+    println!("ratdo {}", update::VERSION);
+    if check {
+        let latest = update::latest_version()?;
+        if update::is_newer(&latest, update::VERSION) {
+            println!("ratdo {} is available", latest);
+        } else {
+            println!("Up to date");
+        }
+    }
+    Ok(())
+}
+
+// ratdo diff <a.json> <b.json> [--json]
+pub fn diff(args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str = "ratdo diff <a.json> <b.json> [--json]";
+
+    let json = args.iter().any(|a| a == "--json");
+    let paths: Vec<&String> = args.iter().filter(|a| *a != "--json").collect();
+    let [old, new] = paths.as_slice() else {
+        usage(USAGE)
+    };
+
+    let diff = diff::diff_pages(&read_store(old)?, &read_store(new)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else if diff.is_empty() {
+        println!("No differences");
+    } else {
+        println!("{}", diff::format_diff(&diff));
+    }
+
+    // Like diff(1), signal differences through the exit code
+    if !diff.is_empty() {
+        process::exit(EXIT_NO_MATCH);
+    }
+    Ok(())
+}
+
+// ratdo apply <ops.json>
+pub fn apply(app: &mut App, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let [path] = args else {
+        usage("ratdo apply <ops.json>")
+    };
+
+    let content = fs::read_to_string(path)?;
+    let operations: Vec<Operation> = serde_json::from_str(&content)
+        .map_err(|err| format!("Invalid operations file {}: {}", path, err))?;
+
+    ops::apply_operations(&mut app.pages, &operations)?;
+    app.save_todos()?;
+
+    // Keep a record of every change made through batch files
+    let timestamp = Local::now().to_rfc3339();
+    let lines: Vec<String> = operations
+        .iter()
+        .map(|operation| format!("{} {}: {}", timestamp, path, operation.summary()))
+        .collect();
+    app.storage.append_log(&lines)?;
+
+    println!("Applied {} operations", operations.len());
+    Ok(())
+}
+
+// ratdo list [--filter <expression>] [--quiet] [--ids | --links]
+pub fn list(app: &App, args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str = "ratdo list [--filter <expression>] [--quiet] [--ids | --links]";
+
+    let mut filter = Filter::default();
+    let mut quiet = false;
+    let mut ids = false;
+    let mut links = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--filter" | "-f" => match args.next() {
+                Some(expression) => filter = Filter::parse(expression)?,
+                None => usage(USAGE),
+            },
+            "--quiet" | "-q" => quiet = true,
+            "--ids" => ids = true,
+            "--links" => links = true,
+            _ => usage(USAGE),
+        }
+    }
+
+    let today = Local::now().date_naive();
+    let mut found = 0;
+    for page in &app.pages {
+        for todo in page.todos.iter().filter(|t| filter.matches(page, t, today)) {
+            found += 1;
+            if quiet {
+                continue;
+            }
+
+            let status = if todo.completed { "[x]" } else { "[ ]" };
+            let due = todo
+                .due_date
+                .map(|due| format!(" (due {})", todo::format_due_date(due)))
+                .unwrap_or_default();
+            let scheduled = todo
+                .scheduled
+                .map(|date| format!(" (scheduled {})", todo::format_due_date(date)))
+                .unwrap_or_default();
+            if links {
+                print!("{} ", todo.link());
+            } else if ids {
+                print!("{} ", todo.id);
+            }
+            println!(
+                "{}: {} {}{}{}",
+                app.page_label(page),
+                status,
+                todo.description,
+                due,
+                scheduled
+            );
+        }
+    }
+
+    if found == 0 {
+        process::exit(EXIT_NO_MATCH);
+    }
+    Ok(())
+}
+
+// ratdo open-id <id | ratdo://todo/id>, opening the TUI on that todo
+pub fn open_id(app: &mut App, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let [link] = args else {
+        usage("ratdo open-id <id | ratdo://todo/id>")
+    };
+    let Some(id) = todo::parse_link(link) else {
+        return Err(format!("Not a todo ID or link: {}", link).into());
+    };
+    if !app.select_todo_by_id(id) {
+        return Err(format!("No todo with the ID {}", id).into());
+    }
+    Ok(())
+}
+
+// ratdo scan <dir> [--page <name>]
+// Import the TODO and FIXME comments of the files under a directory as todos
+// of a page, named after the directory unless given. Comments imported before,
+// from this or any other page, are skipped.
+pub fn scan(app: &mut App, args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str = "ratdo scan <dir> [--page <name>]";
+
+    let (dir, page) = match args {
+        [dir] => (dir, None),
+        [dir, flag, page] if flag == "--page" => (dir, Some(page.clone())),
+        _ => usage(USAGE),
+    };
+    let dir = Path::new(dir);
+    let found = scan::scan(dir).map_err(|err| format!("Cannot scan {}: {}", dir.display(), err))?;
+    let page = match page {
+        Some(page) => page,
+        None => dir
+            .canonicalize()?
+            .file_name()
+            .map_or("Code".to_string(), |name| {
+                name.to_string_lossy().into_owned()
+            }),
+    };
+
+    let total = found.len();
+    let new = scan::unimported(found, &app.pages);
+    let count = new.len();
+
+    if count > 0 {
+        app.create_or_select_page(&page);
+        app.todos_mut()
+            .extend(new.into_iter().map(scan::Found::into_todo));
+        app.save_todos()?;
+    }
+    let todos = if count == 1 { "todo" } else { "todos" };
+    println!(
+        "Imported {} {} to {}, {} of the {} comments found were imported before",
+        count,
+        todos,
+        page,
+        total - count,
+        total
+    );
+    Ok(())
+}
+
+// ratdo postpone --overdue <+Nd|+Nw> | --undo
+// Move all overdue todos to the given number of days from today. The previous
+// due dates are kept in postpone.json until the next postpone, for `--undo`.
+pub fn postpone(app: &mut App, args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str = "ratdo postpone --overdue <+Nd|+Nw> | --undo";
+
+    match args {
+        [flag, offset] if flag == "--overdue" => {
+            let today = Local::now().date_naive();
+            let date = todo::parse_offset(offset)
+                .and_then(|days| today.checked_add_days(days))
+                .ok_or(format!(
+                    "Invalid offset {}, expected e.g. +1d or +2w",
+                    offset
+                ))?;
+
+            let postponed = agenda::postpone_overdue(&mut app.pages, today, date);
+            app.save_todos()?;
+            app.storage.save_postponed(Some(&postponed))?;
+
+            for change in &postponed {
+                if let Some((page, todo)) = find_by_id(app, change) {
+                    println!(
+                        "{}: {} ({} -> {})",
+                        app.page_label(page),
+                        todo.description,
+                        todo::format_due_date(change.from),
+                        todo::format_due_date(change.to)
+                    );
+                }
+            }
+            println!(
+                "Postponed {} overdue todos to {}",
+                postponed.len(),
+                date.format("%Y-%m-%d")
+            );
+        }
+        [flag] if flag == "--undo" => {
+            let Some(postponed) = app.storage.load_postponed()? else {
+                return Err("Nothing to undo".into());
+            };
+            let restored = agenda::undo_postpone(&mut app.pages, &postponed);
+            app.save_todos()?;
+            app.storage.save_postponed(None)?;
+            println!("Restored {} due dates", restored);
+        }
+        _ => usage(USAGE),
+    }
+    Ok(())
+}
+
+fn find_by_id<'a>(
+    app: &'a App,
+    change: &Postponed,
+) -> Option<(&'a todo::TodoPage, &'a todo::Todo)> {
+    app.pages.iter().find_map(|page| {
+        page.todos
+            .iter()
+            .find(|t| t.id == change.id)
+            .map(|todo| (page, todo))
+    })
+}
+
+// Number of single character edits turning one string into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Up to three page labels close to a name that matched no page, closest first
+fn similar_pages(app: &App, name: &str) -> Vec<String> {
+    let name = name.to_lowercase();
+    let limit = (name.chars().count() / 3).max(2);
+    let mut similar: Vec<(usize, String)> = app
+        .page_names()
+        .into_iter()
+        .map(|label| (edit_distance(&name, &label.to_lowercase()), label))
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    similar.sort();
+    similar
+        .into_iter()
+        .take(3)
+        .map(|(_, label)| label)
+        .collect()
+}
+
+// Select the page named on the command line. A page that doesn't exist is
+// only created after asking, with similar names offered in case of a typo,
+// and with `--no-create` the run stops instead.
+pub fn open_page(app: &mut App, name: &str, create: bool) -> Result<(), Box<dyn Error>> {
+    if app.select_page_by_name(name) {
+        return Ok(());
+    }
+    let similar = similar_pages(app, name);
+    // A page that only differs in case is the one that was meant
+    if let Some(label) = similar.iter().find(|l| l.eq_ignore_ascii_case(name)) {
+        app.select_page_by_label(label);
+        return Ok(());
+    }
+
+    if !create {
+        return Err(not_found(name, &similar).into());
+    }
+    // Scripts can't answer, they keep getting the page created
+    if !io::stdin().is_terminal() {
+        app.create_or_select_page(name);
+        return Ok(());
+    }
+
+    eprintln!("Page '{}' not found.", name);
+    for (number, label) in similar.iter().enumerate() {
+        eprintln!("  {}) {}", number + 1, label);
+    }
+    let choices = match similar.len() {
+        0 => String::new(),
+        1 => ", open 1".to_string(),
+        n => format!(", open 1-{}", n),
+    };
+    eprint!("Create it [c]{} or quit [q]? ", choices);
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    let answer = answer.trim();
+    if answer.eq_ignore_ascii_case("c") {
+        app.create_or_select_page(name);
+        return Ok(());
+    }
+    match answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| similar.get(n.checked_sub(1)?))
+    {
+        Some(label) => {
+            app.select_page_by_label(label);
+            Ok(())
+        }
+        None => process::exit(EXIT_ERROR),
+    }
+}
+
+fn not_found(name: &str, similar: &[String]) -> String {
+    match similar {
+        [] => format!("Page '{}' not found", name),
+        _ => format!(
+            "Page '{}' not found, did you mean '{}'?",
+            name,
+            similar.join("' or '")
+        ),
+    }
+}
+
+// ratdo --page <name> [<prefix>:] <item>, <item>; ...
+// Add a comma or semicolon separated list of todos to a page, which is created
+// when needed, before the TUI opens on it. A leading "prefix:" is put in front
+// of every item, `ratdo --page Trip pack: passport, charger` adds "pack
+// passport" and "pack charger".
+pub fn quick_list(app: &mut App, args: &[String], create: bool) -> Result<(), Box<dyn Error>> {
+    let Some((page, words)) = args.split_first() else {
+        usage("ratdo --page <name> [<prefix>:] <item>, <item>; ...")
+    };
+    open_page(app, page, create)?;
+
+    let text = words.join(" ");
+    let (prefix, list) = match text.split_once(": ") {
+        Some((prefix, list)) if !prefix.contains([',', ';']) => {
+            (format!("{} ", prefix.trim()), list)
+        }
+        _ => (String::new(), text.as_str()),
+    };
+
+    let mut added = 0;
+    for item in list
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+    {
+        app.current_input = format!("{}{}", prefix, item);
+        app.add_todo();
+        added += 1;
+    }
+    if added > 0 {
+        app.save_todos()?;
+        app.set_status(format!("Added {} todos to {}", added, page));
+    }
+    Ok(())
+}
+
+// Read the summary sidecar, falling back to the store when no summary was saved yet
+fn load_summary() -> Result<Summary, Box<dyn Error>> {
+    if let Some(summary) = Summary::load()? {
+        return Ok(summary);
+    }
+
+    let mut app = App::new();
+    app.config = config::Config::load()?;
+    app.load_todos()?;
+    Ok(Summary::from_pages(&app.pages))
+}
+
+// ratdo prompt [--no-color]
+// Short segment for shell prompts, e.g. "☑ 3∣⚑1" for three open todos of
+// which one is due today or overdue
+pub fn prompt(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let color = match args {
+        [] => true,
+        [flag] if flag == "--no-color" => false,
+        _ => usage("ratdo prompt [--no-color]"),
+    };
+
+    let totals = load_summary()?.totals;
+    let today = Local::now().date_naive();
+    let overdue = totals.overdue(today);
+    let urgent = overdue + totals.due_today(today);
+
+    let mut segment = format!("☑ {}", totals.open);
+    if urgent > 0 {
+        let flag = format!("⚑{}", urgent);
+        let flag = match (color, overdue > 0) {
+            (false, _) => flag,
+            (true, true) => format!("\x1b[31m{}\x1b[0m", flag),
+            (true, false) => format!("\x1b[33m{}\x1b[0m", flag),
+        };
+        segment.push_str(&format!("∣{}", flag));
+    }
+
+    println!("{}", segment);
+    Ok(())
+}
+
+// Counts of one page or of all pages as reported by `ratdo status`
+#[derive(Serialize)]
+struct StatusCounts<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    open: usize,
+    completed: usize,
+    due_today: usize,
+    overdue: usize,
+}
+
+impl<'a> StatusCounts<'a> {
+    fn new(name: Option<&'a str>, counts: &Counts, today: NaiveDate) -> Self {
+        Self {
+            name,
+            open: counts.open,
+            completed: counts.completed,
+            due_today: counts.due_today(today),
+            overdue: counts.overdue(today),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} open, {} done, {} due today, {} overdue",
+            self.open, self.completed, self.due_today, self.overdue
+        )
+    }
+}
+
+// ratdo status [--json | --waybar]
+pub fn status(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let format = match args {
+        [] => "text",
+        [flag] if flag == "--json" => "json",
+        [flag] if flag == "--waybar" => "waybar",
+        _ => usage("ratdo status [--json | --waybar]"),
+    };
+
+    let summary = load_summary()?;
+    let today = Local::now().date_naive();
+    let totals = StatusCounts::new(None, &summary.totals, today);
+    let pages: Vec<StatusCounts> = summary
+        .pages
+        .iter()
+        .map(|page| StatusCounts::new(Some(&page.name), &page.counts, today))
+        .collect();
+
+    match format {
+        "json" => {
+            let status = serde_json::json!({ "total": totals, "pages": pages });
+            println!("{}", serde_json::to_string_pretty(&status)?);
+        }
+        "waybar" => {
+            // Custom module format, the class lets the bar style urgent states
+            let class = if totals.overdue > 0 {
+                "overdue"
+            } else if totals.due_today > 0 {
+                "due"
+            } else {
+                "clear"
+            };
+            let tooltip: Vec<String> = pages
+                .iter()
+                .map(|page| format!("{}: {}", page.name.unwrap_or(""), page.describe()))
+                .collect();
+            let status = serde_json::json!({
+                "text": format!("☑ {}", totals.open),
+                "tooltip": tooltip.join("\n"),
+                "class": class,
+            });
+            println!("{}", status);
+        }
+        _ => {
+            for page in &pages {
+                println!("{}: {}", page.name.unwrap_or(""), page.describe());
+            }
+            println!("Total: {}", totals.describe());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+// Copy text to the system clipboard with the tool of the platform: pbcopy on
+// macOS, clip on Windows and wl-copy, xclip or xsel on Linux, whichever is
+// installed
+pub fn copy(text: &str) -> io::Result<()> {
+    let tools: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if cfg!(windows) {
+        &[("clip", &[])]
+    } else if env::var_os("WAYLAND_DISPLAY").is_some() {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    } else {
+        &[
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+            ("wl-copy", &[]),
+        ]
+    };
+
+    for (tool, args) in tools {
+        let child = Command::new(tool)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        // Closing stdin lets the tool take the text over
+        child
+            .stdin
+            .take()
+            .expect("piped stdin")
+            .write_all(text.as_bytes())?;
+        if !child.wait()?.success() {
+            return Err(io::Error::other(format!("{} failed", tool)));
+        }
+        return Ok(());
+    }
+
+    Err(not_found(tools))
+}
+
+// Text on the system clipboard, read with the counterpart of the tool `copy`
+// uses
+pub fn paste() -> io::Result<String> {
+    let tools: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("pbpaste", &[])]
+    } else if cfg!(windows) {
+        &[("powershell", &["-NoProfile", "-Command", "Get-Clipboard"])]
+    } else if env::var_os("WAYLAND_DISPLAY").is_some() {
+        &[
+            ("wl-paste", &["--no-newline"]),
+            ("xclip", &["-selection", "clipboard", "-o"]),
+            ("xsel", &["--clipboard", "--output"]),
+        ]
+    } else {
+        &[
+            ("xclip", &["-selection", "clipboard", "-o"]),
+            ("xsel", &["--clipboard", "--output"]),
+            ("wl-paste", &["--no-newline"]),
+        ]
+    };
+
+    for (tool, args) in tools {
+        let output = match Command::new(tool)
+            .args(*args)
+            .stderr(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if !output.status.success() {
+            return Err(io::Error::other(format!("{} failed", tool)));
+        }
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+    }
+
+    Err(not_found(tools))
+}
+
+fn not_found(tools: &[(&str, &[&str])]) -> io::Error {
+    let names: Vec<&str> = tools.iter().map(|(tool, _)| *tool).collect();
+    io::Error::other(format!(
+        "no clipboard tool found, install one of {}",
+        names.join(", ")
+    ))
+}
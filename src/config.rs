@@ -0,0 +1,594 @@
+use crate::keymap::{self, Keymap};
+use crate::template::{self, Template};
+use crate::theme::{self, Theme};
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+// User settings read from ~/.config/ratdo/config.toml, every key is optional
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    // Descriptions longer than this trigger a warning before they are accepted
+    pub max_description_length: usize,
+    // How many days ahead the agenda view looks for due todos
+    pub agenda_horizon_days: i64,
+    // Extra todo files loaded next to the global one, each as its own page group
+    pub stores: Vec<StoreConfig>,
+    // iCalendar file of holidays skipped when rescheduling to the next workday
+    pub holidays: Option<String>,
+    // Named sets of pages, Tab only cycles through the active one
+    pub workspaces: Vec<WorkspaceConfig>,
+    // Name signing comments, defaults to the login name
+    pub name: Option<String>,
+    // Checking off todos on these pages or with these tags asks for a short note
+    pub completion_note_pages: Vec<String>,
+    pub completion_note_tags: Vec<String>,
+    // Which deletions ask for a yes first
+    pub confirm_delete: ConfirmDelete,
+    // Open todos gathered on the day's journal page when a new day starts
+    pub carry_over: Option<CarryOverConfig>,
+    // Look for a new release on GitHub once a week, off unless turned on
+    pub update_check: bool,
+    // Open the summary of yesterday and today on the first start of a day
+    pub day_summary: bool,
+    // Todo rows on one line, or with their details on a second one
+    pub density: Density,
+    // Preset name or a table of colors, see `Theme`
+    #[serde(skip_serializing)]
+    pub theme: Theme,
+    // Keys of the todo list and agenda actions, see `Keymap`
+    #[serde(skip_serializing)]
+    pub keys: Keymap,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoreConfig {
+    pub name: String,
+    // Relative paths are resolved against the config directory
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmDelete {
+    // Todos too, the marked ones included
+    All,
+    // Pages and all their todos, todos go with one key and can be undone
+    #[default]
+    Pages,
+    Never,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Density {
+    // The description and its badges on one line
+    #[default]
+    Compact,
+    // The due date, tags and age on a second line below the description
+    Detailed,
+}
+
+impl Density {
+    pub fn name(self) -> &'static str {
+        match self {
+            Density::Compact => "compact",
+            Density::Detailed => "detailed",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CarryOverConfig {
+    // Page names, or "store/page" labels when several stores are loaded
+    #[serde(default)]
+    pub pages: Vec<String>,
+    // Also the journal pages of earlier days
+    #[serde(default)]
+    pub journal: bool,
+    #[serde(default)]
+    pub action: CarryOverAction,
+}
+
+// What happens to the open todos of the carry-over pages
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CarryOverAction {
+    // Onto today's journal page
+    #[default]
+    Move,
+    // Onto today's journal page, leaving the todo where it was
+    Copy,
+    // Tagged +carried where they are
+    Flag,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorkspaceConfig {
+    pub name: String,
+    // Page names, or "store/page" labels when several stores are loaded
+    pub pages: Vec<String>,
+    // Rules picking this workspace on startup, all given ones have to match:
+    // a time window like "09:00-17:00", host names and directories ratdo is
+    // started in (or below)
+    #[serde(default)]
+    pub hours: Option<String>,
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    #[serde(default)]
+    pub dirs: Vec<String>,
+}
+
+impl WorkspaceConfig {
+    fn has_rules(&self) -> bool {
+        self.hours.is_some() || !self.hosts.is_empty() || !self.dirs.is_empty()
+    }
+
+    // Whether the startup rules select this workspace, never without rules
+    pub fn matches(&self, now: NaiveTime, host: &str, cwd: &Path) -> bool {
+        if !self.has_rules() {
+            return false;
+        }
+
+        let in_hours = match &self.hours {
+            Some(window) => parse_window(window).is_some_and(|(start, end)| {
+                // Windows like "22:00-06:00" wrap around midnight
+                if start <= end {
+                    start <= now && now < end
+                } else {
+                    now >= start || now < end
+                }
+            }),
+            None => true,
+        };
+        let on_host =
+            self.hosts.is_empty() || self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host));
+        let in_dir = self.dirs.is_empty()
+            || self
+                .dirs
+                .iter()
+                .any(|dir| expand_home(dir).is_ok_and(|dir| cwd.starts_with(dir)));
+
+        in_hours && on_host && in_dir
+    }
+}
+
+// "HH:MM-HH:MM"
+fn parse_window(window: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = window.split_once('-')?;
+    let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+    let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+    Some((start, end))
+}
+
+// Name of this machine, empty when it can't be determined
+pub fn hostname() -> String {
+    env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            let output = Command::new("hostname").output().ok()?;
+            String::from_utf8(output.stdout).ok()
+        })
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+impl StoreConfig {
+    pub fn resolved_path(&self) -> io::Result<PathBuf> {
+        let path = expand_home(&self.path)?;
+        match path.is_absolute() {
+            true => Ok(path),
+            false => Ok(config_dir()?.join(path)),
+        }
+    }
+}
+
+// Resolve a leading `~` to the home directory
+pub fn expand_home(path: &str) -> io::Result<PathBuf> {
+    match path.strip_prefix("~/") {
+        Some(rest) => Ok(home_dir()?.join(rest)),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_description_length: 120,
+            agenda_horizon_days: 7,
+            stores: Vec::new(),
+            holidays: None,
+            workspaces: Vec::new(),
+            name: None,
+            completion_note_pages: Vec::new(),
+            completion_note_tags: Vec::new(),
+            confirm_delete: ConfirmDelete::default(),
+            carry_over: None,
+            update_check: false,
+            day_summary: false,
+            density: Density::default(),
+            theme: Theme::default(),
+            keys: Keymap::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn author(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| env::var("USER").ok())
+            .or_else(|| env::var("USERNAME").ok())
+            .unwrap_or_else(|| "me".to_string())
+    }
+
+    // Load the config file, falling back to defaults when it doesn't exist
+    pub fn load() -> io::Result<Self> {
+        let path = path()?;
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Invalid config file {}: {}\nStart with --safe to ignore it",
+                    path.display(),
+                    err
+                ),
+            )
+        })
+    }
+}
+
+// Set a top-level setting of the config file to a TOML value, keeping the
+// rest of the file as it is. The setting, or its commented out line, is
+// replaced, otherwise it is added below the other top-level settings.
+pub fn set(key: &str, value: &str) -> io::Result<()> {
+    let path = path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let table = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let is_key = |line: &str| line.split('=').next().is_some_and(|k| k.trim() == key);
+    let set_at = lines[..table].iter().position(|line| is_key(line));
+    let commented_at = lines[..table].iter().position(|line| {
+        line.trim_start()
+            .strip_prefix('#')
+            .is_some_and(|line| line.contains('=') && is_key(line))
+    });
+    let setting = format!("{} = {}", key, value);
+    match set_at.or(commented_at) {
+        Some(at) => lines[at] = setting,
+        None => {
+            // After the last line of the top-level settings
+            let end = lines[..table]
+                .iter()
+                .rposition(|line| !line.trim().is_empty())
+                .map_or(0, |last| last + 1);
+            lines.insert(end, setting);
+        }
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    // Never leave behind a file that no longer loads
+    toml::from_str::<Config>(&content).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} would become invalid: {}", path.display(), err),
+        )
+    })?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
+}
+
+fn path() -> io::Result<PathBuf> {
+    Ok(config_dir()?.join("config.toml"))
+}
+
+// A config file with every setting commented out at its default, printed by
+// `ratdo --print-default-config`. The keys are listed from `keymap::ACTIONS`.
+pub fn default_config() -> String {
+    let mut config = format!(
+        r##"# RatDo settings, read from ~/.config/ratdo/config.toml. Every setting is
+# optional and shown with its default, remove the # in front of a line to
+# change it.
+
+# Warn before accepting todo descriptions longer than this
+# max_description_length = 120
+
+# How many days ahead the agenda view looks for due todos
+# agenda_horizon_days = 7
+
+# Checking off todos on these pages or with these tags asks for a one line
+# outcome, which is added to the todo's notes
+# completion_note_pages = []
+# completion_note_tags = []
+
+# Which deletions ask for a yes first: "pages" for deleting a page in the page
+# list, "all" for todos too, or "never"
+# confirm_delete = "pages"
+
+# Name signing comments, defaults to the login name
+# name = "alice"
+
+# Holidays skipped, like weekends, when rescheduling to the next workday
+# holidays = "~/.config/ratdo/holidays.ics"
+
+# Look for a new release on GitHub once a week and mention it in the status
+# line. Only the latest release is asked for, nothing else is sent.
+# update_check = false
+
+# On the first start of each day, open a summary of what was done yesterday,
+# what is due today and what is overdue. B opens it again.
+# day_summary = false
+
+# Todo rows: "compact" on one line, or "detailed" with the due date, tags and
+# how long ago the todo was added on a second line. z v switches and saves it
+# here.
+# density = "compact"
+
+# Sets of pages switched with W, Tab then only cycles through the pages of the
+# active workspace. A workspace is picked on startup when all of its optional
+# hours, hosts and dirs rules match.
+# [[workspaces]]
+# name = "Work"
+# pages = ["Work", "Meetings"]
+# hours = "08:00-18:00"
+# hosts = ["work-laptop"]
+# dirs = ["~/work"]
+
+# Extra todo files shown as their own page groups, relative paths are resolved
+# against this directory
+# [[stores]]
+# name = "project"
+# path = "~/project/.ratdo.json"
+
+# On the first start of a day, gather the open todos of these pages, and of
+# earlier journal pages with journal = true, on today's journal page. The action
+# is "move", "copy", or "flag" to tag them +carried where they are.
+# [carry_over]
+# pages = []
+# journal = false
+# action = "move"
+
+# Colors, a preset with any of its colors replaced: title, selection,
+# completed, popup, border, heading, muted, error, overdue, due_today,
+# scheduled, priority_high, priority_medium, priority_low, timer, tag, snoozed,
+# review and quantity. Colors are names ("yellow", "light-blue"), "#rrggbb" or
+# 0-255.
+# Presets: {}
+# [theme]
+# preset = "default"
+# selection = "yellow"
+
+# Keys of the todo list and agenda, a preset with keys for any of the actions
+# below, which replace the preset's ones and are taken from any other action.
+# Keys are single characters, "ctrl+r", "space", "enter", "esc", "tab",
+# "backtab", "delete", "up", "down", "left", "right", "pageup" and "pagedown",
+# or two of them pressed one after the other, like "z a".
+# Presets: {}
+# [keys]
+# preset = "vim"
+"##,
+        theme::PRESETS.map(|p| format!("\"{}\"", p)).join(", "),
+        keymap::PRESETS.map(|p| format!("\"{}\"", p)).join(", "),
+    );
+    for (_, name, keys, description, _) in keymap::ACTIONS {
+        let keys: Vec<String> = keys.iter().map(|key| format!("\"{}\"", key)).collect();
+        config.push_str(&format!(
+            "# {} = [{}]  # {}\n",
+            name,
+            keys.join(", "),
+            description
+        ));
+    }
+    config
+}
+
+// A setup bundled into one file by `ratdo config export`, for copying it to
+// another machine with `ratdo config import`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Bundle {
+    // Text of the config file, comments, theme and keys included, none when
+    // there is no config file
+    #[serde(default)]
+    pub config: Option<String>,
+    #[serde(default)]
+    pub templates: Vec<Template>,
+}
+
+impl Bundle {
+    pub fn export() -> io::Result<Self> {
+        let path = path()?;
+        let config = if path.exists() {
+            Some(fs::read_to_string(path)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            config,
+            templates: template::load()?,
+        })
+    }
+
+    // Write the bundled config file, keeping the current one as
+    // config.toml.bak, and add the templates, replacing the ones with the same
+    // name. The config is checked before anything is written. Returns how many
+    // templates were replaced.
+    pub fn import(self) -> io::Result<usize> {
+        if let Some(config) = &self.config {
+            toml::from_str::<Config>(config).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid config in the bundle: {}", err),
+                )
+            })?;
+        }
+
+        let mut templates = template::load()?;
+        let mut replaced = 0;
+        for imported in self.templates {
+            match templates
+                .iter_mut()
+                .find(|t| t.name.eq_ignore_ascii_case(&imported.name))
+            {
+                Some(existing) => {
+                    *existing = imported;
+                    replaced += 1;
+                }
+                None => templates.push(imported),
+            }
+        }
+        template::save(&templates)?;
+
+        if let Some(config) = self.config {
+            let path = path()?;
+            if path.exists() {
+                fs::copy(&path, path.with_extension("toml.bak"))?;
+            }
+            fs::write(path, config)?;
+        }
+        Ok(replaced)
+    }
+}
+
+// A path under the home directory written with a leading `~`, for display
+pub fn shorten_home(path: &Path) -> String {
+    match home_dir()
+        .ok()
+        .and_then(|home| path.strip_prefix(home).ok())
+    {
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+fn home_dir() -> io::Result<PathBuf> {
+    let home = env::var("HOME")
+        .or_else(|_| env::var("USERPROFILE"))
+        .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "Home directory not found"))?;
+
+    Ok(PathBuf::from(home))
+}
+
+// Directory holding both the config file and the global todo store
+pub fn config_dir() -> io::Result<PathBuf> {
+    Ok(home_dir()?.join(".config").join("ratdo"))
+}
+
+// Move the config directory, todos, session, templates and logs included, to
+// a dated ratdo-backup directory next to it so ratdo starts fresh. Returns
+// where it went.
+pub fn reset() -> io::Result<PathBuf> {
+    let dir = config_dir()?;
+    let backup = dir.with_file_name(format!(
+        "ratdo-backup-{}",
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    ));
+    fs::rename(&dir, &backup)?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_paths_are_relative_to_the_config_dir() {
+        let store = |path: &str| StoreConfig {
+            name: "project".to_string(),
+            path: path.to_string(),
+        };
+        let dir = config_dir().unwrap();
+        assert_eq!(
+            store("work.json").resolved_path().unwrap(),
+            dir.join("work.json")
+        );
+        assert_eq!(
+            store("/tmp/work.json").resolved_path().unwrap(),
+            PathBuf::from("/tmp/work.json")
+        );
+        assert_eq!(
+            store("~/work.json").resolved_path().unwrap(),
+            home_dir().unwrap().join("work.json")
+        );
+    }
+
+    fn time(text: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(text, "%H:%M").unwrap()
+    }
+
+    fn workspace(hours: Option<&str>, hosts: &[&str], dirs: &[&str]) -> WorkspaceConfig {
+        WorkspaceConfig {
+            name: "Work".to_string(),
+            pages: Vec::new(),
+            hours: hours.map(str::to_string),
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            dirs: dirs.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn parse_window_formats() {
+        assert_eq!(
+            parse_window("09:00-17:30"),
+            Some((time("09:00"), time("17:30")))
+        );
+        assert_eq!(
+            parse_window(" 22:00 - 06:00 "),
+            Some((time("22:00"), time("06:00")))
+        );
+        for window in ["", "09:00", "9-17", "09:00-25:00", "09:00–17:00"] {
+            assert_eq!(parse_window(window), None, "{:?}", window);
+        }
+    }
+
+    #[test]
+    fn workspace_hours_wrap_around_midnight() {
+        let cwd = Path::new("/");
+        let day = workspace(Some("09:00-17:00"), &[], &[]);
+        assert!(day.matches(time("09:00"), "", cwd));
+        assert!(!day.matches(time("17:00"), "", cwd));
+        let night = workspace(Some("22:00-06:00"), &[], &[]);
+        assert!(night.matches(time("23:30"), "", cwd));
+        assert!(night.matches(time("05:59"), "", cwd));
+        assert!(!night.matches(time("12:00"), "", cwd));
+        // A window that doesn't parse, or is empty, never matches
+        assert!(!workspace(Some("soon"), &[], &[]).matches(time("12:00"), "", cwd));
+        assert!(!workspace(Some("09:00-09:00"), &[], &[]).matches(time("09:00"), "", cwd));
+    }
+
+    #[test]
+    fn workspace_rules_all_have_to_match() {
+        let now = time("10:00");
+        let cwd = Path::new("/srv/work/api");
+        // Without rules a workspace is only picked by hand
+        assert!(!workspace(None, &[], &[]).matches(now, "laptop", cwd));
+        assert!(workspace(None, &["Laptop"], &[]).matches(now, "laptop", cwd));
+        assert!(workspace(None, &[], &["/srv/work"]).matches(now, "laptop", cwd));
+        assert!(!workspace(None, &[], &["/srv/wor"]).matches(now, "laptop", cwd));
+        let both = workspace(Some("09:00-17:00"), &["desktop"], &["/srv/work"]);
+        assert!(!both.matches(now, "laptop", cwd));
+        assert!(both.matches(now, "desktop", cwd));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::config;
+use crate::merge::{self, Conflict};
+use crate::todo::{read_pages, write_pages, TodoPage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fs, io};
+
+// One request per connection, a line of JSON answered by a line of JSON
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Request {
+    // Pages of a todo file
+    Read {
+        path: PathBuf,
+    },
+    // Pages to save to a todo file, merged with what other clients saved
+    // since `base` was read
+    Write {
+        path: PathBuf,
+        base: Option<Vec<TodoPage>>,
+        pages: Vec<TodoPage>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Response {
+    #[serde(default)]
+    pages: Vec<TodoPage>,
+    // Fields the merge of a write found changed by the writer and others
+    #[serde(default)]
+    conflicts: Vec<Conflict>,
+    error: Option<String>,
+}
+
+// A todo file as the daemon holds it, with the modification time it was
+// read or written at, so changes made without the daemon are picked up
+struct File {
+    pages: Vec<TodoPage>,
+    modified: Option<SystemTime>,
+}
+
+// How long either side waits for the other to send or take its line, so a
+// client that connects and goes quiet can't hold up everyone else
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn socket_path() -> io::Result<PathBuf> {
+    Ok(config::config_dir()?.join("daemon.sock"))
+}
+
+// Pages of a todo file read through the daemon. None when no daemon is
+// running, to read the file directly.
+pub fn read(path: &Path) -> Option<io::Result<Vec<TodoPage>>> {
+    let response = request(&Request::Read {
+        path: path.to_path_buf(),
+    })?;
+    Some(response.map(|response| response.pages))
+}
+
+// Save pages through the daemon, which merges in what other clients saved
+// since `base` was read and writes the file. The merged pages come back with
+// the conflicting fields. None when no daemon is running, to write the file
+// directly.
+pub fn write(
+    path: &Path,
+    base: Option<Vec<TodoPage>>,
+    pages: Vec<TodoPage>,
+) -> Option<io::Result<(Vec<TodoPage>, Vec<Conflict>)>> {
+    let response = request(&Request::Write {
+        path: path.to_path_buf(),
+        base,
+        pages,
+    })?;
+    Some(response.map(|response| (response.pages, response.conflicts)))
+}
+
+#[cfg(unix)]
+fn request(request: &Request) -> Option<io::Result<Response>> {
+    let socket = socket_path().ok()?;
+    // A socket file left behind by a daemon that was killed refuses too
+    let stream = std::os::unix::net::UnixStream::connect(socket).ok()?;
+    Some(exchange(stream, request))
+}
+
+#[cfg(not(unix))]
+fn request(_request: &Request) -> Option<io::Result<Response>> {
+    None
+}
+
+#[cfg(unix)]
+fn exchange(mut stream: std::os::unix::net::UnixStream, request: &Request) -> io::Result<Response> {
+    use std::io::{BufRead, BufReader, Write};
+
+    // The daemon answers others first, give it time for a few of them
+    stream.set_read_timeout(Some(TIMEOUT * 2))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+    let response: Response = serde_json::from_str(&answer)?;
+    match response.error {
+        Some(error) => Err(io::Error::other(format!("ratdo daemon: {}", error))),
+        None => Ok(response),
+    }
+}
+
+// ratdo daemon
+// Own the todo files and apply the reads and writes of the TUI and commands
+// one after the other, until killed
+#[cfg(unix)]
+pub fn run() -> io::Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let socket = socket_path()?;
+    if UnixStream::connect(&socket).is_ok() {
+        return Err(io::Error::other("ratdo daemon is already running"));
+    }
+    if socket.exists() {
+        fs::remove_file(&socket)?;
+    }
+    if let Some(parent) = socket.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(&socket)?;
+    eprintln!("ratdo daemon listening on {}", socket.display());
+    serve(listener)
+}
+
+#[cfg(not(unix))]
+pub fn run() -> io::Result<()> {
+    Err(io::Error::other(
+        "ratdo daemon needs Unix sockets, which this platform doesn't have",
+    ))
+}
+
+#[cfg(unix)]
+fn serve(listener: std::os::unix::net::UnixListener) -> io::Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let mut files: HashMap<PathBuf, File> = HashMap::new();
+    for stream in listener.incoming() {
+        // A client going away or quiet mid-request only loses its own answer
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        if stream.set_read_timeout(Some(TIMEOUT)).is_err()
+            || stream.set_write_timeout(Some(TIMEOUT)).is_err()
+        {
+            continue;
+        }
+        let mut line = String::new();
+        if BufReader::new(&stream).read_line(&mut line).is_err() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => match handle(&mut files, request) {
+                Ok(response) => response,
+                Err(err) => Response {
+                    error: Some(err.to_string()),
+                    ..Response::default()
+                },
+            },
+            Err(err) => Response {
+                error: Some(format!("bad request: {}", err)),
+                ..Response::default()
+            },
+        };
+        let mut answer = serde_json::to_string(&response)?;
+        answer.push('\n');
+        let _ = stream.write_all(answer.as_bytes());
+    }
+    Ok(())
+}
+
+fn handle(files: &mut HashMap<PathBuf, File>, request: Request) -> io::Result<Response> {
+    match request {
+        Request::Read { path } => Ok(Response {
+            pages: load(files, &path)?.pages.clone(),
+            ..Response::default()
+        }),
+        Request::Write { path, base, pages } => {
+            let file = load(files, &path)?;
+            let mut merged = pages;
+            let report = merge::merge_pages(&mut merged, file.pages.clone(), base.as_deref());
+            write_pages(&path, &merged.iter().collect::<Vec<_>>())?;
+            file.modified = modified(&path);
+            file.pages = merged.clone();
+            Ok(Response {
+                pages: merged,
+                conflicts: report.conflicts,
+                error: None,
+            })
+        }
+    }
+}
+
+// The held copy of a todo file, read again when it changed on disk
+fn load<'a>(files: &'a mut HashMap<PathBuf, File>, path: &Path) -> io::Result<&'a mut File> {
+    let on_disk = modified(path);
+    let stale = files.get(path).is_none_or(|file| file.modified != on_disk);
+    if stale {
+        let pages = read_pages(path)?;
+        files.insert(
+            path.to_path_buf(),
+            File {
+                pages,
+                modified: modified(path),
+            },
+        );
+    }
+    Ok(files.get_mut(path).expect("just loaded"))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+    use crate::todo::Todo;
+
+    fn page(descriptions: &[&str]) -> TodoPage {
+        let mut page = TodoPage::new("Default".to_string());
+        page.todos = descriptions
+            .iter()
+            .map(|d| Todo::new(d.to_string()))
+            .collect();
+        page
+    }
+
+    fn descriptions(pages: &[TodoPage]) -> Vec<String> {
+        pages[0]
+            .todos
+            .iter()
+            .map(|t| t.description.clone())
+            .collect()
+    }
+
+    fn read(files: &mut HashMap<PathBuf, File>, path: &Path) -> Vec<TodoPage> {
+        let request = Request::Read {
+            path: path.to_path_buf(),
+        };
+        handle(files, request).unwrap().pages
+    }
+
+    #[test]
+    fn read_a_missing_file() {
+        let dir = TempDir::new("daemon-missing");
+        let file = dir.join("todos.json");
+        let mut files = HashMap::new();
+        assert!(read(&mut files, &file).is_empty());
+    }
+
+    #[test]
+    fn write_merges_with_what_others_saved() {
+        let dir = TempDir::new("daemon-merge");
+        let file = dir.join("todos.json");
+        let base = vec![page(&["Read", "Call"])];
+        write_pages(&file, &base.iter().collect::<Vec<_>>()).unwrap();
+        let mut files = HashMap::new();
+        read(&mut files, &file);
+
+        // One client adds a todo, another saves its deletion of Read later
+        let mut added = base.clone();
+        added[0].todos.push(Todo::new("Shop".to_string()));
+        let mut deleted = base.clone();
+        deleted[0].todos.remove(0);
+        for pages in [added, deleted] {
+            let request = Request::Write {
+                path: file.clone(),
+                base: Some(base.clone()),
+                pages,
+            };
+            handle(&mut files, request).unwrap();
+        }
+
+        assert_eq!(descriptions(&read(&mut files, &file)), ["Call", "Shop"]);
+        let on_disk = read_pages(&file).unwrap();
+        assert_eq!(descriptions(&on_disk), ["Call", "Shop"]);
+    }
+
+    #[test]
+    fn reload_after_an_outside_write() {
+        let dir = TempDir::new("daemon-outside");
+        let file = dir.join("todos.json");
+        write_pages(&file, &[&page(&["Read"])]).unwrap();
+        let mut files = HashMap::new();
+        assert_eq!(descriptions(&read(&mut files, &file)), ["Read"]);
+
+        // Written without the daemon, a moment later
+        write_pages(&file, &[&page(&["Write"])]).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(2);
+        fs::File::options()
+            .write(true)
+            .open(&file)
+            .and_then(|f| f.set_modified(later))
+            .unwrap();
+        assert_eq!(descriptions(&read(&mut files, &file)), ["Write"]);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::merge::{fields, same_todo, UNMERGED_FIELDS};
+use crate::todo::{Todo, TodoPage};
+use serde::Serialize;
+use serde_json::Value;
+
+// Differences between two copies of a todo file, as printed by `ratdo diff`
+#[derive(Serialize, Default, Debug)]
+pub struct StoreDiff {
+    pub added_pages: Vec<String>,
+    pub removed_pages: Vec<String>,
+    pub added_todos: Vec<TodoEntry>,
+    pub removed_todos: Vec<TodoEntry>,
+    pub changed_todos: Vec<TodoChange>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TodoEntry {
+    pub page: String,
+    pub description: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TodoChange {
+    pub page: String,
+    pub description: String,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl StoreDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_pages.is_empty()
+            && self.removed_pages.is_empty()
+            && self.added_todos.is_empty()
+            && self.removed_todos.is_empty()
+            && self.changed_todos.is_empty()
+    }
+}
+
+fn todos_with_page(pages: &[TodoPage]) -> impl Iterator<Item = (&str, &Todo)> {
+    pages
+        .iter()
+        .flat_map(|p| p.todos.iter().map(move |t| (p.name.as_str(), t)))
+}
+
+fn field_changes(old: &Todo, new: &Todo) -> Vec<FieldChange> {
+    let old_fields = fields(old);
+    let mut changes = Vec::new();
+
+    for (key, new_value) in fields(new) {
+        let old_value = old_fields.get(&key).cloned().unwrap_or(Value::Null);
+        if !UNMERGED_FIELDS.contains(&key.as_str()) && old_value != new_value {
+            changes.push(FieldChange {
+                field: key,
+                old: old_value,
+                new: new_value,
+            });
+        }
+    }
+
+    changes
+}
+
+// Compare two copies of the todo file, todos are matched the same way as in merges
+pub fn diff_pages(old: &[TodoPage], new: &[TodoPage]) -> StoreDiff {
+    let mut diff = StoreDiff::default();
+
+    for page in new {
+        if !old.iter().any(|p| p.name == page.name) {
+            diff.added_pages.push(page.name.clone());
+        }
+    }
+    for page in old {
+        if !new.iter().any(|p| p.name == page.name) {
+            diff.removed_pages.push(page.name.clone());
+        }
+    }
+
+    for (page, todo) in todos_with_page(new) {
+        match todos_with_page(old).find(|(_, t)| same_todo(t, todo)) {
+            Some((old_page, old_todo)) => {
+                let mut changes = field_changes(old_todo, todo);
+                if old_page != page {
+                    changes.push(FieldChange {
+                        field: "page".to_string(),
+                        old: Value::from(old_page),
+                        new: Value::from(page),
+                    });
+                }
+                if !changes.is_empty() {
+                    diff.changed_todos.push(TodoChange {
+                        page: page.to_string(),
+                        description: todo.description.clone(),
+                        fields: changes,
+                    });
+                }
+            }
+            None => diff.added_todos.push(TodoEntry {
+                page: page.to_string(),
+                description: todo.description.clone(),
+            }),
+        }
+    }
+
+    for (page, todo) in todos_with_page(old) {
+        if !todos_with_page(new).any(|(_, t)| same_todo(t, todo)) {
+            diff.removed_todos.push(TodoEntry {
+                page: page.to_string(),
+                description: todo.description.clone(),
+            });
+        }
+    }
+
+    diff
+}
+
+// Human readable listing, one line per added or removed item and an
+// indented line per changed field
+pub fn format_diff(diff: &StoreDiff) -> String {
+    let mut lines = Vec::new();
+
+    for name in &diff.added_pages {
+        lines.push(format!("+ page {}", name));
+    }
+    for name in &diff.removed_pages {
+        lines.push(format!("- page {}", name));
+    }
+    for entry in &diff.added_todos {
+        lines.push(format!("+ [{}] {}", entry.page, entry.description));
+    }
+    for entry in &diff.removed_todos {
+        lines.push(format!("- [{}] {}", entry.page, entry.description));
+    }
+    for change in &diff.changed_todos {
+        lines.push(format!("~ [{}] {}", change.page, change.description));
+        for field in &change.fields {
+            lines.push(format!(
+                "    {}: {} -> {}",
+                field.field, field.old, field.new
+            ));
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(name: &str, todos: &[&Todo]) -> TodoPage {
+        let mut page = TodoPage::new(name.to_string());
+        page.todos = todos.iter().map(|&todo| todo.clone()).collect();
+        page
+    }
+
+    #[test]
+    fn empty_copies_have_no_differences() {
+        assert!(diff_pages(&[], &[]).is_empty());
+        let todo = Todo::new("Write report".to_string());
+        let pages = [page("Work", &[&todo])];
+        assert!(diff_pages(&pages, &pages).is_empty());
+    }
+
+    #[test]
+    fn pages_and_todos_added_and_removed() {
+        let report = Todo::new("Write report".to_string());
+        let milk = Todo::new("Buy milk".to_string());
+        let old = [page("Work", &[&report]), page("Home", &[])];
+        let new = [page("Work", &[]), page("Shopping", &[&milk])];
+
+        let diff = diff_pages(&old, &new);
+        assert_eq!(diff.added_pages, ["Shopping"]);
+        assert_eq!(diff.removed_pages, ["Home"]);
+        assert_eq!(diff.added_todos[0].description, "Buy milk");
+        assert_eq!(diff.removed_todos[0].page, "Work");
+        assert!(diff.changed_todos.is_empty());
+    }
+
+    #[test]
+    fn moves_and_edits_are_changes() {
+        let report = Todo::new("Write report".to_string());
+        let mut edited = report.clone();
+        edited.completed = true;
+        edited.touch();
+        let old = [page("Work", &[&report]), page("Done", &[])];
+        let new = [page("Work", &[]), page("Done", &[&edited])];
+
+        let diff = diff_pages(&old, &new);
+        assert!(diff.added_todos.is_empty() && diff.removed_todos.is_empty());
+        let fields: Vec<&str> = diff.changed_todos[0]
+            .fields
+            .iter()
+            .map(|change| change.field.as_str())
+            .collect();
+        // The edit time alone isn't a change
+        assert_eq!(fields, ["completed", "page"]);
+        assert_eq!(diff.changed_todos[0].fields[1].old, "Work");
+    }
+}
@@ -0,0 +1,86 @@
+use crate::todo::{DueStatus, Priority, Todo, TodoPage};
+use chrono::{Local, NaiveDate};
+
+// A single condition of a filter expression
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+    // due:today, due:overdue, due:upcoming
+    Due(DueStatus),
+    // due:any / due:none
+    HasDue(bool),
+    // is:done / is:open
+    Completed(bool),
+    // is:snoozed
+    Deferred,
+    // is:review
+    NeedsReview,
+    // page:<name>
+    Page(String),
+    // +tag
+    Tag(String),
+    // @name
+    Assignee(String),
+    // !high, !med, !low
+    Priority(Priority),
+    // Any other word has to appear in the description
+    Text(String),
+}
+
+// Space separated list of terms that all have to match, e.g. "due:today is:open"
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+    terms: Vec<Term>,
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let mut terms = Vec::new();
+
+        for word in expression.split_whitespace() {
+            let term = match word.split_once(':') {
+                Some(("due", "today")) => Term::Due(DueStatus::Today),
+                Some(("due", "overdue")) => Term::Due(DueStatus::Overdue),
+                Some(("due", "upcoming")) => Term::Due(DueStatus::Upcoming),
+                Some(("due", "any")) => Term::HasDue(true),
+                Some(("due", "none")) => Term::HasDue(false),
+                Some(("due", value)) => return Err(format!("unknown due filter '{}'", value)),
+                Some(("is", "done")) => Term::Completed(true),
+                Some(("is", "open")) => Term::Completed(false),
+                Some(("is", "snoozed")) => Term::Deferred,
+                Some(("is", "review")) => Term::NeedsReview,
+                Some(("is", value)) => return Err(format!("unknown is filter '{}'", value)),
+                Some(("page", name)) => Term::Page(name.to_string()),
+                _ if word.starts_with('!') => match Priority::parse(word) {
+                    Some(priority) => Term::Priority(priority),
+                    None => return Err(format!("unknown priority '{}'", word)),
+                },
+                _ => match (word.strip_prefix('+'), word.strip_prefix('@')) {
+                    (Some(tag), _) if !tag.is_empty() => Term::Tag(tag.to_lowercase()),
+                    (_, Some(name)) if !name.is_empty() => Term::Assignee(name.to_string()),
+                    _ => Term::Text(word.to_lowercase()),
+                },
+            };
+            terms.push(term);
+        }
+
+        Ok(Self { terms })
+    }
+
+    pub fn matches(&self, page: &TodoPage, todo: &Todo, today: NaiveDate) -> bool {
+        self.terms.iter().all(|term| match term {
+            Term::Due(status) => todo.due_status(today) == Some(*status),
+            Term::HasDue(has_due) => todo.due_date.is_some() == *has_due,
+            Term::Completed(completed) => todo.completed == *completed,
+            Term::Deferred => todo.is_deferred(Local::now()),
+            Term::NeedsReview => todo.needs_review,
+            Term::Page(name) => page.name.eq_ignore_ascii_case(name),
+            Term::Tag(tag) => todo.tags.contains(tag),
+            Term::Assignee(name) => todo
+                .assignee
+                .as_ref()
+                .is_some_and(|assignee| assignee.eq_ignore_ascii_case(name)),
+            Term::Priority(priority) => todo.priority == Some(*priority),
+            Term::Text(text) => todo.description.to_lowercase().contains(text),
+        })
+    }
+}
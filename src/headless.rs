@@ -0,0 +1,1235 @@
+// Headless driver for end-to-end tests: scripted key presses go through the
+// same handler as the terminal, against an app that never reads or writes
+// the todo store, and the screen is drawn to a TestBackend
+use crate::storage::Memory;
+use crate::todo::{App, PageType};
+use crate::{dashboard_ui, handle_idle, handle_key, handle_mouse, handle_paste, ui};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use ratatui::{backend::TestBackend, Terminal};
+
+pub struct Harness {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+    // Set once a key asked to quit, later keys are ignored
+    pub quit: bool,
+}
+
+impl Harness {
+    // An ephemeral app with only the default page
+    pub fn new() -> Self {
+        Self {
+            app: App {
+                storage: Box::new(Memory::default()),
+                ..App::new()
+            },
+            terminal: Terminal::new(TestBackend::new(80, 24)).unwrap(),
+            quit: false,
+        }
+    }
+
+    pub fn with_pages(names: &[&str]) -> Self {
+        let mut harness = Self::new();
+        for name in names {
+            harness.app.add_page(name.to_string(), PageType::default());
+        }
+        harness.app.select_page(0);
+        harness
+    }
+
+    pub fn press(&mut self, code: KeyCode) -> &mut Self {
+        self.press_with(code, KeyModifiers::NONE)
+    }
+
+    pub fn press_with(&mut self, code: KeyCode, modifiers: KeyModifiers) -> &mut Self {
+        if !self.quit {
+            self.quit = handle_key(&mut self.app, KeyEvent::new(code, modifiers));
+            self.app.debug_check();
+            self.draw();
+        }
+        self
+    }
+
+    // A mouse event at a cell of the 80x24 screen
+    pub fn mouse(&mut self, kind: MouseEventKind, column: u16, row: u16) -> &mut Self {
+        handle_mouse(
+            &mut self.app,
+            MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            },
+        );
+        self.app.debug_check();
+        self.draw();
+        self
+    }
+
+    // A second without key presses, as after the first key of a chord
+    pub fn idle(&mut self) -> &mut Self {
+        self.quit = handle_idle(&mut self.app);
+        self.app.debug_check();
+        self.draw();
+        self
+    }
+
+    // Text pasted into the terminal
+    pub fn paste(&mut self, text: &str) -> &mut Self {
+        handle_paste(&mut self.app, text);
+        self.app.debug_check();
+        self.draw();
+        self
+    }
+
+    // Press the keys of a script: plain characters are typed as they are,
+    // named keys are written in angle brackets, e.g. "aMilk<Enter>"
+    pub fn keys(&mut self, script: &str) -> &mut Self {
+        let mut chars = script.chars();
+        while let Some(c) = chars.next() {
+            if c != '<' {
+                self.press(KeyCode::Char(c));
+                continue;
+            }
+
+            let name: String = chars.by_ref().take_while(|&c| c != '>').collect();
+            let code = match name.as_str() {
+                "Enter" => KeyCode::Enter,
+                "Esc" => KeyCode::Esc,
+                "Tab" => KeyCode::Tab,
+                "BackTab" => KeyCode::BackTab,
+                "Backspace" => KeyCode::Backspace,
+                "Up" => KeyCode::Up,
+                "Down" => KeyCode::Down,
+                "Space" => KeyCode::Char(' '),
+                "lt" => KeyCode::Char('<'),
+                _ => panic!("unknown key <{}>", name),
+            };
+            self.press(code);
+        }
+        self
+    }
+
+    fn draw(&mut self) {
+        self.terminal.draw(|f| ui(f, &mut self.app)).unwrap();
+    }
+
+    // The last drawn screen, one line per row
+    pub fn screen(&mut self) -> String {
+        self.draw();
+        self.drawn()
+    }
+
+    // The screen of `ratdo dashboard` for the todos of the app
+    pub fn dashboard(&mut self) -> String {
+        let updated = chrono::Local::now();
+        self.terminal
+            .draw(|f| dashboard_ui(f, &mut self.app, updated))
+            .unwrap();
+        self.drawn()
+    }
+
+    fn drawn(&self) -> String {
+        let buffer = self.terminal.backend().buffer();
+        let mut screen = String::new();
+        for y in 0..buffer.area.height {
+            for x in 0..buffer.area.width {
+                screen.push_str(buffer[(x, y)].symbol());
+            }
+            screen.push('\n');
+        }
+        screen
+    }
+
+    // Descriptions of the todos on the page with this name, in stored order
+    pub fn descriptions(&self, page: &str) -> Vec<String> {
+        let page = self.app.pages.iter().find(|p| p.name == page).unwrap();
+        page.todos.iter().map(|t| t.description.clone()).collect()
+    }
+
+    pub fn current_page(&self) -> &str {
+        &self.app.current_page().name
+    }
+}
+
+mod tests {
+    use super::*;
+    use crate::todo::InputMode;
+
+    #[test]
+    fn add_todos() {
+        let mut h = Harness::new();
+        h.keys("aBuy milk<Enter>aWalk dog +home<Enter>");
+
+        assert_eq!(h.descriptions("Default"), ["Buy milk", "Walk dog"]);
+        assert_eq!(h.app.todos()[1].tags, ["home"]);
+        let screen = h.screen();
+        assert!(screen.contains("[ ] Buy milk"));
+        assert!(screen.contains("[ ] Walk dog"));
+    }
+
+    #[test]
+    fn escape_cancels_adding() {
+        let mut h = Harness::new();
+        h.keys("aNever mind<Esc>");
+
+        assert!(h.descriptions("Default").is_empty());
+        assert!(matches!(h.app.input_mode, InputMode::Normal));
+        assert!(!h.screen().contains("Add Todo"));
+    }
+
+    #[test]
+    fn empty_description_is_rejected() {
+        let mut h = Harness::new();
+        h.keys("a   <Enter>");
+
+        assert!(h.descriptions("Default").is_empty());
+        assert!(h.screen().contains("Todo description cannot be empty"));
+    }
+
+    #[test]
+    fn move_todo_down() {
+        let mut h = Harness::new();
+        h.keys("aFirst<Enter>aSecond<Enter>aThird<Enter>");
+        h.keys("kkpjp");
+
+        assert_eq!(h.descriptions("Default"), ["Second", "First", "Third"]);
+    }
+
+    #[test]
+    fn toggle_and_delete() {
+        let mut h = Harness::new();
+        h.keys("aOne<Enter>aTwo<Enter>k<Space>");
+
+        assert!(h.app.todos()[0].completed);
+        assert!(h.screen().contains("[x] One"));
+
+        h.keys("d");
+        assert_eq!(h.descriptions("Default"), ["Two"]);
+        h.keys("d");
+        assert!(h.descriptions("Default").is_empty());
+        assert_eq!(h.app.state.selected(), None);
+    }
+
+    #[test]
+    fn page_switch_keeps_todos_apart() {
+        let mut h = Harness::with_pages(&["Work", "Home"]);
+        h.keys("aReport<Enter><Tab>aSlides<Enter><Tab>aDishes<Enter>");
+
+        assert_eq!(h.descriptions("Default"), ["Report"]);
+        assert_eq!(h.descriptions("Work"), ["Slides"]);
+        assert_eq!(h.descriptions("Home"), ["Dishes"]);
+
+        h.keys("<BackTab>");
+        assert_eq!(h.current_page(), "Work");
+        assert!(h.screen().contains("Default 1 │ Work 1 │ Home 1"));
+        assert!(h.screen().contains("(ephemeral) ■■■■■■■■■■ 0/1"));
+    }
+
+    #[test]
+    fn quick_add_tracks_quantities_as_the_target_page() {
+        let mut h = Harness::with_pages(&["Groceries"]);
+        h.app.pages[1].settings.quantities = true;
+        h.keys("aMilk 2 /Groceries<Enter>");
+        assert_eq!(h.app.pages[1].todos[0].description, "Milk");
+        assert_eq!(h.app.pages[1].todos[0].quantity, Some(2));
+
+        h.keys("<Tab>aRoom 101 /Default<Enter>");
+        assert_eq!(h.descriptions("Default"), ["Room 101"]);
+        assert_eq!(h.app.pages[0].todos[0].quantity, None);
+    }
+
+    #[test]
+    fn add_move_page_switch_delete() {
+        let mut h = Harness::with_pages(&["Work"]);
+        h.keys("aA<Enter>aB<Enter>aC /Work<Enter>");
+        assert_eq!(h.descriptions("Default"), ["A", "B"]);
+        assert_eq!(h.descriptions("Work"), ["C"]);
+
+        // Slash words that aren't pages don't create any
+        h.keys("aFix /etc/hosts<Enter>aD /Wrok<Enter>");
+        assert_eq!(h.app.pages.len(), 2);
+        assert_eq!(
+            h.descriptions("Default"),
+            ["A", "B", "Fix /etc/hosts", "D /Wrok"]
+        );
+        h.keys("dd");
+
+        h.keys("kpjp");
+        assert_eq!(h.descriptions("Default"), ["B", "A"]);
+
+        h.keys("<Tab>d<BackTab>");
+        assert!(h.descriptions("Work").is_empty());
+        assert_eq!(h.current_page(), "Default");
+    }
+
+    #[test]
+    fn delete_page_from_selector() {
+        let mut h = Harness::with_pages(&["Work", "Home"]);
+        h.keys("<Tab><Tab>aDishes<Enter>");
+        h.keys("Pkd");
+        assert!(h.screen().contains("Delete page Work and its 0 todos?"));
+        h.keys("n");
+        assert_eq!(h.app.pages.len(), 3);
+        assert!(matches!(h.app.input_mode, InputMode::PageSelect));
+        h.keys("dy");
+
+        let names: Vec<&str> = h.app.pages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Default", "Home"]);
+        h.keys("<Enter>");
+        assert_eq!(h.current_page(), "Home");
+        assert_eq!(h.descriptions("Home"), ["Dishes"]);
+
+        // Todos only ask when configured to
+        h.app.config = toml::from_str("confirm_delete = \"all\"").unwrap();
+        h.keys("d");
+        assert!(h.screen().contains("Delete \"Dishes\"?"));
+        h.keys("y");
+        assert!(h.descriptions("Home").is_empty());
+    }
+
+    #[test]
+    fn page_selector_scrolls_long_lists() {
+        let names: Vec<String> = (1..=30).map(|i| format!("Page {}", i)).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut h = Harness::with_pages(&names);
+        h.keys("aOne<Enter>aTwo<Enter> P");
+        assert!(h.screen().contains("● Default 1/2"));
+
+        // The popup stays on the screen and follows the selection down
+        h.keys(&"j".repeat(25));
+        let screen = h.screen();
+        assert!(screen.contains("> ● Page 25 0/0"));
+        assert!(!screen.contains("● Default"));
+
+        h.press(KeyCode::End);
+        assert_eq!(h.app.page_select_state.selected(), Some(30));
+        h.press(KeyCode::Home).press(KeyCode::PageDown);
+        assert_eq!(h.app.page_select_state.selected(), Some(10));
+    }
+
+    #[test]
+    fn search_narrows_the_list() {
+        let mut h = Harness::new();
+        h.keys("aBuy milk<Enter>aCall mom<Enter>aBuy bread<Enter>");
+        h.keys("/buy");
+
+        let screen = h.screen();
+        assert!(screen.contains("Buy milk"));
+        assert!(screen.contains("Buy bread"));
+        assert!(!screen.contains("Call mom"));
+        assert!(screen.contains("/buy"));
+        assert!(screen.contains("Todos, Showing 2 of 3"));
+
+        h.keys("<Enter>n<Space>");
+        assert!(h.app.todos()[2].completed);
+        h.keys("N<Space>");
+        assert!(h.app.todos()[0].completed);
+
+        h.keys("<Esc>");
+        assert!(h.app.search.is_none());
+        assert!(h.screen().contains("Call mom"));
+        assert_eq!(h.app.selected_index(), Some(0));
+
+        h.keys("/tea");
+        assert!(h
+            .screen()
+            .contains("No todos match 'tea', press Esc to clear"));
+    }
+
+    #[test]
+    fn archive_and_restore() {
+        let mut h = Harness::new();
+        h.keys("aOne<Enter>aTwo<Enter>aThree<Enter>");
+        h.keys("<Space>kk<Space>X");
+        assert_eq!(h.descriptions("Default"), ["Two"]);
+
+        h.keys("x");
+        assert!(h.descriptions("Default").is_empty());
+        assert!(h.screen().contains("Archived Two"));
+
+        // Most recently archived first
+        h.keys("H");
+        assert!(h.screen().contains("Archive: Default"));
+        h.keys("jj<Enter>dd");
+        assert_eq!(h.descriptions("Default"), ["One"]);
+        assert!(matches!(h.app.input_mode, InputMode::Normal));
+        assert!(h.app.current_page().archived.is_empty());
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut h = Harness::with_pages(&["Work"]);
+        h.keys("aOne<Enter>aTwo<Enter>k<Space>dpj");
+        assert_eq!(h.descriptions("Default"), ["Two"]);
+
+        // Delete, then toggle
+        h.keys("pu");
+        assert_eq!(h.descriptions("Default"), ["One", "Two"]);
+        h.keys("u");
+        assert!(!h.app.todos()[0].completed);
+        assert!(h.screen().contains("Undid Toggle"));
+
+        h.press_with(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert!(h.app.todos()[0].completed);
+
+        // Undoing a page deletion brings its todos back
+        h.keys("<Tab>aReport<Enter>Pjd<Esc>u");
+        assert_eq!(h.descriptions("Work"), ["Report"]);
+
+        // A new change drops the redo steps
+        h.keys("uaThree<Enter>");
+        h.press_with(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert!(h.screen().contains("Nothing to redo"));
+    }
+
+    #[test]
+    fn help_overlay() {
+        let mut h = Harness::new();
+        h.keys("?");
+        assert!(h.screen().contains("Key Bindings"));
+        assert!(h.screen().contains("Ctrl+r"));
+
+        // q closes the overlay instead of quitting
+        h.keys("jjq");
+        assert!(!h.quit);
+        assert!(!h.screen().contains("Key Bindings"));
+        assert!(h.screen().contains("?: Help"));
+    }
+
+    #[test]
+    fn fuzzy_page_switcher() {
+        let mut h = Harness::with_pages(&["Shopping list", "Work", "Home office"]);
+        h.keys("'hof<Enter>");
+        assert_eq!(h.current_page(), "Home office");
+
+        // Down picks the next match, Esc leaves the page as it was
+        h.keys("'o<Down><Enter>");
+        assert_eq!(h.current_page(), "Work");
+        h.keys("'shop<Esc>");
+        assert_eq!(h.current_page(), "Work");
+    }
+
+    #[test]
+    fn edit_at_the_cursor() {
+        let mut h = Harness::new();
+        h.keys("aBuy milk");
+        h.press(KeyCode::Home);
+        h.keys("Now ");
+        h.press(KeyCode::End)
+            .press(KeyCode::Left)
+            .press(KeyCode::Delete);
+        h.press_with(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        h.keys("bread<Enter>");
+        assert_eq!(h.descriptions("Default"), ["Now Buy bread"]);
+
+        // Editing starts at the end again, Ctrl+u clears
+        h.keys("e");
+        h.press_with(KeyCode::Char('u'), KeyModifiers::CONTROL);
+        h.keys("Eggs<Enter>");
+        assert_eq!(h.descriptions("Default"), ["Eggs"]);
+    }
+
+    #[test]
+    fn help_follows_the_keymap() {
+        let mut h = Harness::new();
+        h.app.config.keys = crate::keymap::Keymap::preset("arrows").unwrap();
+        h.keys("aPay rent<Enter>");
+        assert!(h.screen().contains("Up/Down: Select | Enter: Done"));
+
+        // Popups show their section of the overlay
+        h.press(KeyCode::Char('t'));
+        assert!(h
+            .screen()
+            .contains("Enter: Save, empty clears the date | Esc: Cancel"));
+        h.keys("<Esc>?");
+        let screen = h.screen();
+        assert!(screen.contains("Todos"));
+        assert!(screen.contains("Enter, Space  Toggle done"));
+    }
+
+    #[test]
+    fn counted_and_edge_moves() {
+        let mut h = Harness::new();
+        h.keys("aOne<Enter>aTwo<Enter>aThree<Enter>aFour<Enter>aFive<Enter>");
+        h.keys("gg");
+        assert!(h.screen().contains(">  [ ] One"));
+        h.keys("3j");
+        assert!(h.screen().contains(">  [ ] Four"));
+        // Counts stop at the ends instead of wrapping
+        h.keys("9j");
+        assert!(h.screen().contains(">  [ ] Five"));
+        h.keys("2G");
+        assert!(h.screen().contains(">  [ ] Two"));
+
+        // In move mode the todo moves along
+        h.keys("p2jp");
+        assert_eq!(
+            h.descriptions("Default"),
+            ["One", "Three", "Four", "Two", "Five"]
+        );
+        h.keys("G");
+        assert!(h.screen().contains(">  [ ] Five"));
+
+        // Past the todos ticked off at the top
+        h.keys("gg<Space>j<Space>Ggo");
+        assert!(h.screen().contains(">  [ ] Four"));
+    }
+
+    #[test]
+    fn group_by_due_date() {
+        let mut h = Harness::new();
+        h.keys("aRead<Enter>aPay rent due:today<Enter>aCall mum due:yesterday<Enter>");
+        h.keys("bb");
+        assert_eq!(h.app.visible_indices(), [2, 1, 0]);
+        assert!(h.screen().contains("▾ Overdue 1"));
+
+        // Collapse the group of the selected todo, the header stays selectable
+        h.keys("za");
+        assert!(h.screen().contains(">  ▸ Overdue 1"));
+        assert_eq!(h.app.visible_indices(), [1, 0]);
+        h.keys("j<Space>k<Space>");
+        assert!(h.screen().contains(">  [ ] Call mum"));
+        assert!(h.app.todos()[1].completed);
+
+        // A z that doesn't start the chord still snoozes
+        h.keys("z2d<Enter>");
+        assert!(h.app.todos()[2].deferred_until.is_some());
+    }
+
+    #[test]
+    fn group_by_priority_and_tag() {
+        let mut h = Harness::new();
+        h.keys("aRead +home<Enter>aShip !high +work<Enter>aCall<Enter>");
+        h.keys("bbb");
+        assert!(h.screen().contains("▾ High Priority 1"));
+        assert!(h.screen().contains("▾ No Priority 2"));
+        assert_eq!(h.app.visible_indices(), [1, 0, 2]);
+
+        h.keys("b");
+        assert!(h.screen().contains("▾ +home 1"));
+        assert!(h.screen().contains("▾ No Tags 1"));
+        assert_eq!(h.app.visible_indices(), [0, 1, 2]);
+        // Collapsing +work passes the selection on to the header
+        h.keys("ggjza");
+        assert!(h.screen().contains(">  ▸ +work 1"));
+        assert_eq!(h.app.visible_indices(), [0, 2]);
+    }
+
+    #[test]
+    fn done_section_at_the_bottom() {
+        let mut h = Harness::new();
+        h.keys("aOne<Enter>aTwo<Enter>aThree<Enter>zdgg<Space>");
+        assert_eq!(h.app.visible_indices(), [1, 2, 0]);
+        assert!(h.screen().contains("▾ Done 1"));
+        // The next todo takes the row of the completed one
+        assert!(h.screen().contains(">  [ ] Two"));
+        h.keys("<Space>");
+        assert_eq!(h.app.visible_indices(), [2, 0, 1]);
+
+        // Moving stays within the open todos
+        h.keys("pjp");
+        assert_eq!(h.app.visible_indices(), [2, 0, 1]);
+
+        // Collapsed on its header, remembered with the page
+        h.keys("Gza");
+        assert!(h.screen().contains(">  ▸ Done 2"));
+        assert_eq!(h.app.visible_indices(), [2]);
+        assert!(h.app.current_page().settings.done_section);
+    }
+
+    #[test]
+    fn number_keys_switch_pages() {
+        let mut h = Harness::with_pages(&["Work", "Home", "Books"]);
+        h.keys("3").idle();
+        assert_eq!(h.current_page(), "Home");
+        h.keys("0").idle();
+        assert_eq!(h.current_page(), "Books");
+        // Anything but a move goes to the page first, then acts there
+        h.keys("2aCall Bob<Enter>");
+        assert_eq!(h.descriptions("Work"), ["Call Bob"]);
+        h.keys("9").idle();
+        assert!(h.screen().contains("No page 9"));
+        // Followed by a move it counts
+        h.keys("aTwo<Enter>gg2j");
+        assert_eq!(h.current_page(), "Work");
+    }
+
+    #[test]
+    fn day_summary_once_a_day() {
+        let mut h = Harness::new();
+        h.keys("aPay rent due:today<Enter>aCall mum due:yesterday<Enter>aRead<Enter><Space>");
+        let yesterday = chrono::Local::now() - chrono::Duration::days(1);
+        h.app.todos_mut()[2].completed_at = Some(yesterday);
+
+        // Off unless configured
+        let today = h.app.today;
+        h.app.summarize_new_day(today);
+        assert!(matches!(h.app.input_mode, InputMode::Normal));
+        h.app.config.day_summary = true;
+        h.app.summarize_new_day(today);
+        let screen = h.screen();
+        assert!(screen.contains("Done yesterday 1"));
+        assert!(screen.contains("✓ Read"));
+        assert!(screen.contains("Due today 1"));
+        assert!(screen.contains("Overdue 1"));
+        assert!(screen.contains("Call mum  1d late"));
+
+        // Only the first start of the day, B opens it again
+        h.keys("<Esc>");
+        h.app.summarize_new_day(today);
+        assert!(matches!(h.app.input_mode, InputMode::Normal));
+        h.keys("B");
+        assert!(h.screen().contains("Due today 1"));
+    }
+
+    #[test]
+    fn status_line_marks_unsaved_changes() {
+        let mut h = Harness::new();
+        assert!(h.screen().contains("ephemeral, not saved"));
+        // Esc with nothing to clear changes nothing
+        h.keys("<Esc>");
+        assert!(!h.screen().contains("●"));
+        h.keys("aPay rent +home<Enter>");
+        assert!(h.screen().contains("● ephemeral, not saved"));
+        h.press_with(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert!(h.screen().contains("Ephemeral sessions aren't saved"));
+
+        // Nor does filtering by a tag, which isn't saved
+        h.app.dirty = false;
+        h.keys("f<Enter>");
+        assert_eq!(h.app.current_page().tag_filter.as_deref(), Some("home"));
+        h.keys("<Esc>");
+        assert!(h.app.current_page().tag_filter.is_none());
+        assert!(!h.app.dirty);
+    }
+
+    #[test]
+    fn detailed_rows_show_details_below() {
+        let mut h = Harness::new();
+        h.keys("aPay rent +home<Enter>");
+        assert!(h.screen().contains("[ ] Pay rent +home"));
+        h.keys("zv");
+        let screen = h.screen();
+        assert!(screen.contains("Detailed rows"));
+        let lines: Vec<&str> = screen.lines().collect();
+        let row = lines.iter().position(|l| l.contains("Pay rent")).unwrap();
+        assert!(!lines[row].contains("+home"));
+        assert!(lines[row + 1].contains("+home  added just now"));
+        h.keys("zv");
+        assert!(h.screen().contains("[ ] Pay rent +home"));
+    }
+
+    #[test]
+    fn conflicts_pick_a_side() {
+        let mut h = Harness::new();
+        h.keys("aPay rent<Enter>");
+        let id = h.app.todos()[0].id;
+        let conflict = |field: &str, theirs: &str| crate::merge::Conflict {
+            id,
+            description: "Pay rent".to_string(),
+            field: field.to_string(),
+            mine: serde_json::json!("Pay rent"),
+            theirs: serde_json::json!(theirs),
+            kept_mine: true,
+        };
+        h.app.conflicts = vec![
+            conflict("description", "Pay the rent"),
+            conflict("notes", "x"),
+        ];
+        h.app.conflict_select_state.select(Some(0));
+        h.app.input_mode = InputMode::Conflicts;
+        assert!(h.screen().contains("2 Conflicts"));
+        assert!(h.screen().contains("theirs Pay the rent"));
+        h.keys("t");
+        assert_eq!(h.descriptions("Default"), ["Pay the rent"]);
+        h.keys("m");
+        assert!(matches!(h.app.input_mode, InputMode::Normal));
+        assert!(h.app.todos()[0].notes.is_empty());
+    }
+
+    #[test]
+    fn focus_on_one_todo_at_a_time() {
+        let mut h = Harness::new();
+        h.keys("aFirst<Enter>aSecond +home<Enter>aThird<Enter>ggF");
+        let screen = h.screen();
+        assert!(screen.contains("Focus, 3 Open"));
+        assert!(screen.contains("First"));
+        assert!(!screen.contains("Second"));
+        h.keys("j");
+        assert!(h.screen().contains("Second"));
+        assert!(h.screen().contains("+home"));
+        h.keys(" ");
+        assert!(h.screen().contains("Focus, 2 Open"));
+        assert!(h.screen().contains("Third"));
+        h.keys("  ");
+        assert!(h.screen().contains("Everything here is done"));
+        assert!(h.screen().contains("[x] Second"));
+
+        // A required completion note is asked for before moving on
+        h.app.config = toml::from_str("completion_note_tags = [\"home\"]").unwrap();
+        h.keys("FaFourth +home<Enter>aFifth<Enter>kF ");
+        assert!(matches!(h.app.input_mode, InputMode::CompletionNote));
+        h.keys("watered<Enter>");
+        assert!(h.screen().contains("Focus, 1 Open"));
+        assert!(h.screen().contains("Fifth"));
+    }
+
+    #[test]
+    fn undo_keeps_later_edits_apart() {
+        let mut h = Harness::new();
+        h.keys("aCall mum<Enter>Eask about Sunday");
+        h.press_with(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.todos()[0].notes, "ask about Sunday");
+        h.keys("u");
+        assert!(h.screen().contains("Undid Notes"));
+        assert_eq!(h.descriptions("Default"), ["Call mum"]);
+        assert!(h.app.todos()[0].notes.is_empty());
+        h.keys("u");
+        assert!(h.app.todos().is_empty());
+        h.press_with(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        h.press_with(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.todos()[0].notes, "ask about Sunday");
+    }
+
+    #[test]
+    fn undo_in_the_agenda() {
+        let mut h = Harness::new();
+        h.keys("aPay rent due:today<Enter>A<Space>");
+        assert!(h.app.todos()[0].completed);
+        h.keys("u");
+        assert!(h.screen().contains("Undid Complete"));
+        assert!(!h.app.todos()[0].completed);
+        h.press_with(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert!(h.app.todos()[0].completed);
+    }
+
+    #[test]
+    fn toasts_confirm_actions() {
+        let mut h = Harness::new();
+        h.keys("aBuy milk<Enter>d");
+        assert!(h.screen().contains("Deleted 'Buy milk' — press u to undo"));
+        h.keys("u");
+        assert!(h.screen().contains("Undid Delete"));
+        assert!(!h.screen().contains("Deleted 'Buy milk'"));
+    }
+
+    #[test]
+    fn import_pasted_list() {
+        let mut h = Harness::new();
+        h.keys("aFirst<Enter>");
+        h.paste("Agenda:\n- [ ] Book room +work\n\n2) Send invites due:tomorrow\n* Order lunch");
+        assert!(h.screen().contains("Import 4 Todos"));
+        // Leave the heading out
+        h.keys("d<Enter>");
+        assert_eq!(
+            h.descriptions("Default"),
+            ["First", "Book room", "Send invites", "Order lunch"]
+        );
+        assert_eq!(h.app.todos()[1].tags, ["work"]);
+        assert!(h.app.todos()[2].due_date.is_some());
+        assert!(h.screen().contains(">  [ ] Book room"));
+    }
+
+    #[test]
+    fn dashboard_counts_and_agenda() {
+        let mut h = Harness::new();
+        h.keys("aPay rent due:today<Enter>aCall mum due:yesterday<Enter>aRead<Enter>");
+        let screen = h.dashboard();
+        assert!(screen.contains("1 overdue  1 today  3 open  0 done today"));
+        assert!(screen.contains("Pay rent"));
+        assert!(!screen.contains("Read"));
+    }
+
+    #[test]
+    fn paste_into_the_popup() {
+        let mut h = Harness::new();
+        h.keys("aBuy ");
+        h.paste("oat\r\nmilk");
+        h.keys("<Enter>");
+        assert_eq!(h.descriptions("Default"), ["Buy oat milk"]);
+
+        // Nothing happens outside of popups
+        h.paste("x");
+        assert_eq!(h.descriptions("Default"), ["Buy oat milk"]);
+    }
+
+    #[test]
+    fn jump_to_todo() {
+        let mut h = Harness::new();
+        h.keys("aBuy milk<Enter>aCall the bank<Enter>aClean the kitchen<Enter>");
+        h.press_with(KeyCode::Char('j'), KeyModifiers::CONTROL);
+        h.keys("ck");
+        assert!(h.screen().contains("> Clean the kitchen"));
+
+        // The list is left as it was, only the selection moves
+        h.keys("<Down><Enter>");
+        assert_eq!(h.app.selected_index(), Some(1));
+        assert_eq!(h.descriptions("Default").len(), 3);
+    }
+
+    #[test]
+    fn recent_page_cycling() {
+        let mut h = Harness::with_pages(&["Work", "Home"]);
+        h.keys("<Tab><Tab>`");
+        assert_eq!(h.current_page(), "Work");
+
+        // Pressing again goes further back
+        h.keys("`");
+        assert_eq!(h.current_page(), "Default");
+
+        // Another key ends the cycle, the page reached is now the most recent
+        h.keys("j`");
+        assert_eq!(h.current_page(), "Home");
+        h.keys("j`");
+        assert_eq!(h.current_page(), "Default");
+    }
+
+    #[test]
+    fn carry_over_to_journal_page() {
+        let mut h = Harness::with_pages(&["2025-03-02", "2025-03-03"]);
+        h.keys("<Tab>aOld<Enter><Tab>aDone<Enter>aOpen<Enter>k<Space>");
+        h.app
+            .open_journal(chrono::NaiveDate::from_ymd_opt(2025, 3, 4).unwrap());
+        assert_eq!(h.current_page(), "2025-03-04");
+
+        // Only the latest earlier page is carried over
+        h.keys("Y");
+        assert_eq!(h.descriptions("2025-03-04"), ["Open"]);
+        assert_eq!(h.descriptions("2025-03-03"), ["Done"]);
+        assert_eq!(h.descriptions("2025-03-02"), ["Old"]);
+        assert!(h.screen().contains("Carried over 1 todo from 2025-03-03"));
+    }
+
+    #[test]
+    fn carry_over_rule_on_new_day() {
+        let mut h = Harness::with_pages(&["Work", "Home"]);
+        h.keys("<Tab>aReport<Enter>aFiled<Enter><Space><Tab>aGroceries<Enter>");
+        h.app.config = toml::from_str("[carry_over]\npages = [\"work\"]").unwrap();
+        let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+        h.app.carry_over_new_day(today);
+
+        // The page shown stays, the summary names where todos came from
+        assert_eq!(h.current_page(), "Home");
+        assert_eq!(h.descriptions("2025-03-04"), ["Report"]);
+        assert_eq!(h.descriptions("Work"), ["Filed"]);
+        assert_eq!(h.descriptions("Home"), ["Groceries"]);
+        assert!(h
+            .screen()
+            .contains("Carried over 1 todo to 2025-03-04: 1 from Work"));
+
+        // Only once a day
+        h.keys("<BackTab>aLate<Enter>");
+        h.app.carry_over_new_day(today);
+        assert_eq!(h.descriptions("Work"), ["Filed", "Late"]);
+    }
+
+    #[test]
+    fn carry_over_moves_todos_with_the_same_description() {
+        let mut h = Harness::with_pages(&["Work", "Home"]);
+        h.keys("<Tab>aCall Ann<Enter><Tab>aCall Ann<Enter>");
+        h.app.config = toml::from_str("[carry_over]\npages = [\"work\", \"home\"]").unwrap();
+        let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+        h.app.carry_over_new_day(today);
+
+        // Neither is lost, both are on the journal page
+        assert_eq!(h.descriptions("2025-03-04"), ["Call Ann", "Call Ann"]);
+        assert!(h.descriptions("Work").is_empty());
+        assert!(h.descriptions("Home").is_empty());
+    }
+
+    #[test]
+    fn mouse_selects_and_toggles() {
+        let mut h = Harness::new();
+        h.keys("aOne<Enter>aTwo<Enter>aThree<Enter>");
+        let click = MouseEventKind::Down(crossterm::event::MouseButton::Left);
+
+        // The list's first row is at y 4, its checkboxes end before x 10
+        h.mouse(click, 20, 4);
+        assert_eq!(h.app.selected_index(), Some(0));
+        assert!(!h.app.todos()[0].completed);
+        h.mouse(click, 20, 4);
+        assert!(h.app.todos()[0].completed);
+        h.mouse(click, 7, 5);
+        assert_eq!(h.app.selected_index(), Some(1));
+        assert!(h.app.todos()[1].completed);
+
+        // Below the todos and on the border nothing happens
+        h.mouse(click, 20, 10).mouse(click, 2, 6);
+        assert_eq!(h.app.selected_index(), Some(1));
+
+        // The wheel stops at the last todo
+        h.mouse(MouseEventKind::ScrollDown, 20, 4)
+            .mouse(MouseEventKind::ScrollDown, 20, 4);
+        assert_eq!(h.app.selected_index(), Some(2));
+        h.mouse(MouseEventKind::ScrollUp, 20, 4);
+        assert_eq!(h.app.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn new_day_while_running() {
+        let mut h = Harness::new();
+        h.keys("aCall<Enter>");
+        let today = h.app.today;
+        let tomorrow = today.succ_opt().unwrap();
+        h.app.todos_mut()[0].due_date = tomorrow
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_local_timezone(chrono::Local)
+            .single();
+        h.app.add_page("Stretch".to_string(), PageType::Habits);
+        h.keys("aTouch toes<Enter><Space>A");
+        assert!(h.screen().contains("Tomorrow"));
+
+        h.app.refresh_date(today);
+        assert!(h.app.pages[1].todos[0].completed);
+        h.app.dirty = false;
+        h.app.refresh_date(tomorrow);
+        assert!(!h.app.pages[1].todos[0].completed);
+        assert!(h.app.dirty);
+        let screen = h.screen();
+        assert!(screen.contains("Today"));
+        assert!(!screen.contains("Tomorrow"));
+
+        // The reset is a change of its own to undo
+        h.keys("<Esc>u");
+        assert!(h.screen().contains("Undid New Day"));
+        assert!(h.app.pages[1].todos[0].completed);
+    }
+
+    #[test]
+    fn stats_of_all_pages() {
+        let mut h = Harness::with_pages(&["Work"]);
+        h.keys("aOne<Enter>aTwo<Enter>aThree<Enter><Space>k<Space><Tab>aReport<Enter>");
+        let month_ago = chrono::Local::now() - chrono::Duration::days(30);
+        h.app.pages[0].todos[0].created_at = month_ago;
+        h.keys("S");
+
+        let screen = h.screen();
+        assert!(screen.contains(" Default     1     2   66%"));
+        assert!(screen.contains(" All         2     2   50%"));
+        assert!(screen.contains("30d Default: One"));
+        assert!(screen.contains("Completed per Day, 2 todos in the Last"));
+        h.keys("<Esc>");
+        assert_eq!(h.app.view, crate::todo::View::List);
+    }
+
+    #[test]
+    fn footer_follows_the_selected_todo() {
+        let mut h = Harness::new();
+        assert!(h.screen().contains("?: Help | a: Add | q: Quit"));
+
+        h.keys("aRead https://example.com<Enter>");
+        assert!(h.screen().contains("o: Open Link"));
+        h.keys("QaMilk 2<Enter>");
+        let screen = h.screen();
+        assert!(screen.contains("+: More | -: Less"));
+        assert!(!screen.contains("Open Link"));
+        h.keys("<Space>");
+        assert!(h.screen().contains("Space: Reopen"));
+    }
+
+    #[test]
+    fn calendar_lists_the_todos_of_a_day() {
+        let mut h = Harness::with_pages(&["Work"]);
+        h.keys("aPay rent due:today<Enter><Tab>aReport due:+3d<Enter>aShip it due:+3d<Enter>");
+        h.keys("K");
+        let today = h.app.today;
+        let screen = h.screen();
+        assert!(screen.contains(&today.format("%B %Y").to_string()));
+        assert!(screen.contains("Pay rent"));
+        assert!(!screen.contains("Report"));
+
+        h.keys("lll");
+        assert_eq!(h.app.calendar_day, today + chrono::Days::new(3));
+        let screen = h.screen();
+        assert!(screen.contains("Work: [ ] Report"));
+        assert!(screen.contains("Work: [ ] Ship it"));
+
+        // Completing a todo takes it off the day, Enter opens its page
+        h.keys("<Space><Enter>");
+        assert!(h.app.pages[1].todos[0].completed);
+        assert_eq!(h.current_page(), "Work");
+        assert_eq!(h.app.selected_index(), Some(1));
+
+        h.keys("K").press(KeyCode::PageDown);
+        let next_month = today + chrono::Months::new(1);
+        assert!(h.screen().contains(&next_month.format("%B %Y").to_string()));
+    }
+
+    #[test]
+    fn bulk_actions_on_marked_todos() {
+        let mut h = Harness::with_pages(&["Work"]);
+        h.keys("aOne<Enter>aTwo<Enter>aThree<Enter>");
+
+        // Marking moves on to the next todo
+        h.keys("kkMjM");
+        assert!(h.screen().contains("Todos, 2 Marked"));
+        h.keys("<Space>");
+        let completed: Vec<bool> = h.app.todos().iter().map(|t| t.completed).collect();
+        assert_eq!(completed, [true, false, true]);
+        assert!(h.app.marked.is_empty());
+
+        h.keys("MjM#+urgent home<Enter>");
+        assert_eq!(h.app.todos()[0].tags, ["urgent", "home"]);
+        assert!(h.app.todos()[1].tags.is_empty());
+        h.keys("MjM#-home<Enter>");
+        assert_eq!(h.app.todos()[2].tags, ["urgent"]);
+
+        h.keys("MjM>work<Enter>");
+        assert_eq!(h.descriptions("Work"), ["One", "Three"]);
+        assert_eq!(h.descriptions("Default"), ["Two"]);
+        assert!(h.screen().contains("Moved 2 todos to page Work"));
+
+        // Esc drops the marks, d then deletes the selected todo only
+        h.keys("aFour<Enter>M<Esc>d");
+        assert_eq!(h.descriptions("Default"), ["Four"]);
+        h.keys("aFive<Enter>kMMd");
+        assert!(h.descriptions("Default").is_empty());
+    }
+
+    #[test]
+    fn visual_mode_marks_a_range() {
+        let mut h = Harness::new();
+        h.keys("aOne<Enter>aTwo<Enter>aThree<Enter>aFour<Enter>");
+        h.keys("kkkVjj");
+        assert_eq!(h.app.marked.len(), 3);
+        h.keys("k");
+        assert_eq!(h.app.marked.len(), 2);
+
+        // An action applies to the range and ends visual mode
+        h.keys("<Space>");
+        let completed: Vec<bool> = h.app.todos().iter().map(|t| t.completed).collect();
+        assert_eq!(completed, [true, true, false, false]);
+        assert!(h.app.visual_anchor.is_none());
+
+        h.keys("Vjd");
+        assert_eq!(h.descriptions("Default"), ["One", "Four"]);
+        h.keys("Vk<Esc>");
+        assert!(h.app.marked.is_empty());
+        assert!(h.app.visual_anchor.is_none());
+    }
+
+    #[test]
+    fn long_todos_wrap() {
+        let mut h = Harness::new();
+        h.keys("aA long todo description that does not fit into one line of the list so it wraps<Enter>aLast<Enter>");
+        let screen = h.screen();
+        assert!(screen.contains("[ ] A long todo description"));
+        assert!(screen.contains("        list so it wraps"));
+
+        // Clicks find rows below wrapped ones
+        let click = MouseEventKind::Down(crossterm::event::MouseButton::Left);
+        h.mouse(click, 20, 5);
+        assert_eq!(h.app.selected_index(), Some(0));
+        h.mouse(click, 20, 6);
+        assert_eq!(h.app.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn remapped_keys() {
+        let mut h = Harness::with_pages(&["Work"]);
+        h.app.config = toml::from_str(
+            "[keys]\nnext = [\"h\", \"down\"]\nprevious = \"t\"\ndue-date = \"ctrl+t\"",
+        )
+        .unwrap();
+        h.keys("aOne<Enter>aTwo<Enter>t");
+        assert_eq!(h.app.selected_index(), Some(0));
+        h.keys("h");
+        assert_eq!(h.app.selected_index(), Some(1));
+
+        // j is free now, and the page list moves with h too
+        h.keys("j");
+        assert_eq!(h.app.selected_index(), Some(1));
+        h.keys("Ph<Enter>");
+        assert_eq!(h.current_page(), "Work");
+
+        let err = toml::from_str::<crate::config::Config>("[keys]\njump = \"g\"").unwrap_err();
+        assert!(err.to_string().contains("unknown action `jump`"));
+    }
+
+    #[test]
+    fn page_jumps_in_long_lists() {
+        let mut h = Harness::new();
+        for i in 0..40 {
+            h.keys(&format!("aTodo {}<Enter>", i));
+        }
+        // 13 todos fit on the 24 rows of the screen
+        assert_eq!(h.app.page_lines(), 13);
+        h.press(KeyCode::PageUp);
+        assert_eq!(h.app.selected_index(), Some(26));
+        h.press_with(KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.selected_index(), Some(19));
+        assert!(h.screen().contains("Todo 19"));
+        assert!(!h.screen().contains("Todo 39"));
+
+        // No wrapping around at the ends
+        h.press(KeyCode::PageDown).press(KeyCode::PageDown);
+        assert_eq!(h.app.selected_index(), Some(39));
+        h.press_with(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.selected_index(), Some(39));
+
+        // Todos wrapped onto two lines take two of them
+        let mut h = Harness::new();
+        for i in 0..20 {
+            h.keys(&format!("aTodo {} {}<Enter>", i, "long ".repeat(20)));
+        }
+        h.press(KeyCode::PageUp);
+        assert_eq!(h.app.selected_index(), Some(13));
+        assert!(h.screen().contains("Todo 13"));
+    }
+
+    #[test]
+    fn long_descriptions_are_confirmed_each_time() {
+        let mut h = Harness::new();
+        h.app.config.max_description_length = 10;
+        h.keys("aA long description<Enter>");
+        assert!(h
+            .screen()
+            .contains("Description is 18 characters (limit 10)"));
+        h.keys("<Enter>");
+        assert_eq!(h.descriptions("Default"), ["A long description"]);
+
+        // Warned again after the popup was closed without adding
+        h.keys("aA long description<Enter><Esc>aA long description<Enter>");
+        assert_eq!(h.descriptions("Default"), ["A long description"]);
+
+        // Only the description counts, not the words setting the details
+        h.keys("<Esc>aPay rent due:tomorrow !high +home<Enter>");
+        assert_eq!(
+            h.descriptions("Default"),
+            ["A long description", "Pay rent"]
+        );
+    }
+
+    #[test]
+    fn key_presets() {
+        let mut h = Harness::new();
+        h.app.config = toml::from_str("[keys]\npreset = \"emacs\"\ndelete = \"d\"").unwrap();
+        h.keys("aOne<Enter>aTwo<Enter>");
+        h.press_with(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.selected_index(), Some(0));
+        h.keys("d");
+        assert_eq!(h.descriptions("Default"), ["Two"]);
+
+        // Enter completes with the arrows preset
+        h.app.config = toml::from_str("keys = \"arrows\"").unwrap();
+        h.keys("<Enter>");
+        assert!(h.app.todos()[0].completed);
+        assert!(h.screen().contains("Up/Down: Select"));
+
+        let err = toml::from_str::<crate::config::Config>("keys = \"nano\"").unwrap_err();
+        assert!(err.to_string().contains("unknown key preset `nano`"));
+    }
+
+    #[test]
+    fn title_shows_page_progress() {
+        let mut h = Harness::new();
+        assert!(!h.screen().contains("0/0"));
+        h.keys("aOne<Enter>aTwo<Enter><Space>");
+        assert!(h.screen().contains("■■■■■■■■■■ 1/2"));
+
+        // Tabs that don't fit are cut off around the current page
+        let mut h = Harness::with_pages(&["Work", "Home", "Shopping", "Reading", "Garden", "Trip"]);
+        h.keys("<BackTab>");
+        let title = h.screen().lines().nth(2).unwrap().to_string();
+        assert!(title.contains("… │ Home 0"));
+        assert!(title.contains("│ Trip 0"));
+        assert!(!title.contains("Work"));
+    }
+
+    #[test]
+    fn newer_release_on_the_status_line() {
+        let mut h = Harness::new();
+        h.app.update_available = Some("0.2.0".into());
+        assert!(h.screen().contains("ratdo 0.2.0 is available"));
+    }
+
+    #[test]
+    fn default_config_parses() {
+        let text = crate::config::default_config();
+        toml::from_str::<crate::config::Config>(&text).unwrap();
+
+        // Every key line uncommented gives the default keys back
+        let keys: String = text[text.find("# [keys]").unwrap()..]
+            .lines()
+            .map(|line| line.trim_start_matches("# ").split("  #").next().unwrap())
+            .filter(|line| !line.starts_with("preset"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let config: crate::config::Config = toml::from_str(&keys).unwrap();
+        let mut h = Harness::new();
+        h.app.config = config;
+        h.keys("aOne<Enter><Space>");
+        assert!(h.app.todos()[0].completed);
+    }
+
+    #[test]
+    fn open_todo_by_link() {
+        let mut h = Harness::with_pages(&["Work"]);
+        h.keys("aOne<Enter><Tab>aTwo<Enter>aThree<Enter><BackTab>");
+        let link = h.app.pages[1].todos[1].link();
+
+        let id = crate::todo::parse_link(&link).unwrap();
+        assert!(h.app.select_todo_by_id(id));
+        assert_eq!(h.current_page(), "Work");
+        assert_eq!(h.app.selected_index(), Some(1));
+        h.keys("v");
+        assert!(h.screen().contains("ratdo://todo/"));
+        assert_eq!(crate::todo::parse_link("ratdo://todo/nope"), None);
+    }
+
+    #[test]
+    fn usage_counts_actions() {
+        let mut h = Harness::new();
+        h.keys("aOne<Enter>aTwo<Enter>kj<Space>");
+
+        // Typing in popups isn't counted, only the keys of actions
+        let usage = &h.app.usage;
+        assert_eq!(usage.actions.get("add"), Some(&2));
+        assert_eq!(usage.actions.get("edit"), None);
+        assert_eq!(usage.most_used()[0].1, "add");
+        assert!(usage
+            .never_used()
+            .iter()
+            .any(|&(_, name, _)| name == "quit"));
+    }
+
+    #[test]
+    fn quit_stops_handling_keys() {
+        let mut h = Harness::new();
+        h.keys("qaIgnored<Enter>");
+
+        assert!(h.quit);
+        assert!(h.descriptions("Default").is_empty());
+    }
+
+    #[test]
+    fn quantities_only_edited_while_tracked() {
+        let mut h = Harness::new();
+        h.keys("QaMilk x2<Enter>Q");
+        assert_eq!(h.app.todos()[0].quantity, Some(2));
+
+        h.keys("e");
+        assert_eq!(h.app.current_input, "Milk");
+        h.keys("<Enter>");
+        assert_eq!(h.descriptions("Default"), ["Milk"]);
+        assert_eq!(h.app.todos()[0].quantity, Some(2));
+    }
+
+    #[test]
+    fn marked_todos_needing_notes_are_not_bulk_completed() {
+        let mut h = Harness::new();
+        h.app.config = toml::from_str("completion_note_tags = [\"client\"]").unwrap();
+        h.keys("aCall client +client<Enter>aWater plants<Enter>");
+        h.keys("ggMM<Space>");
+        assert!(h
+            .screen()
+            .contains("Todos needing a completion note are checked off one at a time"));
+        assert!(h.app.todos().iter().all(|todo| !todo.completed));
+
+        // Without such a todo among them the marked ones are completed
+        h.keys("<Esc>jM<Space>");
+        assert!(!h.app.todos()[0].completed);
+        assert!(h.app.todos()[1].completed);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::todo::{App, InputMode};
+
+// A titled group of "key", "what it does" pairs
+pub type Section = (&'static str, &'static [(&'static str, &'static str)]);
+
+const PAGE_LIST: Section = (
+    "Page list",
+    &[
+        ("Enter", "Open the page"),
+        ("n, a", "New page, Tab picks its type"),
+        ("i", "Set the page icon"),
+        ("d", "Delete the page"),
+        ("j/k, Up/Down", "Navigate"),
+        ("Esc, P", "Close"),
+    ],
+);
+
+const NEW_PAGE: Section = (
+    "New page",
+    &[
+        ("Enter", "Create the page"),
+        ("Tab", "Next page type"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const ADD_EDIT: Section = (
+    "Add and edit popup",
+    &[
+        ("Enter", "Save"),
+        ("Tab", "Complete a /page word"),
+        ("Up/Down", "Pick a page for the /page word"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const INPUT: Section = (
+    "Every input popup",
+    &[
+        ("Left/Right, Home/End", "Move the cursor"),
+        ("Ctrl+w, Ctrl+u", "Delete the word before the cursor, clear"),
+    ],
+);
+
+const DATE: Section = (
+    "Due date, scheduled date and reminder",
+    &[("Enter", "Save, empty clears the date"), ("Esc", "Cancel")],
+);
+
+const SNOOZE: Section = (
+    "Snooze",
+    &[
+        ("Enter", "Snooze, empty wakes the todo up"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const POSTPONE: Section = (
+    "Postpone overdue todos",
+    &[
+        ("Enter", "Move them all, +Nd or +Nw from today"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const COMPLETION_NOTE: Section = (
+    "Completion note",
+    &[("Enter", "Complete with this note"), ("Esc", "Keep open")],
+);
+
+const COMMENT: Section = (
+    "Comment",
+    &[("Enter", "Add the comment"), ("Esc", "Cancel")],
+);
+
+const ATTACH: Section = (
+    "Attach",
+    &[("Enter", "Attach the file path or URL"), ("Esc", "Cancel")],
+);
+
+const TEMPLATE_NAME: Section = (
+    "Save as template",
+    &[
+        ("Enter", "Save, an existing name adds the todo"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const TAG: Section = (
+    "Tags",
+    &[("Enter", "Add +tags and remove -tags"), ("Esc", "Cancel")],
+);
+
+const PAGE_ICON: Section = (
+    "Page icon",
+    &[
+        ("Enter", "Set the icon, empty removes it"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const GO_TO_PAGE: Section = (
+    "Go to page",
+    &[
+        ("Enter", "Open the page"),
+        ("Up/Down, Tab", "Navigate the matches"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const MOVE_TO_PAGE: Section = (
+    "Move to page",
+    &[
+        ("Enter", "Move the todos there"),
+        ("Up/Down, Tab", "Navigate the matches"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const JUMP_TO_TODO: Section = (
+    "Jump to todo",
+    &[
+        ("Enter", "Select the todo"),
+        ("Up/Down, Tab", "Navigate the matches"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const RESCHEDULE: Section = (
+    "Reschedule menu",
+    &[
+        ("d / n", "Tomorrow / next workday"),
+        ("w / s / m", "In a week / the weekend / next Monday"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const SEARCH: Section = (
+    "Search",
+    &[
+        ("Enter", "Keep the matches listed"),
+        ("Up/Down", "Navigate the matches"),
+        ("Esc", "Clear the search"),
+    ],
+);
+
+const CONFIRM_DELETE: Section = (
+    "Delete confirmation",
+    &[("y, Enter", "Delete"), ("n, Esc", "Keep")],
+);
+
+const NOTES: Section = (
+    "Notes",
+    &[("Ctrl+s", "Save"), ("Enter", "New line"), ("Esc", "Cancel")],
+);
+
+const TAG_FILTER: Section = (
+    "Tag filter",
+    &[
+        ("Enter", "Filter by the tag"),
+        ("j/k, Up/Down", "Navigate"),
+        ("Esc", "Clear the filter"),
+    ],
+);
+
+const TEMPLATES: Section = (
+    "Templates",
+    &[
+        ("Enter", "Add the todos of the template"),
+        ("d", "Delete the template"),
+        ("j/k, Up/Down", "Navigate"),
+        ("Esc", "Close"),
+    ],
+);
+
+const IMPORT: Section = (
+    "Import from the clipboard",
+    &[
+        ("Enter", "Add the lines as todos"),
+        ("d", "Leave the line out"),
+        ("j/k, Up/Down", "Navigate"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const DAY_SUMMARY: Section = (
+    "Day summary",
+    &[("j/k, Up/Down", "Scroll"), ("Esc, Enter, q", "Close")],
+);
+
+const CONFLICTS: Section = (
+    "Conflicts found when saving",
+    &[
+        ("m", "Keep my value"),
+        ("t", "Take the value saved elsewhere"),
+        ("j/k, Up/Down", "Navigate"),
+        ("Esc", "Keep the newest edit of the rest"),
+    ],
+);
+
+const ARCHIVE: Section = (
+    "Archive",
+    &[
+        ("Enter, r", "Restore the todo"),
+        ("d", "Delete it for good"),
+        ("j/k, Up/Down", "Navigate"),
+        ("Esc", "Close"),
+    ],
+);
+
+const LINKS: Section = (
+    "Links",
+    &[
+        ("Enter", "Open the link"),
+        ("d", "Remove the link"),
+        ("j/k, Up/Down", "Navigate"),
+        ("Esc", "Close"),
+    ],
+);
+
+const LISTS: Section = (
+    "Every list and picker",
+    &[
+        ("PageUp/PageDown", "Jump 10 entries"),
+        ("Home/End", "First / last entry"),
+    ],
+);
+
+const HELP: Section = (
+    "Help",
+    &[
+        ("j/k, Up/Down", "Scroll"),
+        ("PageUp/PageDown", "Scroll 10 lines"),
+        ("Home/End", "Top / bottom"),
+        ("Esc, ?, q", "Close"),
+    ],
+);
+
+// Keys of the popups and menus, shown by the help overlay (?) after the
+// configurable list and agenda keys of `keymap::ACTIONS`. Keep in sync with
+// `handle_key`. The keys of next and previous also move through menus.
+pub const SECTIONS: &[Section] = &[
+    PAGE_LIST,
+    NEW_PAGE,
+    ADD_EDIT,
+    INPUT,
+    DATE,
+    SNOOZE,
+    POSTPONE,
+    COMPLETION_NOTE,
+    COMMENT,
+    ATTACH,
+    TEMPLATE_NAME,
+    TAG,
+    PAGE_ICON,
+    GO_TO_PAGE,
+    MOVE_TO_PAGE,
+    JUMP_TO_TODO,
+    RESCHEDULE,
+    SEARCH,
+    CONFIRM_DELETE,
+    NOTES,
+    TAG_FILTER,
+    TEMPLATES,
+    IMPORT,
+    DAY_SUMMARY,
+    CONFLICTS,
+    ARCHIVE,
+    LINKS,
+    LISTS,
+    HELP,
+];
+
+// Section of the open popup or menu, shown in the help bar. None in the list
+// and the views, whose help bar comes from the keymap.
+pub fn popup_section(app: &App) -> Option<Section> {
+    Some(match app.input_mode {
+        InputMode::Normal => return None,
+        InputMode::Editing if app.show_page_selector => NEW_PAGE,
+        InputMode::Editing => ADD_EDIT,
+        InputMode::PageSelect => PAGE_LIST,
+        InputMode::DueDate | InputMode::Scheduled | InputMode::Reminder => DATE,
+        InputMode::Defer => SNOOZE,
+        InputMode::Reschedule => RESCHEDULE,
+        InputMode::Postpone => POSTPONE,
+        InputMode::CompletionNote => COMPLETION_NOTE,
+        InputMode::Comment => COMMENT,
+        InputMode::Attach => ATTACH,
+        InputMode::TemplateName => TEMPLATE_NAME,
+        InputMode::Tag => TAG,
+        InputMode::PageIcon => PAGE_ICON,
+        InputMode::PageSwitch if app.moving_to_page => MOVE_TO_PAGE,
+        InputMode::PageSwitch => GO_TO_PAGE,
+        InputMode::TodoJump => JUMP_TO_TODO,
+        InputMode::Search => SEARCH,
+        InputMode::ConfirmDelete => CONFIRM_DELETE,
+        InputMode::Notes => NOTES,
+        InputMode::TagFilter => TAG_FILTER,
+        InputMode::Templates => TEMPLATES,
+        InputMode::Import => IMPORT,
+        InputMode::DaySummary => DAY_SUMMARY,
+        InputMode::Conflicts => CONFLICTS,
+        InputMode::Archive => ARCHIVE,
+        InputMode::Links => LINKS,
+        InputMode::Help => HELP,
+    })
+}
+
+// "key: what it does" of a section joined by " | " to fit `width`
+pub fn bar((_, keys): Section, width: usize) -> String {
+    let entries = keys
+        .iter()
+        .map(|(key, action)| format!("{}: {}", key, action))
+        .collect();
+    fit(entries, width)
+}
+
+// Help bar entries joined by " | ", leaving out entries before the last one
+// that no longer fit `width`
+pub fn fit(mut entries: Vec<String>, width: usize) -> String {
+    let last = entries.pop().unwrap_or_default();
+    let mut bar = String::new();
+    for entry in entries {
+        if bar.chars().count() + entry.chars().count() + 3 + last.chars().count() > width {
+            continue;
+        }
+        bar.push_str(&entry);
+        bar.push_str(" | ");
+    }
+    bar.push_str(&last);
+    bar
+}
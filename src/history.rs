@@ -0,0 +1,49 @@
+use crate::todo::TodoPage;
+
+// Undo steps kept, older ones are dropped
+const LIMIT: usize = 100;
+
+// The pages and selection as they were before an action
+pub struct Snapshot {
+    // What the action did, for the status message, e.g. "Delete"
+    pub action: &'static str,
+    pub pages: Vec<TodoPage>,
+    pub current_page_index: usize,
+    pub selected: Option<usize>,
+}
+
+// Undo and redo stacks of snapshots. A new action drops the redo steps.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl History {
+    pub fn record(&mut self, snapshot: Snapshot) {
+        if self.undo.len() == LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(snapshot);
+        self.redo.clear();
+    }
+
+    // Step back, `current` becomes the redo step. Returns the state to restore.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.undo.pop()?;
+        self.redo.push(Snapshot {
+            action: snapshot.action,
+            ..current
+        });
+        Some(snapshot)
+    }
+
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push(Snapshot {
+            action: snapshot.action,
+            ..current
+        });
+        Some(snapshot)
+    }
+}
@@ -0,0 +1,82 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::text::Span;
+
+// Edit the text of an input popup with the cursor `cursor` characters in,
+// past the end meaning at the end: typing and Delete/Backspace work at the
+// cursor, Left/Right and Home/End (Ctrl+a/Ctrl+e) move it, Ctrl+w deletes the
+// word before it and Ctrl+u clears the line. False for other keys.
+pub fn edit(text: &mut String, cursor: &mut usize, key: KeyEvent) -> bool {
+    let len = text.chars().count();
+    let at = (*cursor).min(len);
+    let byte = |chars: usize| {
+        text.char_indices()
+            .nth(chars)
+            .map_or(text.len(), |(i, _)| i)
+    };
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+    *cursor = match key.code {
+        KeyCode::Char('a') if ctrl => 0,
+        KeyCode::Char('e') if ctrl => len,
+        KeyCode::Char('u') if ctrl => {
+            text.clear();
+            0
+        }
+        KeyCode::Char('w') if ctrl => {
+            let before: Vec<char> = text.chars().take(at).collect();
+            let spaces = before
+                .iter()
+                .rev()
+                .take_while(|c| c.is_whitespace())
+                .count();
+            let word = before[..at - spaces]
+                .iter()
+                .rev()
+                .take_while(|c| !c.is_whitespace())
+                .count();
+            let start = at - spaces - word;
+            text.replace_range(byte(start)..byte(at), "");
+            start
+        }
+        KeyCode::Char(_) if ctrl => return false,
+        KeyCode::Char(c) => {
+            text.insert(byte(at), c);
+            at + 1
+        }
+        KeyCode::Backspace if at > 0 => {
+            text.replace_range(byte(at - 1)..byte(at), "");
+            at - 1
+        }
+        KeyCode::Delete if at < len => {
+            text.replace_range(byte(at)..byte(at + 1), "");
+            at
+        }
+        KeyCode::Backspace | KeyCode::Delete => at,
+        KeyCode::Left => at.saturating_sub(1),
+        KeyCode::Right => (at + 1).min(len),
+        KeyCode::Home => 0,
+        KeyCode::End => len,
+        _ => return false,
+    };
+    true
+}
+
+// Insert pasted text at the cursor, which ends up after it
+pub fn insert(text: &mut String, cursor: &mut usize, pasted: &str) {
+    let at = (*cursor).min(text.chars().count());
+    let byte = text.char_indices().nth(at).map_or(text.len(), |(i, _)| i);
+    text.insert_str(byte, pasted);
+    *cursor = at + pasted.chars().count();
+}
+
+// Columns the text before the cursor takes on its line
+pub fn cursor_column(text: &str, cursor: usize) -> u16 {
+    let before: String = text.chars().take(cursor).collect();
+    let line = before.rsplit('\n').next().unwrap_or("");
+    Span::raw(line).width() as u16
+}
+
+// Line of a multi-line text the cursor is on, counted from 0
+pub fn cursor_line(text: &str, cursor: usize) -> u16 {
+    text.chars().take(cursor).filter(|&c| c == '\n').count() as u16
+}
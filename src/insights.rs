@@ -0,0 +1,80 @@
+use crate::keymap::{Action, Keymap, ACTIONS};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// How many of the most used actions `ratdo insights` lists
+const MOST_USED: usize = 15;
+
+// Actions used in the TUI by name, counted in the session file for
+// `ratdo insights` and never sent anywhere
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Usage {
+    // When the first action was counted
+    pub since: Option<DateTime<Local>>,
+    pub actions: BTreeMap<String, u64>,
+}
+
+impl Usage {
+    pub fn count(&mut self, action: Action) {
+        let Some((_, name, ..)) = ACTIONS.iter().find(|(a, ..)| *a == action) else {
+            return;
+        };
+        self.since.get_or_insert_with(Local::now);
+        *self.actions.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    // Used actions of the keymap and their counts, most used first
+    pub fn most_used(&self) -> Vec<(Action, &'static str, u64)> {
+        let mut used: Vec<(Action, &'static str, u64)> = ACTIONS
+            .iter()
+            .filter_map(|&(action, name, ..)| Some((action, name, *self.actions.get(name)?)))
+            .collect();
+        used.sort_by_key(|&(.., count)| std::cmp::Reverse(count));
+        used
+    }
+
+    // Actions of the keymap that were never used, in the order of the help
+    pub fn never_used(&self) -> Vec<(Action, &'static str, &'static str)> {
+        ACTIONS
+            .iter()
+            .filter(|(_, name, ..)| !self.actions.contains_key(*name))
+            .map(|&(action, name, _, description, _)| (action, name, description))
+            .collect()
+    }
+
+    // Plain text report of `ratdo insights`, with the keys bound to each action
+    pub fn report(&self, keys: &Keymap) -> String {
+        let Some(since) = self.since else {
+            return "Nothing counted yet, actions used in the TUI are counted from now on\n"
+                .to_string();
+        };
+        let bound = |action: Action| {
+            let names = keys.names(action);
+            names.join(", ")
+        };
+
+        let used = self.most_used();
+        let total: u64 = used.iter().map(|&(.., count)| count).sum();
+        let mut report = format!(
+            "{} actions since {}\n\nMost used\n",
+            total,
+            since.format("%Y-%m-%d")
+        );
+        for &(action, name, count) in used.iter().take(MOST_USED) {
+            report.push_str(&format!("  {:>6}  {:<22} {}\n", count, name, bound(action)));
+        }
+
+        report.push_str("\nNever used\n");
+        for (action, name, description) in self.never_used() {
+            report.push_str(&format!(
+                "  {:<22} {:<14} {}\n",
+                name,
+                bound(action),
+                description
+            ));
+        }
+        report
+    }
+}
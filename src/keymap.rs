@@ -0,0 +1,922 @@
+use crate::help;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{de::Error, Deserialize, Deserializer};
+use std::collections::BTreeMap;
+
+// What a key does in the todo list and the agenda, see `handle_key`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Quit,
+    Add,
+    Edit,
+    Delete,
+    Toggle,
+    Next,
+    Previous,
+    First,
+    Last,
+    FirstOpen,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    NextPage,
+    PreviousPage,
+    PageList,
+    GoToPage,
+    JumpToTodo,
+    RecentPage,
+    Workspace,
+    Move,
+    Mark,
+    Visual,
+    MoveToPage,
+    Tag,
+    Undo,
+    Redo,
+    Archive,
+    ArchiveCompleted,
+    ArchiveBrowser,
+    CarryOver,
+    DueDate,
+    Schedule,
+    Reminder,
+    Reschedule,
+    Postpone,
+    DaySummary,
+    Focus,
+    Save,
+    Timer,
+    OpenLinks,
+    Yank,
+    Import,
+    YankWithNotes,
+    Attach,
+    Comment,
+    Pin,
+    Snooze,
+    ShowSnoozed,
+    LargeText,
+    Density,
+    Group,
+    ToggleGroup,
+    DoneSection,
+    TagFilter,
+    Details,
+    Open,
+    Notes,
+    Agenda,
+    Review,
+    Calendar,
+    Stats,
+    NextDay,
+    PreviousDay,
+    NeedsReview,
+    SaveTemplate,
+    InsertTemplate,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    Back,
+    Checklist,
+    Quantities,
+    Increase,
+    Decrease,
+    Help,
+}
+
+// Groups of actions in the help overlay, in the order it lists them
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Category {
+    Todos,
+    Marking,
+    Dates,
+    Navigation,
+    Pages,
+    Views,
+    General,
+}
+
+impl Category {
+    pub const ALL: [Category; 7] = [
+        Category::Todos,
+        Category::Marking,
+        Category::Dates,
+        Category::Navigation,
+        Category::Pages,
+        Category::Views,
+        Category::General,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Category::Todos => "Todos",
+            Category::Marking => "Marking and moving",
+            Category::Dates => "Dates",
+            Category::Navigation => "Navigation",
+            Category::Pages => "Pages",
+            Category::Views => "Agenda and other views",
+            Category::General => "General",
+        }
+    }
+}
+
+// Every action with its name in the config file, default keys, what it does
+// and where the help lists it
+pub const ACTIONS: &[(Action, &str, &[&str], &str, Category)] = &[
+    (Action::Add, "add", &["a"], "Add a todo", Category::Todos),
+    (
+        Action::Edit,
+        "edit",
+        &["e"],
+        "Edit the selected todo",
+        Category::Todos,
+    ),
+    (
+        Action::Delete,
+        "delete",
+        &["d"],
+        "Delete, or the marked todos",
+        Category::Todos,
+    ),
+    (
+        Action::Toggle,
+        "toggle",
+        &["space"],
+        "Toggle done or the marked todos, complete in the agenda",
+        Category::Todos,
+    ),
+    (
+        Action::Next,
+        "next",
+        &["j", "down"],
+        "Next todo",
+        Category::Navigation,
+    ),
+    (
+        Action::Previous,
+        "previous",
+        &["k", "up"],
+        "Previous todo",
+        Category::Navigation,
+    ),
+    (
+        Action::First,
+        "first",
+        &["g g", "home"],
+        "First todo, with a number before it that todo",
+        Category::Navigation,
+    ),
+    (
+        Action::Last,
+        "last",
+        &["G", "end"],
+        "Last todo, with a number before it that todo",
+        Category::Navigation,
+    ),
+    (
+        Action::FirstOpen,
+        "first-open",
+        &["g o"],
+        "First todo that isn't done",
+        Category::Navigation,
+    ),
+    (
+        Action::PageDown,
+        "page-down",
+        &["pagedown"],
+        "A screen down, a month in the calendar",
+        Category::Navigation,
+    ),
+    (
+        Action::PageUp,
+        "page-up",
+        &["pageup"],
+        "A screen up, a month in the calendar",
+        Category::Navigation,
+    ),
+    (
+        Action::HalfPageDown,
+        "half-page-down",
+        &["ctrl+d"],
+        "Half a screen down, a week in the calendar",
+        Category::Navigation,
+    ),
+    (
+        Action::HalfPageUp,
+        "half-page-up",
+        &["ctrl+u"],
+        "Half a screen up, a week in the calendar",
+        Category::Navigation,
+    ),
+    (
+        Action::Move,
+        "move",
+        &["p"],
+        "Move mode, next/previous then move the todo",
+        Category::Marking,
+    ),
+    (
+        Action::Mark,
+        "mark",
+        &["M"],
+        "Mark for a bulk delete, toggle, move or tag, then next",
+        Category::Marking,
+    ),
+    (
+        Action::Visual,
+        "visual",
+        &["V"],
+        "Visual mode, next/previous mark a range",
+        Category::Marking,
+    ),
+    (
+        Action::MoveToPage,
+        "move-to-page",
+        &[">"],
+        "Move the todo or the marked ones to another page",
+        Category::Marking,
+    ),
+    (
+        Action::Tag,
+        "tag",
+        &["#"],
+        "Add +tags and remove -tags of the todo or the marked ones",
+        Category::Marking,
+    ),
+    (Action::Undo, "undo", &["u"], "Undo", Category::Todos),
+    (Action::Redo, "redo", &["ctrl+r"], "Redo", Category::Todos),
+    (
+        Action::Archive,
+        "archive",
+        &["x"],
+        "Archive the todo",
+        Category::Todos,
+    ),
+    (
+        Action::ArchiveCompleted,
+        "archive-completed",
+        &["X"],
+        "Archive all completed todos",
+        Category::Todos,
+    ),
+    (
+        Action::ArchiveBrowser,
+        "archive-browser",
+        &["H"],
+        "Browse the archive of the page",
+        Category::Todos,
+    ),
+    (
+        Action::CarryOver,
+        "carry-over",
+        &["Y"],
+        "Carry over open todos of the previous journal page",
+        Category::Todos,
+    ),
+    (
+        Action::DueDate,
+        "due-date",
+        &["t"],
+        "Due date",
+        Category::Dates,
+    ),
+    (
+        Action::Schedule,
+        "schedule",
+        &["s"],
+        "Scheduled date",
+        Category::Dates,
+    ),
+    (
+        Action::Reminder,
+        "reminder",
+        &["r"],
+        "Reminder",
+        Category::Dates,
+    ),
+    (
+        Action::Reschedule,
+        "reschedule",
+        &["."],
+        "Reschedule menu",
+        Category::Dates,
+    ),
+    (Action::Snooze, "snooze", &["z"], "Snooze", Category::Dates),
+    (
+        Action::ShowSnoozed,
+        "show-snoozed",
+        &["Z"],
+        "Show snoozed todos",
+        Category::Dates,
+    ),
+    (
+        Action::Pin,
+        "pin",
+        &["*"],
+        "Pin to the top",
+        Category::Todos,
+    ),
+    (
+        Action::NeedsReview,
+        "needs-review",
+        &["!"],
+        "Flag for review",
+        Category::Todos,
+    ),
+    (
+        Action::Timer,
+        "timer",
+        &["T"],
+        "Start or stop the timer",
+        Category::Todos,
+    ),
+    (
+        Action::Details,
+        "details",
+        &["v"],
+        "Details pane",
+        Category::Navigation,
+    ),
+    (
+        Action::Open,
+        "open",
+        &["enter"],
+        "Details pane, in the agenda open the todo's page",
+        Category::Navigation,
+    ),
+    (
+        Action::Notes,
+        "notes",
+        &["E"],
+        "Edit notes",
+        Category::Todos,
+    ),
+    (
+        Action::Comment,
+        "comment",
+        &["C"],
+        "Comment",
+        Category::Todos,
+    ),
+    (
+        Action::OpenLinks,
+        "open-links",
+        &["o"],
+        "Open links",
+        Category::Todos,
+    ),
+    (
+        Action::Yank,
+        "yank",
+        &["y"],
+        "Copy the description to the clipboard",
+        Category::Todos,
+    ),
+    (
+        Action::YankWithNotes,
+        "yank-with-notes",
+        &["ctrl+y"],
+        "Copy the description and notes to the clipboard",
+        Category::Todos,
+    ),
+    (
+        Action::Import,
+        "import",
+        &["I"],
+        "Add the lines of the clipboard as todos",
+        Category::Todos,
+    ),
+    (
+        Action::Attach,
+        "attach",
+        &["L"],
+        "Attach a file or URL",
+        Category::Todos,
+    ),
+    (
+        Action::TagFilter,
+        "tag-filter",
+        &["f"],
+        "Filter by tag",
+        Category::Navigation,
+    ),
+    (
+        Action::Search,
+        "search",
+        &["/"],
+        "Search the page",
+        Category::Navigation,
+    ),
+    (
+        Action::NextMatch,
+        "next-match",
+        &["n"],
+        "Next match",
+        Category::Navigation,
+    ),
+    (
+        Action::PreviousMatch,
+        "previous-match",
+        &["N"],
+        "Previous match",
+        Category::Navigation,
+    ),
+    (
+        Action::Back,
+        "back",
+        &["esc"],
+        "Clear the marks, search or tag filter, leave the agenda",
+        Category::Navigation,
+    ),
+    (
+        Action::SaveTemplate,
+        "save-template",
+        &["m"],
+        "Save as template",
+        Category::Todos,
+    ),
+    (
+        Action::InsertTemplate,
+        "insert-template",
+        &["i"],
+        "Insert a template",
+        Category::Todos,
+    ),
+    (
+        Action::Checklist,
+        "checklist",
+        &["c"],
+        "Checklist mode",
+        Category::Pages,
+    ),
+    (
+        Action::Quantities,
+        "quantities",
+        &["Q"],
+        "Quantities",
+        Category::Pages,
+    ),
+    (
+        Action::Increase,
+        "increase",
+        &["+"],
+        "Increase the quantity",
+        Category::Todos,
+    ),
+    (
+        Action::Decrease,
+        "decrease",
+        &["-"],
+        "Decrease the quantity",
+        Category::Todos,
+    ),
+    (
+        Action::LargeText,
+        "large-text",
+        &["D"],
+        "Large text",
+        Category::Pages,
+    ),
+    (
+        Action::Density,
+        "density",
+        &["z v"],
+        "Compact rows, or details on a second line",
+        Category::Pages,
+    ),
+    (
+        Action::Group,
+        "group",
+        &["b"],
+        "Group by when todos were added, due date, priority, tag, or not",
+        Category::Pages,
+    ),
+    (
+        Action::ToggleGroup,
+        "toggle-group",
+        &["z a"],
+        "Collapse or expand the group",
+        Category::Pages,
+    ),
+    (
+        Action::DoneSection,
+        "done-section",
+        &["z d"],
+        "Done todos in a section at the bottom, or in between",
+        Category::Pages,
+    ),
+    (
+        Action::NextPage,
+        "next-page",
+        &["tab"],
+        "Next page",
+        Category::Pages,
+    ),
+    (
+        Action::PreviousPage,
+        "previous-page",
+        &["backtab"],
+        "Previous page",
+        Category::Pages,
+    ),
+    (
+        Action::PageList,
+        "page-list",
+        &["P"],
+        "Page list",
+        Category::Pages,
+    ),
+    (
+        Action::GoToPage,
+        "go-to-page",
+        &["'", "ctrl+o"],
+        "Go to a page by typing part of its name",
+        Category::Pages,
+    ),
+    (
+        Action::JumpToTodo,
+        "jump-to-todo",
+        &["ctrl+j"],
+        "Jump to a todo of the page by typing part of it",
+        Category::Navigation,
+    ),
+    (
+        Action::RecentPage,
+        "recent-page",
+        &["`"],
+        "Previous page, again for the one before",
+        Category::Pages,
+    ),
+    (
+        Action::Workspace,
+        "workspace",
+        &["W"],
+        "Next workspace",
+        Category::Pages,
+    ),
+    (Action::Agenda, "agenda", &["A"], "Agenda", Category::Views),
+    (
+        Action::Review,
+        "review",
+        &["R"],
+        "Review list",
+        Category::Views,
+    ),
+    (
+        Action::Calendar,
+        "calendar",
+        &["K"],
+        "Calendar of due dates",
+        Category::Views,
+    ),
+    (
+        Action::Stats,
+        "stats",
+        &["S"],
+        "Statistics of all pages",
+        Category::Views,
+    ),
+    (
+        Action::NextDay,
+        "next-day",
+        &["l", "right"],
+        "Next day in the calendar",
+        Category::Views,
+    ),
+    (
+        Action::PreviousDay,
+        "previous-day",
+        &["h", "left"],
+        "Previous day in the calendar",
+        Category::Views,
+    ),
+    (
+        Action::Postpone,
+        "postpone",
+        &["O"],
+        "Postpone all overdue todos (agenda)",
+        Category::Dates,
+    ),
+    (
+        Action::DaySummary,
+        "day-summary",
+        &["B"],
+        "Summary of yesterday and today",
+        Category::Views,
+    ),
+    (
+        Action::Focus,
+        "focus",
+        &["F"],
+        "Just the selected todo, to work through the list one by one",
+        Category::Views,
+    ),
+    (
+        Action::Save,
+        "save",
+        &["ctrl+s"],
+        "Save now rather than on quit",
+        Category::General,
+    ),
+    (Action::Help, "help", &["?"], "This help", Category::General),
+    (Action::Quit, "quit", &["q"], "Quit", Category::General),
+];
+
+pub const PRESETS: [&str; 3] = ["vim", "emacs", "arrows"];
+
+// Starting points for `[keys]`, vim being the defaults of `ACTIONS`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Preset {
+    #[default]
+    Vim,
+    Emacs,
+    // Arrows, Enter and Delete for the common actions, with more of them
+    // hinted in the footer
+    Arrows,
+}
+
+// Keys the presets bind over the defaults, taking them from other actions
+const EMACS: &[(Action, &[&str])] = &[
+    (Action::Next, &["ctrl+n", "down"]),
+    (Action::Previous, &["ctrl+p", "up"]),
+    (Action::Delete, &["ctrl+k"]),
+    (Action::Search, &["ctrl+s"]),
+    (Action::Save, &["ctrl+x ctrl+s"]),
+    (Action::Back, &["ctrl+g", "esc"]),
+];
+
+const ARROWS: &[(Action, &[&str])] = &[
+    (Action::Next, &["down"]),
+    (Action::Previous, &["up"]),
+    (Action::Toggle, &["enter", "space"]),
+    // Details in the list, the todo's page in the agenda
+    (Action::Open, &["v"]),
+    (Action::Delete, &["delete", "d"]),
+    (Action::NextPage, &["right", "tab"]),
+    (Action::PreviousPage, &["left", "backtab"]),
+];
+
+// A key with or without Ctrl
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Key {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+impl Key {
+    pub fn from_event(event: KeyEvent) -> Self {
+        Self {
+            code: event.code,
+            ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+        }
+    }
+
+    // "a", "A", "ctrl+r", "space", "enter", "esc", "tab", "backtab", "up", ...
+    pub fn parse(text: &str) -> Option<Self> {
+        let (ctrl, name) = match text.strip_prefix("ctrl+") {
+            Some(name) => (true, name),
+            None => (false, text),
+        };
+        let code = match name {
+            "space" => KeyCode::Char(' '),
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" | "shift+tab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return None,
+                }
+            }
+        };
+        Some(Self { code, ctrl })
+    }
+
+    // A key, or a chord of two keys pressed one after the other like "z a"
+    pub fn parse_chord(text: &str) -> Option<Vec<Self>> {
+        let keys: Vec<Self> = text.split(' ').map(Self::parse).collect::<Option<_>>()?;
+        (1..=2).contains(&keys.len()).then_some(keys)
+    }
+
+    // How the help shows the key
+    pub fn name(&self) -> String {
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "Shift+Tab".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
+            KeyCode::Delete => "Delete".to_string(),
+            KeyCode::Up => "Up".to_string(),
+            KeyCode::Down => "Down".to_string(),
+            KeyCode::Left => "Left".to_string(),
+            KeyCode::Right => "Right".to_string(),
+            KeyCode::PageUp => "PageUp".to_string(),
+            KeyCode::PageDown => "PageDown".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            other => format!("{:?}", other),
+        };
+        if self.ctrl {
+            format!("Ctrl+{}", name)
+        } else {
+            name
+        }
+    }
+}
+
+// Keys bound to each action, read from `keys` in the config file: either the
+// name of a preset or a table with an optional `preset` and keys for actions.
+// Keys given for an action replace the preset's ones and are taken from other
+// actions.
+//
+//     [keys]
+//     preset = "emacs"
+//     next = ["h", "down"]
+//     previous = "t"
+//     toggle-group = "z a"
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    // Single keys and chords of two keys
+    bindings: Vec<(Vec<Key>, Action)>,
+    pub preset: Preset,
+}
+
+impl Keymap {
+    pub fn action(&self, event: KeyEvent) -> Option<Action> {
+        self.bound(&[Key::from_event(event)])
+    }
+
+    // Action of the chord of `first` then `second`
+    pub fn chord(&self, first: KeyEvent, second: KeyEvent) -> Option<Action> {
+        self.bound(&[Key::from_event(first), Key::from_event(second)])
+    }
+
+    // Whether the key starts a chord, and so waits for the next key
+    pub fn starts_chord(&self, event: KeyEvent) -> bool {
+        let key = Key::from_event(event);
+        self.bindings
+            .iter()
+            .any(|(keys, _)| keys.len() > 1 && keys[0] == key)
+    }
+
+    fn bound(&self, keys: &[Key]) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == keys)
+            .map(|&(_, action)| action)
+    }
+
+    // Names of the keys and chords bound to the action, like "Ctrl+r" or "z a"
+    pub fn names(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|&&(_, bound)| bound == action)
+            .map(|(keys, _)| {
+                let names: Vec<String> = keys.iter().map(Key::name).collect();
+                names.join(" ")
+            })
+            .collect()
+    }
+
+    // The first key of the action for hints, "-" when it has none
+    pub fn hint(&self, action: Action) -> String {
+        self.names(action)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    // "key: label" hints for a footer, joined by " | ", the first keys of
+    // several actions by "/". Hints with an unbound action are left out, as
+    // are hints before the last one that no longer fit `width`.
+    pub fn footer(&self, hints: &[(&[Action], &str)], width: usize) -> String {
+        let hints = hints
+            .iter()
+            .filter(|(actions, _)| actions.iter().all(|&a| !self.names(a).is_empty()))
+            .map(|(actions, label)| {
+                let keys: Vec<String> = actions.iter().map(|&a| self.hint(a)).collect();
+                format!("{}: {}", keys.join("/"), label)
+            })
+            .collect();
+        help::fit(hints, width)
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let (preset, keys) = match name {
+            "vim" => (Preset::Vim, &[][..]),
+            "emacs" => (Preset::Emacs, EMACS),
+            "arrows" => (Preset::Arrows, ARROWS),
+            _ => return None,
+        };
+        let mut keymap = Keymap {
+            preset,
+            ..Keymap::default()
+        };
+        for &(action, keys) in keys {
+            let keys = keys
+                .iter()
+                .map(|key| Key::parse_chord(key).expect("valid preset key"))
+                .collect();
+            keymap.bind(action, keys);
+        }
+        Some(keymap)
+    }
+
+    fn bind(&mut self, action: Action, keys: Vec<Vec<Key>>) {
+        self.bindings
+            .retain(|(key, bound)| *bound != action && !keys.contains(key));
+        self.bindings
+            .extend(keys.into_iter().map(|key| (key, action)));
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .flat_map(|&(action, _, keys, ..)| {
+                keys.iter().map(move |key| {
+                    let keys = Key::parse_chord(key).expect("valid default key");
+                    (keys, action)
+                })
+            })
+            .collect();
+        Self {
+            bindings,
+            preset: Preset::Vim,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Keymap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Keys {
+            One(String),
+            Several(Vec<String>),
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Preset(String),
+            Table(BTreeMap<String, Keys>),
+        }
+
+        let (preset, table) = match Raw::deserialize(deserializer)? {
+            Raw::Preset(name) => (name, BTreeMap::new()),
+            Raw::Table(mut table) => match table.remove("preset") {
+                Some(Keys::One(name)) => (name, table),
+                Some(Keys::Several(_)) => {
+                    return Err(D::Error::custom("expected the name of one key preset"))
+                }
+                None => ("vim".to_string(), table),
+            },
+        };
+        let mut keymap = Keymap::preset(&preset).ok_or_else(|| {
+            D::Error::custom(format!(
+                "unknown key preset `{}`, expected one of {}",
+                preset,
+                PRESETS.join(", ")
+            ))
+        })?;
+        for (name, keys) in table {
+            let action = ACTIONS
+                .iter()
+                .find(|(_, action_name, ..)| *action_name == name)
+                .map(|&(action, ..)| action)
+                .ok_or_else(|| D::Error::custom(format!("unknown action `{}`", name)))?;
+            let keys = match keys {
+                Keys::One(key) => vec![key],
+                Keys::Several(keys) => keys,
+            };
+            let keys = keys
+                .iter()
+                .map(|key| {
+                    Key::parse_chord(key).ok_or_else(|| {
+                        D::Error::custom(format!("invalid key `{}` for {}", key, name))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            keymap.bind(action, keys);
+        }
+        Ok(keymap)
+    }
+}
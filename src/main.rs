@@ -1,46 +1,160 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    text::{Line, Span},
+    widgets::{
+        BarChart, Block, Borders, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Tabs, Wrap,
+    },
     Frame, Terminal,
 };
 use std::env;
 use std::error::Error;
+use std::fs;
 use std::io;
+use std::ops::Range;
+use std::process;
+use std::time::{Duration, Instant, SystemTime};
 
 // Import our own modules
+mod agenda;
+mod calendar;
+mod cli;
+mod clipboard;
+mod config;
+mod daemon;
+mod diff;
+mod filter;
+#[cfg(test)]
+mod headless;
+mod help;
+mod history;
+mod input;
+mod insights;
+mod keymap;
+mod merge;
+mod opener;
+mod ops;
+mod popup;
+mod scan;
+mod session;
+mod stats;
+mod storage;
+mod summary;
+#[cfg(test)]
+mod temp;
+mod template;
+mod theme;
 mod todo;
-use todo::{App, InputMode};
+mod update;
+use agenda::Bucket;
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate};
+use config::{Config, Density};
+use keymap::{Action, Key, Preset};
+use popup::ScrollPopup;
+use theme::Theme;
+use todo::{App, DueStatus, InputMode, PageType, Priority, Reschedule, Row, Todo, TodoPage, View};
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        process::exit(cli::EXIT_ERROR);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
     // Parse command line arguments
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // A blank throwaway list that is never read from or written to disk
+    let ephemeral = args.iter().any(|a| a == "--ephemeral");
+    args.retain(|a| a != "--ephemeral");
+
+    // Built-in theme, keys and settings, for getting back in when the config breaks the TUI
+    let safe = args.iter().any(|a| a == "--safe");
+    args.retain(|a| a != "--safe");
+
+    // Page names on the command line have to exist instead of asking to create them
+    let create = !args.iter().any(|a| a == "--no-create");
+    args.retain(|a| a != "--no-create");
+
+    // Commands that don't need the todo store loaded
+    match args.get(1).map(String::as_str) {
+        Some("diff") => return cli::diff(&args[2..]),
+        Some("prompt") => return cli::prompt(&args[2..]),
+        Some("status") => return cli::status(&args[2..]),
+        Some("config") => return cli::config(&args[2..]),
+        Some("--version") => return cli::version(&args[2..]),
+        Some("reset") => return cli::reset(&args[2..]),
+        Some("insights") => return cli::insights(&args[2..]),
+        Some("daemon") => return Ok(daemon::run()?),
+        Some("--print-default-config") => {
+            print!("{}", config::default_config());
+            return Ok(());
+        }
+        _ => {}
+    }
 
     // Create app instance
     let mut app = App::new();
+    if ephemeral {
+        app.storage = Box::new(storage::Memory::default());
+    } else if safe {
+        app.storage = Box::new(storage::Files { safe: true });
+    } else {
+        app.config = Config::load()?;
+    }
     app.load_todos()?;
 
-    // Handle CLI commands
+    // Commands that print or change the todos and exit
+    match args.get(1).map(String::as_str) {
+        Some("show") => {
+            // Print available todo pages and exit
+            println!("Available todo pages:");
+            for (idx, page) in app.pages.iter().enumerate() {
+                println!("  {}: {}", idx + 1, app.page_title(page));
+            }
+            return Ok(());
+        }
+        Some("merge") => return cli::merge(&mut app, &args[2..]),
+        Some("apply") => return cli::apply(&mut app, &args[2..]),
+        Some("list") => return cli::list(&app, &args[2..]),
+        Some("postpone") => return cli::postpone(&mut app, &args[2..]),
+        Some("scan") => return cli::scan(&mut app, &args[2..]),
+        Some("dashboard") => return dashboard(app),
+        _ => {}
+    }
+
+    // The TUI starts in the workspace matching its rules or left last time
+    app.restore_session()?;
+    // Before the command line picks a todo or page, which then stays selected
+    app.carry_over_new_day(Local::now().date_naive());
+    app.summarize_new_day(Local::now().date_naive());
+
     if args.len() > 1 {
         match args[1].as_str() {
-            "show" => {
-                // Print available todo pages and exit
-                println!("Available todo pages:");
-                for (idx, name) in app.page_names().iter().enumerate() {
-                    println!("  {}: {}", idx + 1, name);
-                }
-                return Ok(());
+            // Continue to the TUI on the linked todo
+            "open-id" => cli::open_id(&mut app, &args[2..])?,
+            link if link.starts_with(todo::LINK_PREFIX) => cli::open_id(&mut app, &args[1..])?,
+            // A page named "today" comes before the journal
+            "today" if !app.pages.iter().any(|p| p.name == "today") => {
+                app.open_journal(Local::now().date_naive())
             }
+            // Continue to the TUI on the page the list was added to
+            "--page" => cli::quick_list(&mut app, &args[2..], create)?,
             page_name => {
-                // Command is a page name - create or select that page
-                app.create_or_select_page(page_name);
+                // Command is a page name - select it, or create it once confirmed
+                cli::open_page(&mut app, page_name, create)?;
                 // Continue to the TUI
             }
         }
@@ -49,7 +163,12 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
@@ -60,7 +179,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -72,346 +192,967 @@ fn main() -> Result<(), Box<dyn Error>> {
 }
 
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+    let updates = app.config.update_check.then(update::check_in_background);
     loop {
+        if let Some(Ok(version)) = updates.as_ref().map(|updates| updates.try_recv()) {
+            app.update_available = Some(version);
+        }
+        app.refresh_date(Local::now().date_naive());
+        app.fire_reminders();
+        app.debug_check();
         terminal.draw(|f| ui(f, &mut app))?;
 
+        // Redraw every second so running timers and reminders stay current
+        let quit = if !event::poll(Duration::from_secs(1))? {
+            handle_idle(&mut app)
+        } else {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => handle_key(&mut app, key),
+                Event::Mouse(mouse) => {
+                    handle_mouse(&mut app, mouse);
+                    false
+                }
+                Event::Paste(text) => {
+                    handle_paste(&mut app, &text);
+                    false
+                }
+                _ => false,
+            }
+        };
+        if quit {
+            // A failed save keeps ratdo open with the error, unless it already
+            // failed before
+            match app.save_todos() {
+                Err(err) if !app.save_failed => {
+                    app.save_failed = true;
+                    app.notify_error(format!(
+                        "Could not save: {}. Quit again to leave without saving",
+                        err
+                    ));
+                    continue;
+                }
+                _ => {}
+            }
+            // Fields changed here and elsewhere are settled before leaving
+            if !app.conflicts.is_empty() {
+                continue;
+            }
+            app.save_session()?;
+            return Ok(());
+        }
+    }
+}
+
+// Read-only dashboard of `ratdo dashboard`, for a small tmux pane or another
+// monitor: never saves, and reloads the stores once their files change
+fn dashboard(mut app: App) -> Result<(), Box<dyn Error>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = run_dashboard(&mut terminal, &mut app);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(res?)
+}
+
+fn run_dashboard<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    // Modification times of the store files, checked every second
+    let modified = |app: &App| -> Vec<Option<SystemTime>> {
+        app.stores
+            .iter()
+            .map(|store| fs::metadata(&store.path).and_then(|m| m.modified()).ok())
+            .collect()
+    };
+    let mut seen = modified(app);
+    let mut updated = Local::now();
+    app.view = View::Agenda;
+    loop {
+        let today = Local::now().date_naive();
+        if modified(app) != seen || app.today != today {
+            app.today = today;
+            app.load_todos()?;
+            seen = modified(app);
+            updated = Local::now();
+        }
+        terminal.draw(|f| dashboard_ui(f, app, updated))?;
+
+        if !event::poll(Duration::from_secs(1))? {
+            continue;
+        }
         if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                match app.input_mode {
-                    InputMode::Normal => match key.code {
-                        KeyCode::Char('q') => {
-                            app.save_todos()?;
-                            return Ok(());
-                        }
-                        KeyCode::Char('e') => {
-                            if !app.todos().is_empty() {
-                                app.start_editing();
-                            }
-                        }
-                        KeyCode::Char('a') => {
-                            app.input_mode = InputMode::Editing;
-                            app.edit_mode = false; // Changed to false for adding new todos
-                            app.current_input = String::new();
-                        }
-                        KeyCode::Char('d') => app.delete_todo(),
-                        KeyCode::Char(' ') => app.toggle_todo(),
-                        KeyCode::Char('p') => {
-                            if !app.todos().is_empty() {
-                                app.toggle_picking_mode();
-                            }
-                        }
-                        KeyCode::Char('P') => {
-                            // Toggle page selector
-                            app.toggle_page_selector();
-                        }
-                        KeyCode::Tab => {
-                            // Switch to next page
-                            app.next_page();
-                        }
-                        KeyCode::BackTab => {
-                            // Switch to previous page
-                            app.previous_page();
-                        }
-                        KeyCode::Down => app.next(),
-                        KeyCode::Up => app.previous(),
-                        KeyCode::Char('j') => app.next(),
-                        KeyCode::Char('k') => app.previous(),
-                        _ => {}
-                    },
-                    InputMode::Editing => match key.code {
-                        KeyCode::Enter => {
-                            if app.show_page_selector && !app.current_input.is_empty() {
-                                // Add a new page
-                                app.add_page(app.current_input.clone());
-                                app.current_input.clear();
-                                app.show_page_selector = false;
-                                app.input_mode = InputMode::Normal;
-                            } else if app.edit_mode && !app.current_input.is_empty() {
-                                app.update_todo();
-                            } else if !app.current_input.is_empty() {
-                                app.add_todo();
-                            }
-                            app.input_mode = InputMode::Normal;
-                            app.edit_mode = false;
-                        }
-                        KeyCode::Char(c) => {
-                            app.current_input.push(c);
-                        }
-                        KeyCode::Backspace => {
-                            app.current_input.pop();
-                        }
-                        KeyCode::Esc => {
-                            app.input_mode = InputMode::Normal;
-                            app.edit_mode = false;
-                            app.show_page_selector = false;
-                        }
-                        _ => {}
-                    },
-                    InputMode::PageSelect => match key.code {
-                        KeyCode::Enter => {
-                            // Select the highlighted page
-                            if let Some(selected) = app.page_select_state.selected() {
-                                app.current_page_index = selected;
-                                app.show_page_selector = false;
-                                app.input_mode = InputMode::Normal;
-                            }
-                        }
-                        KeyCode::Char('n') | KeyCode::Char('a') => {
-                            // Create a new page from the page selector
-                            app.input_mode = InputMode::Editing;
-                            app.edit_mode = false;
-                            app.current_input = String::new();
-                            // Keep page selector flag true
-                        }
-                        KeyCode::Char('d') => {
-                            // Delete the selected page (if there's more than one)
-                            if app.pages.len() > 1 {
-                                if let Some(selected) = app.page_select_state.selected() {
-                                    app.pages.remove(selected);
-
-                                    // Adjust current page index if needed
-                                    if selected >= app.pages.len() {
-                                        app.page_select_state.select(Some(app.pages.len() - 1));
-                                    } else {
-                                        app.page_select_state.select(Some(selected));
-                                    }
-
-                                    // Update current_page_index to match the new selection
-                                    app.current_page_index =
-                                        app.page_select_state.selected().unwrap_or(0);
-
-                                    // Reset todo selection for the new page
-                                    let todo_count = app.todos().len();
-                                    if todo_count > 0 {
-                                        app.state.select(Some(0));
-                                    } else {
-                                        app.state.select(None);
-                                    }
-                                }
-                            }
-                        }
-                        KeyCode::Down | KeyCode::Char('j') => {
-                            // Navigate down in page list
-                            if !app.pages.is_empty() {
-                                let i = match app.page_select_state.selected() {
-                                    Some(i) => {
-                                        if i >= app.pages.len() - 1 {
-                                            0
-                                        } else {
-                                            i + 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app.page_select_state.select(Some(i));
-                            }
-                        }
-                        KeyCode::Up | KeyCode::Char('k') => {
-                            // Navigate up in page list
-                            if !app.pages.is_empty() {
-                                let i = match app.page_select_state.selected() {
-                                    Some(i) => {
-                                        if i == 0 {
-                                            app.pages.len() - 1
-                                        } else {
-                                            i - 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app.page_select_state.select(Some(i));
-                            }
-                        }
-                        KeyCode::Esc | KeyCode::Char('P') => {
-                            // Exit page select mode
-                            app.show_page_selector = false;
-                            app.input_mode = InputMode::Normal;
-                        }
-                        _ => {}
-                    },
+            let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL;
+            let quit = app.config.keys.action(key) == Some(Action::Quit);
+            if key.kind == KeyEventKind::Press && (ctrl_c || quit || key.code == KeyCode::Esc) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+// Counts of the open todos over the agenda, with the time of the last reload
+fn dashboard_ui(f: &mut Frame, app: &mut App, updated: DateTime<Local>) {
+    let theme = app.config.theme;
+    let [counts_area, agenda_area, footer_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ])
+    .areas(f.area());
+
+    let entries = app.agenda_entries();
+    let in_bucket = |bucket| entries.iter().filter(|e| e.bucket == bucket).count();
+    let open: usize = app.pages.iter().map(|p| stats::page_stats(p).open).sum();
+    let done_today = stats::completed_per_day(&app.pages, app.today, 1)[0].1;
+    let count = |n: usize, label: &str, color: Color| {
+        let style = match n {
+            0 => Style::default().fg(theme.muted),
+            _ => Style::default().fg(color).add_modifier(Modifier::BOLD),
+        };
+        vec![
+            Span::styled(n.to_string(), style),
+            Span::raw(format!(" {}  ", label)),
+        ]
+    };
+    let mut counts = vec![Span::styled("🐀 ", Style::default().fg(theme.title))];
+    counts.extend(count(in_bucket(Bucket::Overdue), "overdue", theme.overdue));
+    counts.extend(count(in_bucket(Bucket::Today), "today", theme.due_today));
+    counts.extend(count(open, "open", theme.title));
+    counts.extend(count(done_today as usize, "done today", theme.completed));
+    f.render_widget(Paragraph::new(Line::from(counts)), counts_area);
+
+    app.agenda_state.select(None);
+    render_agenda(f, app, agenda_area);
+
+    let footer = format!("Updated {} · q to quit", updated.format("%H:%M:%S"));
+    f.render_widget(
+        Paragraph::new(footer)
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Right),
+        footer_area,
+    );
+}
+
+// Apply a key press to the app, true when it asks to quit. Kept apart from
+// the terminal so scripted key presses can drive the app in tests.
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    // The first key of a chord like `z a` waits for the next one in the list
+    // and views. When that doesn't finish the chord, both act on their own.
+    if matches!(app.input_mode, InputMode::Normal) {
+        if let Some(first) = app.pending_key.take() {
+            if let Some(action) = app.config.keys.chord(first, key) {
+                return handle_action(app, key, Some(action));
+            }
+            if handle_pending_key(app, first) {
+                return true;
+            }
+        } else if app.config.keys.starts_chord(key) {
+            app.pending_key = Some(key);
+            app.set_status(format!("{} …", Key::from_event(key).name()));
+            return false;
+        }
+    }
+    handle_action(app, key, app.config.keys.action(key))
+}
+
+// Nothing was pressed for a second: the first key of a chord acts on its own
+// and a number goes to its page
+fn handle_idle(app: &mut App) -> bool {
+    match (app.pending_key.take(), app.count.take()) {
+        (Some(first), count) => {
+            app.count = count;
+            handle_pending_key(app, first)
+        }
+        (None, Some(number)) => {
+            app.go_to_page_number(number);
+            false
+        }
+        (None, None) => false,
+    }
+}
+
+// Let the waiting first key of a chord act on its own
+fn handle_pending_key(app: &mut App, first: KeyEvent) -> bool {
+    handle_action(app, first, app.config.keys.action(first))
+}
+
+// Apply a key press with the action it is bound to
+fn handle_action(app: &mut App, key: KeyEvent, action: Option<Action>) -> bool {
+    app.status_message = None;
+    // Popups opened from here start with the cursor at the end of their text
+    if matches!(app.input_mode, InputMode::Normal | InputMode::PageSelect) {
+        app.input_cursor = usize::MAX;
+    }
+    if action != Some(Action::RecentPage) {
+        app.end_recent_cycle();
+    }
+    // The page reached by the previous key counts as visited
+    app.note_page_visit();
+
+    // Lists and menus also move with the keys bound to next and previous
+    let menu = matches!(
+        app.input_mode,
+        InputMode::PageSelect
+            | InputMode::Templates
+            | InputMode::Links
+            | InputMode::TagFilter
+            | InputMode::Archive
+            | InputMode::Help
+    );
+    let key = match action {
+        Some(Action::Next) if menu => KeyEvent::new(KeyCode::Down, KeyModifiers::NONE),
+        Some(Action::Previous) if menu => KeyEvent::new(KeyCode::Up, KeyModifiers::NONE),
+        _ => key,
+    };
+    if let (InputMode::Normal, Some(action)) = (&app.input_mode, action) {
+        app.usage.count(action);
+    }
+
+    // A number typed in the list repeats next and previous, or picks the todo
+    // for first and last, like 5j or 12G in vim. Followed by anything else it
+    // goes to the page with that number as printed by `ratdo show`, 0 to the
+    // last page.
+    let count = app.count.take();
+    if let (InputMode::Normal, View::List, None, KeyCode::Char(digit @ '0'..='9')) =
+        (&app.input_mode, app.view, action, key.code)
+    {
+        let count = count.unwrap_or(0).saturating_mul(10) + (digit as usize - '0' as usize);
+        app.count = Some(count);
+        app.set_status(format!("{} …", count));
+        return false;
+    }
+    let count = match (count, action) {
+        (Some(_), Some(Action::Next | Action::Previous | Action::First | Action::Last)) => count,
+        (Some(_), Some(Action::Back)) => return false,
+        (Some(number), _) => {
+            app.go_to_page_number(number);
+            None
+        }
+        (None, _) => None,
+    };
+
+    match app.input_mode {
+        InputMode::Normal if app.focus => match action {
+            Some(Action::Quit) => return true,
+            Some(Action::Toggle) => app.focus_done(),
+            Some(Action::Next) => app.focus_skip(true),
+            Some(Action::Previous) => app.focus_skip(false),
+            Some(Action::Edit) => app.start_editing(),
+            Some(Action::Notes) => app.start_editing_notes(),
+            Some(Action::Timer) => app.toggle_timer(),
+            Some(Action::Undo) => app.undo(),
+            Some(Action::Redo) => app.redo(),
+            Some(Action::Help) => app.open_help(),
+            Some(Action::Save) => app.save(),
+            Some(Action::Back | Action::Focus) => app.toggle_focus(),
+            _ => {}
+        },
+        InputMode::Normal if app.view != View::List => match action {
+            Some(Action::Quit) => return true,
+            Some(Action::Next) => app.agenda_next(),
+            Some(Action::Previous) => app.agenda_previous(),
+            Some(Action::First) => app.agenda_edge(false),
+            Some(Action::Last) => app.agenda_edge(true),
+            Some(Action::Toggle) => app.complete_agenda_todo(),
+            Some(Action::DueDate) => app.start_editing_due_date(),
+            Some(Action::Schedule) => app.start_editing_scheduled(),
+            Some(Action::Reminder) => app.start_editing_reminder(),
+            Some(Action::Reschedule) => app.start_reschedule(),
+            Some(Action::GoToPage) => app.start_page_switcher(),
+            Some(Action::Postpone) => app.start_postpone(),
+            Some(Action::Undo) => app.undo(),
+            Some(Action::Redo) => app.redo(),
+            Some(Action::OpenLinks) => app.open_links(),
+            Some(Action::Yank) => app.yank(false),
+            Some(Action::YankWithNotes) => app.yank(true),
+            Some(Action::Attach) => app.start_attaching(),
+            Some(Action::NeedsReview) => app.toggle_review(),
+            Some(Action::Help) => app.open_help(),
+            Some(Action::DaySummary) => app.open_day_summary(),
+            Some(Action::Save) => app.save(),
+            Some(Action::Open) => app.open_agenda_todo(),
+            Some(Action::Back) => app.toggle_view(app.view),
+            Some(Action::Agenda) => app.toggle_view(View::Agenda),
+            Some(Action::Review) => app.toggle_view(View::Review),
+            Some(Action::Calendar) => app.toggle_view(View::Calendar),
+            Some(Action::Stats) => app.toggle_view(View::Stats),
+            // Days, weeks and months of the calendar
+            Some(Action::NextDay | Action::NextPage) if app.view == View::Calendar => {
+                app.move_calendar(1, 0)
+            }
+            Some(Action::PreviousDay | Action::PreviousPage) if app.view == View::Calendar => {
+                app.move_calendar(-1, 0)
+            }
+            Some(Action::HalfPageDown) if app.view == View::Calendar => app.move_calendar(7, 0),
+            Some(Action::HalfPageUp) if app.view == View::Calendar => app.move_calendar(-7, 0),
+            Some(Action::PageDown) if app.view == View::Calendar => app.move_calendar(0, 1),
+            Some(Action::PageUp) if app.view == View::Calendar => app.move_calendar(0, -1),
+            _ => {}
+        },
+        InputMode::Normal => match action {
+            Some(Action::Quit) => return true,
+            Some(Action::Edit) if !app.todos().is_empty() => app.start_editing(),
+            Some(Action::Add) => {
+                app.input_mode = InputMode::Editing;
+                app.edit_mode = false; // Changed to false for adding new todos
+                app.current_input = String::new();
+            }
+            Some(Action::Delete) => app.request_delete_todo(),
+            Some(Action::GoToPage) => app.start_page_switcher(),
+            Some(Action::JumpToTodo) => app.start_todo_jump(),
+            Some(Action::RecentPage) => app.cycle_recent_page(),
+            Some(Action::Undo) => app.undo(),
+            Some(Action::Redo) => app.redo(),
+            Some(Action::Archive) => app.archive_todo(),
+            Some(Action::ArchiveCompleted) => app.archive_completed(),
+            Some(Action::CarryOver) => app.carry_over(),
+            Some(Action::ArchiveBrowser) => app.start_archive_browser(),
+            Some(Action::Toggle) => app.toggle_todo(),
+            Some(Action::DueDate) => app.start_editing_due_date(),
+            Some(Action::Schedule) => app.start_editing_scheduled(),
+            Some(Action::Reminder) => app.start_editing_reminder(),
+            Some(Action::Reschedule) => app.start_reschedule(),
+            Some(Action::Timer) => app.toggle_timer(),
+            Some(Action::OpenLinks) => app.open_links(),
+            Some(Action::Yank) => app.yank(false),
+            Some(Action::YankWithNotes) => app.yank(true),
+            Some(Action::Import) => app.start_import(),
+            Some(Action::Attach) => app.start_attaching(),
+            Some(Action::Comment) => app.start_commenting(),
+            Some(Action::Pin) => app.toggle_pin(),
+            Some(Action::Snooze) => app.start_deferring(),
+            Some(Action::ShowSnoozed) => app.toggle_show_deferred(),
+            Some(Action::LargeText) => app.toggle_large_text(),
+            Some(Action::Focus) => app.toggle_focus(),
+            Some(Action::Density) => app.toggle_density(),
+            Some(Action::Group) => app.cycle_grouping(),
+            Some(Action::ToggleGroup) => app.toggle_group(),
+            Some(Action::DoneSection) => app.toggle_done_section(),
+            Some(Action::Help) => app.open_help(),
+            Some(Action::DaySummary) => app.open_day_summary(),
+            Some(Action::Save) => app.save(),
+            Some(Action::TagFilter) => app.start_tag_filter(),
+            Some(Action::Details | Action::Open) => {
+                app.show_details = !app.show_details;
+            }
+            Some(Action::Notes) => app.start_editing_notes(),
+            Some(Action::Agenda) => app.toggle_view(View::Agenda),
+            Some(Action::Review) => app.toggle_view(View::Review),
+            Some(Action::Calendar) => app.toggle_view(View::Calendar),
+            Some(Action::Stats) => app.toggle_view(View::Stats),
+            Some(Action::NeedsReview) => app.toggle_review(),
+            Some(Action::Workspace) => app.next_workspace(),
+            Some(Action::SaveTemplate) => app.start_saving_template(),
+            Some(Action::InsertTemplate) => app.start_template_picker(),
+            Some(Action::Search) => app.start_search(),
+            Some(Action::NextMatch) => app.search_next(true),
+            Some(Action::PreviousMatch) => app.search_next(false),
+            Some(Action::Back) if !app.marked.is_empty() => app.clear_marks(),
+            Some(Action::Back) if app.search.is_some() => app.clear_search(),
+            Some(Action::Back) => app.clear_tag_filter(),
+            Some(Action::Checklist) => app.toggle_checklist_mode(),
+            Some(Action::Quantities) => app.toggle_quantities(),
+            Some(Action::Increase) => app.adjust_quantity(true),
+            Some(Action::Decrease) => app.adjust_quantity(false),
+            Some(Action::Move) if !app.todos().is_empty() => app.toggle_picking_mode(),
+            Some(Action::Mark) => app.toggle_mark(),
+            Some(Action::Visual) => app.toggle_visual(),
+            Some(Action::MoveToPage) => app.start_moving_to_page(),
+            Some(Action::Tag) => app.start_tagging(),
+            Some(Action::PageList) => {
+                // Toggle page selector
+                app.toggle_page_selector();
+            }
+            Some(Action::NextPage) => {
+                // Switch to next page
+                app.next_page();
+            }
+            Some(Action::PreviousPage) => {
+                // Switch to previous page
+                app.previous_page();
+            }
+            Some(Action::Next) => match count {
+                Some(count) => app.move_by(count, true),
+                None => app.next(),
+            },
+            Some(Action::Previous) => match count {
+                Some(count) => app.move_by(count, false),
+                None => app.previous(),
+            },
+            Some(Action::First | Action::Last) if count.is_some() => {
+                app.go_to_todo(count.unwrap_or(1))
+            }
+            Some(Action::First) => app.move_by(usize::MAX, false),
+            Some(Action::Last) => app.move_by(usize::MAX, true),
+            Some(Action::FirstOpen) => app.first_open(),
+            Some(Action::PageDown) => app.jump_lines(app.page_lines() as isize),
+            Some(Action::PageUp) => app.jump_lines(-(app.page_lines() as isize)),
+            Some(Action::HalfPageDown) => app.jump_lines(app.page_lines().div_ceil(2) as isize),
+            Some(Action::HalfPageUp) => app.jump_lines(-(app.page_lines().div_ceil(2) as isize)),
+            _ => {}
+        },
+        InputMode::Editing => match key.code {
+            KeyCode::Enter => {
+                if app.show_page_selector {
+                    if !app.current_input.is_empty() {
+                        // Add a new page
+                        app.add_page(app.current_input.clone(), app.new_page_type);
+                        app.current_input.clear();
+                        app.show_page_selector = false;
+                    }
+                } else if !app.validate_todo_input() {
+                    // Keep the popup open so the input can be fixed
+                    return false;
+                } else if app.edit_mode {
+                    app.update_todo();
+                } else {
+                    app.add_todo();
+                }
+                app.input_mode = InputMode::Normal;
+                app.edit_mode = false;
+            }
+            KeyCode::Tab if app.show_page_selector => {
+                app.new_page_type = app.new_page_type.next();
+            }
+            // Complete the `/page` word from the list under the popup
+            KeyCode::Tab => {
+                app.complete_page();
+            }
+            KeyCode::Down => app.select_page_completion(true),
+            KeyCode::Up => app.select_page_completion(false),
+            KeyCode::Esc => {
+                app.input_mode = InputMode::Normal;
+                app.edit_mode = false;
+                app.show_page_selector = false;
+                app.confirmed_long_input = None;
+            }
+            _ => {
+                if app.edit_input(key) {
+                    app.reset_page_completion();
+                }
+            }
+        },
+        InputMode::DueDate | InputMode::Scheduled | InputMode::Reminder | InputMode::Defer => {
+            match key.code {
+                KeyCode::Enter if app.update_date() => {
+                    app.input_mode = InputMode::Normal;
+                }
+                KeyCode::Esc => {
+                    app.current_input.clear();
+                    app.input_mode = InputMode::Normal;
+                }
+                _ => {
+                    app.edit_input(key);
+                }
+            }
+        }
+        InputMode::Reschedule => {
+            if let KeyCode::Char(c) = key.code {
+                if let Some(option) = Reschedule::from_key(c) {
+                    app.reschedule(option);
+                }
+            }
+            app.input_mode = InputMode::Normal;
+        }
+        InputMode::Postpone => match key.code {
+            KeyCode::Enter if app.postpone_overdue() => {
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                app.current_input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {
+                app.edit_input(key);
+            }
+        },
+        InputMode::CompletionNote => match key.code {
+            KeyCode::Enter if app.complete_with_note() => {
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                app.current_input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {
+                app.edit_input(key);
+            }
+        },
+        InputMode::ConfirmDelete => match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => app.confirm_delete(true),
+            KeyCode::Char('n') | KeyCode::Esc => app.confirm_delete(false),
+            _ => {}
+        },
+        InputMode::Tag => match key.code {
+            KeyCode::Enter if app.apply_tags() => {
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                app.current_input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {
+                app.edit_input(key);
+            }
+        },
+        InputMode::Comment => match key.code {
+            KeyCode::Enter => {
+                app.add_comment();
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                app.current_input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {
+                app.edit_input(key);
+            }
+        },
+        InputMode::Attach => match key.code {
+            KeyCode::Enter => {
+                app.add_attachment();
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                app.current_input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {
+                app.edit_input(key);
+            }
+        },
+        InputMode::TemplateName => match key.code {
+            KeyCode::Enter => {
+                app.save_template();
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                app.current_input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {
+                app.edit_input(key);
+            }
+        },
+        InputMode::PageSwitch | InputMode::TodoJump => match key.code {
+            KeyCode::Enter if matches!(app.input_mode, InputMode::TodoJump) => {
+                app.jump_to_todo_match()
+            }
+            KeyCode::Enter => app.switch_to_page_match(),
+            KeyCode::Down | KeyCode::Tab => app.select_page_match(true),
+            KeyCode::Up | KeyCode::BackTab => app.select_page_match(false),
+            KeyCode::Esc => {
+                app.current_input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {
+                if app.edit_input(key) {
+                    app.reset_page_switch();
+                }
+            }
+        },
+        InputMode::DaySummary => match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                app.input_mode = InputMode::Normal
+            }
+            code => popup::scroll(&mut app.summary_scroll, code),
+        },
+        InputMode::Help => match key.code {
+            KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q') => {
+                app.input_mode = InputMode::Normal
+            }
+            code => popup::scroll(&mut app.help_scroll, code),
+        },
+        InputMode::PageIcon => match key.code {
+            KeyCode::Enter => app.set_page_icon(),
+            KeyCode::Esc => {
+                app.current_input.clear();
+                app.input_mode = InputMode::PageSelect;
+            }
+            _ => {
+                app.edit_input(key);
+            }
+        },
+        InputMode::Templates => match key.code {
+            KeyCode::Enter => {
+                app.insert_template();
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char('d') => app.delete_template(),
+            KeyCode::Esc | KeyCode::Char('i') => app.input_mode = InputMode::Normal,
+            code => popup::navigate(&mut app.template_select_state, app.templates.len(), code),
+        },
+        InputMode::Import => match key.code {
+            KeyCode::Enter => {
+                app.import_todos();
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char('d') => app.drop_import_line(),
+            KeyCode::Esc => app.input_mode = InputMode::Normal,
+            code => popup::navigate(&mut app.import_select_state, app.import.len(), code),
+        },
+        InputMode::Conflicts => match key.code {
+            KeyCode::Char('m') => app.resolve_conflict(false),
+            KeyCode::Char('t') => app.resolve_conflict(true),
+            KeyCode::Esc => app.keep_newest(),
+            code => popup::navigate(&mut app.conflict_select_state, app.conflicts.len(), code),
+        },
+        InputMode::Archive => match key.code {
+            KeyCode::Enter | KeyCode::Char('r') => app.take_archived(true),
+            KeyCode::Char('d') => app.take_archived(false),
+            KeyCode::Esc | KeyCode::Char('H') => app.input_mode = InputMode::Normal,
+            code => {
+                let count = app.current_page().archived.len();
+                popup::navigate(&mut app.archive_select_state, count, code)
+            }
+        },
+        InputMode::Links => match key.code {
+            KeyCode::Enter => {
+                if let Some(selected) = app.link_select_state.selected() {
+                    app.open_link(selected);
+                }
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char('d') => app.remove_attachment(),
+            KeyCode::Esc | KeyCode::Char('o') => app.input_mode = InputMode::Normal,
+            code => {
+                let count = app.links().len();
+                popup::navigate(&mut app.link_select_state, count, code)
+            }
+        },
+        InputMode::Notes => match key.code {
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.update_notes();
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Enter => {
+                app.edit_input(KeyEvent::new(KeyCode::Char('\n'), KeyModifiers::NONE));
+            }
+            KeyCode::Esc => {
+                app.current_input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {
+                app.edit_input(key);
+            }
+        },
+        InputMode::Search => match key.code {
+            KeyCode::Enter => app.finish_search(),
+            KeyCode::Char(c) => app.search_push(c),
+            KeyCode::Backspace => app.search_pop(),
+            KeyCode::Esc => app.clear_search(),
+            KeyCode::Down => app.next(),
+            KeyCode::Up => app.previous(),
+            _ => {}
+        },
+        InputMode::TagFilter => match key.code {
+            KeyCode::Enter => app.apply_tag_filter(),
+            KeyCode::Esc | KeyCode::Char('f') => {
+                // Leave the picker and drop any active filter
+                app.clear_tag_filter();
+                app.input_mode = InputMode::Normal;
+            }
+            code => {
+                let count = app.current_page().tags().len();
+                popup::navigate(&mut app.tag_select_state, count, code)
+            }
+        },
+        InputMode::PageSelect => match key.code {
+            KeyCode::Enter => {
+                // Select the highlighted page
+                if let Some(selected) = app.page_select_state.selected() {
+                    app.select_page(selected);
+                    app.show_page_selector = false;
+                    app.input_mode = InputMode::Normal;
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('a') => {
+                // Create a new page from the page selector
+                app.input_mode = InputMode::Editing;
+                app.edit_mode = false;
+                app.current_input = String::new();
+                app.new_page_type = PageType::default();
+                // Keep page selector flag true
+            }
+            KeyCode::Char('i') => app.start_editing_page_icon(),
+            // Delete the selected page (if there's more than one)
+            KeyCode::Char('d') => {
+                if let Some(selected) = app.page_select_state.selected() {
+                    app.request_delete_page(selected);
                 }
             }
+            KeyCode::Esc | KeyCode::Char('P') => {
+                // Exit page select mode
+                app.show_page_selector = false;
+                app.input_mode = InputMode::Normal;
+            }
+            code => popup::navigate(&mut app.page_select_state, app.pages.len(), code),
+        },
+    }
+    false
+}
+
+// Text pasted into the terminal goes into the open input popup at the cursor,
+// on one line except in the notes. Outside of popups it is dropped.
+fn handle_paste(app: &mut App, text: &str) {
+    // Pasted into the list, the lines are previewed for import like with I
+    if let (InputMode::Normal, View::List) = (&app.input_mode, app.view) {
+        app.preview_import(text);
+        return;
+    }
+    let text = match app.input_mode {
+        InputMode::Notes => text.replace("\r\n", "\n").replace('\r', "\n"),
+        _ => text.split_whitespace().collect::<Vec<_>>().join(" "),
+    };
+    match app.input_mode {
+        InputMode::Search => text.chars().for_each(|c| app.search_push(c)),
+        InputMode::Editing => {
+            app.paste_input(&text);
+            app.reset_page_completion();
+        }
+        InputMode::PageSwitch | InputMode::TodoJump => {
+            app.paste_input(&text);
+            app.reset_page_switch();
+        }
+        InputMode::DueDate
+        | InputMode::Scheduled
+        | InputMode::Reminder
+        | InputMode::Defer
+        | InputMode::Postpone
+        | InputMode::CompletionNote
+        | InputMode::Tag
+        | InputMode::Comment
+        | InputMode::Attach
+        | InputMode::TemplateName
+        | InputMode::PageIcon
+        | InputMode::Notes => app.paste_input(&text),
+        _ => {}
+    }
+}
+
+// Second click on a row within this long toggles the todo
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+// Clicking a todo selects it, clicking its checkbox or clicking it twice in a
+// row toggles it, and the wheel moves the selection. Only the todo list
+// reacts, not the agenda, popups or move mode.
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    if !matches!(app.input_mode, InputMode::Normal)
+        || app.view != View::List
+        || app.picking_mode
+        || app.focus
+    {
+        return;
+    }
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            let Some(row) = app.row_at(mouse.column, mouse.row) else {
+                return;
+            };
+            app.status_message = None;
+            let now = Instant::now();
+            let again = app
+                .last_click
+                .is_some_and(|(at, last)| last == row && now - at < DOUBLE_CLICK);
+            // Border, highlight symbol and " [ ]"
+            let on_checkbox = mouse.column < app.list_area.x + 8;
+            app.state.select(Some(row));
+            if matches!(app.rows().get(row), Some(Row::Group(..))) {
+                // Clicking a group header folds it or opens it up
+                app.last_click = None;
+                app.toggle_group();
+            } else if again || on_checkbox {
+                app.last_click = None;
+                app.toggle_todo();
+            } else {
+                app.last_click = Some((now, row));
+            }
+        }
+        // Stop at the ends instead of wrapping around like j/k
+        MouseEventKind::ScrollDown
+            if app
+                .state
+                .selected()
+                .is_some_and(|row| row + 1 < app.rows().len()) =>
+        {
+            app.next()
         }
+        MouseEventKind::ScrollUp if app.state.selected().is_some_and(|row| row > 0) => {
+            app.previous()
+        }
+        _ => {}
     }
 }
 
 fn ui(f: &mut Frame, app: &mut App) {
+    let theme = app.config.theme;
+    // The arrows key preset hints at more keys, on two lines
+    let beginner = app.config.keys.preset == Preset::Arrows;
     // Create a layout
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(2)
         .constraints(
             [
-                Constraint::Length(1), // Title
-                Constraint::Min(1),    // Todos list
-                Constraint::Length(3), // Help
+                Constraint::Length(1),                            // Title
+                Constraint::Min(1),                               // Todos list
+                Constraint::Length(1),                            // Status line
+                Constraint::Length(if beginner { 4 } else { 3 }), // Help
             ]
             .as_ref(),
         )
         .split(f.area());
 
     // Title with page name
-    let page_name = &app.current_page().name;
-    let title = Paragraph::new(format!("[ To Do 🐀: {} ]", page_name))
-        .style(Style::default().fg(Color::Yellow))
-        .alignment(Alignment::Center)
-        .block(Block::default());
-    f.render_widget(title, chunks[0]);
-
-    // Todos
-    let todos: Vec<ListItem> = app
-        .todos()
-        .iter()
-        .enumerate() // Get index with item
-        .map(|(i, todo)| {
-            let status = if todo.completed { "[x]" } else { "[ ]" };
-
-            let content = if app.picking_mode && Some(i) == app.state.selected() {
-                // Show a moving indicator when in picking mode and this is the selected todo
-                format!(" {} {}", status, todo.description)
-            } else {
-                format!(" {} {}", status, todo.description)
-            };
+    let page = app.current_page();
+    let mut mode_markers = String::new();
+    if page.settings.checklist_mode {
+        mode_markers.push_str(" ☑");
+    }
+    if page.settings.quantities {
+        mode_markers.push_str(" #");
+    }
+    if page.settings.habit_mode {
+        mode_markers.push_str(" ↻");
+    }
+    if let (Some(query), View::List) = (&app.search, app.view) {
+        mode_markers.push_str(&format!(" /{}", query));
+    }
+    if let Some(label) = app.storage.label() {
+        mode_markers.push_str(&format!(" ({})", label));
+    }
+    let remaining = page.remaining_estimate();
+    if remaining > 0 {
+        mode_markers.push_str(&format!(" · ~{} left", todo::format_estimate(remaining)));
+    }
+    let title = match app.view {
+        View::List => None,
+        View::Agenda => Some("Agenda"),
+        View::Review => Some("Review"),
+        View::Calendar => Some("Calendar"),
+        View::Stats => Some("Stats"),
+    };
+    if let Some(title) = title {
+        let title = Paragraph::new(format!("[ To Do 🐀: {} ]", title))
+            .style(Style::default().fg(theme.title))
+            .alignment(Alignment::Center)
+            .block(Block::default());
+        f.render_widget(title, chunks[0]);
+    } else {
+        // Tabs of the pages, then the markers and progress of the current one
+        let mut status = vec![Span::raw(mode_markers)];
+        status.extend(progress_spans(&theme, page));
+        let status = Line::from(status).style(Style::default().fg(theme.title));
+        let prefix = Line::from(match app.workspace_name() {
+            Some(name) => format!("🐀 {} /", name),
+            None => "🐀".to_string(),
+        });
+        let [prefix_area, tabs_area, status_area] = Layout::horizontal([
+            Constraint::Length(prefix.width() as u16),
+            Constraint::Min(0),
+            Constraint::Length(status.width() as u16),
+        ])
+        .areas(chunks[0]);
+        f.render_widget(
+            Paragraph::new(prefix).style(Style::default().fg(theme.title)),
+            prefix_area,
+        );
+        render_page_tabs(f, app, tabs_area);
+        f.render_widget(Paragraph::new(status), status_area);
+    }
 
-            let style = if todo.completed {
-                Style::default()
-                    .fg(Color::Gray)
-                    .add_modifier(Modifier::CROSSED_OUT)
-            } else {
-                Style::default()
-            };
+    // Split off the detail pane next to the list when it is open
+    let (list_area, detail_area) = if app.show_details {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(chunks[1]);
+        (columns[0], Some(columns[1]))
+    } else {
+        (chunks[1], None)
+    };
 
-            ListItem::new(Span::styled(content, style))
-        })
-        .collect();
+    match app.view {
+        View::List if app.focus => render_focus(f, app, list_area),
+        View::List => render_todo_list(f, app, list_area),
+        View::Agenda | View::Review => render_agenda(f, app, list_area),
+        View::Calendar => render_calendar(f, app, list_area),
+        View::Stats => render_stats(f, app, list_area),
+    }
 
-    let todos = List::new(todos)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(if app.picking_mode {
-                    "Moving Todo (Navigate with j/k)"
-                } else {
-                    "Todos"
-                }),
-        )
-        .highlight_style(if app.picking_mode {
-            // Use a different highlight style when picking
-            Style::default().fg(Color::Black).bg(Color::Yellow)
-        } else {
-            Style::default().fg(Color::LightYellow)
-        })
-        .highlight_symbol(if app.picking_mode {
-            " >>" // Different symbol when picking
-        } else {
-            " > "
-        });
+    if let Some(detail_area) = detail_area {
+        render_details(f, app, detail_area);
+    }
 
-    f.render_stateful_widget(todos, chunks[1], &mut app.state);
+    render_status_line(f, app, chunks[2]);
 
-    // Help
-    let help_text = match app.input_mode {
-        InputMode::Normal => {
-            if app.picking_mode {
-                "p: Exit Move Mode | j/k: Move Item Up/Down"
-            } else {
-                "q: Quit | e: Edit | a: Add | d: Delete | P: Page List | Tab/Shift+Tab: Switch Page | p: Move | Space: Toggle | j/k: Navigate"
-            }
-        }
-        InputMode::Editing => {
-            if app.show_page_selector {
-                "Esc: Cancel | Enter: Create Page"
-            } else {
-                "Esc: Cancel | Enter: Save"
-            }
-        }
-        InputMode::PageSelect => {
-            "Esc: Cancel | Enter: Select Page | n/a: New Page | d: Delete Page | j/k: Navigate"
+    // Help, with the configured keys in the list and views and the keys of
+    // the open popup otherwise
+    let keys = &app.config.keys;
+    let width = chunks[3].width.saturating_sub(2) as usize;
+    let help_text = match help::popup_section(app) {
+        Some(section) => help::bar(section, width),
+        None if beginner => {
+            let (first, second) = BEGINNER_HINTS.split_at(5);
+            format!(
+                "{}\n{}",
+                keys.footer(first, width),
+                keys.footer(second, width)
+            )
         }
+        None => keys.footer(&view_hints(app), width),
     };
 
     let help = Paragraph::new(help_text)
-        .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL).title("Help"));
-    f.render_widget(help, chunks[2]);
+        .style(Style::default().fg(theme.muted))
+        .block(bordered(&theme).title("Help"));
+    f.render_widget(help, chunks[3]);
 
     // Render the page selector if active
     if app.show_page_selector {
-        // Create a centered popup for the page selector
-        let area = f.area();
-        let popup_width = area.width.min(30).max(50);
-        let popup_height = app.pages.len() as u16 + 2;
-        let popup_x = (area.width.saturating_sub(popup_width)) / 2;
-        let popup_y = (area.height.saturating_sub(popup_height)) / 2;
-
-        let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
-
-        // Create a clear background for the popup
-        let clear = ratatui::widgets::Clear;
-        f.render_widget(clear, popup_area);
-
-        // Create page items
+        // Page list, a colored dot per page and its open/total todos
         let page_items: Vec<ListItem> = app
             .pages
             .iter()
             .map(|page| {
-                ListItem::new(Span::styled(
-                    &page.name,
-                    if page.name == app.current_page().name {
-                        Style::default().fg(Color::Yellow)
-                    } else {
-                        Style::default()
-                    },
-                ))
+                let open = page.todos.iter().filter(|t| !t.completed).count();
+                ListItem::new(Line::from(vec![
+                    Span::styled("● ", Style::default().fg(name_color(&page.name))),
+                    Span::styled(
+                        app.page_title(page),
+                        if page.name == app.current_page().name {
+                            Style::default().fg(theme.heading)
+                        } else {
+                            Style::default()
+                        },
+                    ),
+                    Span::styled(
+                        format!(" {}/{}", open, page.todos.len()),
+                        Style::default().fg(theme.muted),
+                    ),
+                ]))
             })
             .collect();
 
-        // Page list widget
-        let pages_list = List::new(page_items)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Select Page (n/a: New, d: Delete)"),
-            )
-            .highlight_style(Style::default().fg(Color::LightYellow))
-            .highlight_symbol(" > ");
-
-        f.render_stateful_widget(pages_list, popup_area, &mut app.page_select_state);
+        ScrollPopup::new(&theme, "Select Page (n/a: New, i: Icon, d: Delete)")
+            .width(50)
+            .render_list(f, page_items, &mut app.page_select_state);
     }
 
     // Render the input popup when in editing mode
     if let InputMode::Editing = app.input_mode {
         if !app.show_page_selector {
-            // Create a centered popup for the input
-            let area = f.area();
-            let popup_width = area.width.saturating_sub(40);
-            let popup_height = 3;
-            let popup_x = (area.width.saturating_sub(popup_width)) / 2;
-            let popup_y = (area.height.saturating_sub(popup_height)) / 2;
-
-            let popup_area =
-                ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
-
-            // Create a clear background for the popup
-            let clear = ratatui::widgets::Clear;
-            f.render_widget(clear, popup_area);
-
-            // Input popup
             let input_title = if app.edit_mode {
                 "Edit Todo"
             } else {
                 "Add Todo"
             };
-            let input = Paragraph::new(app.current_input.as_str())
-                .style(Style::default().fg(Color::Yellow))
-                .block(Block::default().borders(Borders::ALL).title(input_title));
-            f.render_widget(input, popup_area);
-
-            // Set cursor position within the popup
-            f.set_cursor_position((
-                popup_area.x + app.current_input.len() as u16 + 1,
-                popup_area.y + 1,
-            ));
+            render_input_popup(f, &theme, input_title, &app.current_input, app.input_cursor);
+            render_page_completions(f, app);
         } else {
             // Show the page creation popup
             let area = f.area();
@@ -423,25 +1164,1711 @@ fn ui(f: &mut Frame, app: &mut App) {
             let popup_area =
                 ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
 
-            // Create a clear background for the popup
-            let clear = ratatui::widgets::Clear;
-            f.render_widget(clear, popup_area);
-
             // New page popup
-            let input = Paragraph::new(app.current_input.as_str())
-                .style(Style::default().fg(Color::Yellow))
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("New Page Name"),
-                );
-            f.render_widget(input, popup_area);
-
-            // Set cursor position within the popup
-            f.set_cursor_position((
-                popup_area.x + app.current_input.len() as u16 + 1,
-                popup_area.y + 1,
-            ));
+            let input_title = format!("New {} Page", app.new_page_type.name());
+            render_input_at(
+                f,
+                &theme,
+                &input_title,
+                &app.current_input,
+                app.input_cursor,
+                popup_area,
+            );
         }
     }
+
+    if let InputMode::TagFilter = app.input_mode {
+        // Tag picker popup, sized to the tags of the page
+        let tag_items: Vec<ListItem> = app
+            .current_page()
+            .tags()
+            .iter()
+            .map(|tag| ListItem::new(format!("+{}", tag)))
+            .collect();
+        ScrollPopup::new(&theme, "Filter by Tag")
+            .width(30)
+            .render_list(f, tag_items, &mut app.tag_select_state);
+    }
+
+    if let InputMode::Notes = app.input_mode {
+        // Larger popup for multi-line notes
+        let area = f.area();
+        let popup_width = area.width.saturating_sub(20);
+        let popup_height = area.height.saturating_sub(10).clamp(3, 15);
+        let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+        let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+        let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+        f.render_widget(ratatui::widgets::Clear, popup_area);
+
+        let notes = Paragraph::new(app.current_input.as_str())
+            .style(Style::default().fg(theme.popup))
+            .block(bordered(&theme).title("Notes"));
+        f.render_widget(notes, popup_area);
+
+        let line = input::cursor_line(&app.current_input, app.input_cursor);
+        f.set_cursor_position((
+            popup_area.x + input::cursor_column(&app.current_input, app.input_cursor) + 1,
+            popup_area.y + (line + 1).min(popup_height.saturating_sub(2)),
+        ));
+    }
+
+    if let InputMode::DueDate = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "Due Date (YYYY-MM-DD [HH:MM])",
+            &app.current_input,
+            app.input_cursor,
+        );
+    }
+
+    if let InputMode::Defer = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "Snooze Until (YYYY-MM-DD)",
+            &app.current_input,
+            app.input_cursor,
+        );
+    }
+
+    if let InputMode::Reminder = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "Remind At (YYYY-MM-DD HH:MM)",
+            &app.current_input,
+            app.input_cursor,
+        );
+    }
+
+    if let InputMode::Scheduled = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "Scheduled For (YYYY-MM-DD [HH:MM])",
+            &app.current_input,
+            app.input_cursor,
+        );
+    }
+
+    if let InputMode::CompletionNote = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "What was the outcome?",
+            &app.current_input,
+            app.input_cursor,
+        );
+    }
+
+    if let InputMode::Tag = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "Tags (-tag removes)",
+            &app.current_input,
+            app.input_cursor,
+        );
+    }
+
+    if let InputMode::Comment = app.input_mode {
+        render_input_popup(f, &theme, "Comment", &app.current_input, app.input_cursor);
+    }
+
+    if let InputMode::Attach = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "Attach File or URL",
+            &app.current_input,
+            app.input_cursor,
+        );
+    }
+
+    if let InputMode::Links = app.input_mode {
+        // Link chooser, as wide as the longest link allows
+        let items: Vec<ListItem> = app.links().into_iter().map(ListItem::new).collect();
+        ScrollPopup::new(&theme, "Open").render_list(f, items, &mut app.link_select_state);
+    }
+
+    if let InputMode::TemplateName = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "Save as Template",
+            &app.current_input,
+            app.input_cursor,
+        );
+    }
+
+    if let InputMode::Templates = app.input_mode {
+        // Template picker, each entry with the number of todos it adds
+        let items: Vec<ListItem> = app
+            .templates
+            .iter()
+            .map(|t| ListItem::new(format!("{} ({})", t.name, t.todos.len())))
+            .collect();
+        ScrollPopup::new(&theme, "Templates").render_list(f, items, &mut app.template_select_state);
+    }
+
+    if let InputMode::Import = app.input_mode {
+        // Clipboard lines about to become todos
+        let items: Vec<ListItem> = app
+            .import
+            .iter()
+            .map(|line| ListItem::new(line.as_str()))
+            .collect();
+        let title = format!("Import {} Todos", app.import.len());
+        ScrollPopup::new(&theme, title).render_list(f, items, &mut app.import_select_state);
+    }
+
+    if let InputMode::Conflicts = app.input_mode {
+        // Each field as saved here and elsewhere, the one kept marked
+        let items: Vec<ListItem> = app
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                let (mine, theirs) = match conflict.kept_mine {
+                    true => ("•", " "),
+                    false => (" ", "•"),
+                };
+                // Strings without their quotes, other values as JSON
+                let value = |value: &serde_json::Value| match value {
+                    serde_json::Value::String(text) => text.clone(),
+                    serde_json::Value::Null => "none".to_string(),
+                    value => value.to_string(),
+                };
+                ListItem::new(vec![
+                    Line::from(format!("{}: {}", conflict.description, conflict.field)),
+                    Line::from(format!("  {} mine   {}", mine, value(&conflict.mine))),
+                    Line::from(format!("  {} theirs {}", theirs, value(&conflict.theirs))),
+                ])
+            })
+            .collect();
+        let title = format!("{} Conflicts, Changed Elsewhere Too", app.conflicts.len());
+        ScrollPopup::new(&theme, title).render_list(f, items, &mut app.conflict_select_state);
+    }
+
+    if let InputMode::Archive = app.input_mode {
+        // Archive browser, most recently archived first with the completion date
+        let items: Vec<ListItem> = app
+            .current_page()
+            .archived
+            .iter()
+            .rev()
+            .map(|todo| match todo.completed_at {
+                Some(done) => format!("{}  (done {})", todo.description, done.format("%Y-%m-%d")),
+                None => todo.description.clone(),
+            })
+            .map(ListItem::new)
+            .collect();
+        let title = format!("Archive: {}", app.current_page().name);
+        ScrollPopup::new(&theme, title).render_list(f, items, &mut app.archive_select_state);
+    }
+
+    if let InputMode::PageSwitch = app.input_mode {
+        let title = if app.moving_to_page {
+            "Move to Page"
+        } else {
+            "Go to Page"
+        };
+        render_input_popup(f, &theme, title, &app.current_input, app.input_cursor);
+        render_page_switcher(f, app);
+    }
+
+    if let InputMode::TodoJump = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "Jump to Todo",
+            &app.current_input,
+            app.input_cursor,
+        );
+        render_page_switcher(f, app);
+    }
+
+    if let InputMode::Help = app.input_mode {
+        render_help(f, app);
+    }
+
+    if let InputMode::DaySummary = app.input_mode {
+        render_day_summary(f, app);
+    }
+
+    if let InputMode::PageIcon = app.input_mode {
+        render_input_popup(f, &theme, "Page Icon", &app.current_input, app.input_cursor);
+    }
+
+    if let InputMode::ConfirmDelete = app.input_mode {
+        render_confirm_popup(f, &theme, &app.delete_question());
+    }
+
+    if let InputMode::Postpone = app.input_mode {
+        render_input_popup(
+            f,
+            &theme,
+            "Postpone Overdue By",
+            &app.current_input,
+            app.input_cursor,
+        );
+    }
+
+    if let InputMode::Reschedule = app.input_mode {
+        render_reschedule_menu(f, app);
+    }
+
+    render_toast(f, app, chunks[2].y);
+}
+
+// The latest toast in the bottom right corner, just above the status line at
+// `bottom`, until it expires
+fn render_toast(f: &mut Frame, app: &App, bottom: u16) {
+    let Some(toast) = app.toast.as_ref().filter(|toast| !toast.expired()) else {
+        return;
+    };
+    let theme = app.config.theme;
+    let area = f.area();
+    let width =
+        (Span::raw(toast.message.as_str()).width() as u16 + 4).min(area.width.saturating_sub(4));
+    let toast_area = Rect::new(
+        area.width.saturating_sub(width + 2),
+        bottom.saturating_sub(3),
+        width,
+        3,
+    );
+    let (text, border) = match toast.error {
+        true => (theme.error, theme.error),
+        false => (theme.popup, theme.border),
+    };
+    f.render_widget(ratatui::widgets::Clear, toast_area);
+    f.render_widget(
+        Paragraph::new(format!(" {}", toast.message))
+            .style(Style::default().fg(text))
+            .block(bordered(&theme).border_style(Style::default().fg(border))),
+        toast_area,
+    );
+}
+
+// Block with the theme's border around it
+fn bordered<'a>(theme: &Theme) -> Block<'a> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+}
+
+// Footer hints of the current view, or of the mode the list is in (focus,
+// move, visual) and otherwise of the todo list itself
+fn view_hints(app: &App) -> Vec<Hint> {
+    match app.view {
+        View::Calendar => vec![
+            (&[Action::PreviousDay, Action::NextDay], "Day"),
+            (&[Action::HalfPageUp, Action::HalfPageDown], "Week"),
+            (&[Action::PageUp, Action::PageDown], "Month"),
+            (&[Action::Back], "Back"),
+        ],
+        View::Stats => vec![
+            (&[Action::Back], "Back"),
+            (&[Action::Agenda], "Agenda"),
+            (&[Action::Calendar], "Calendar"),
+            (&[Action::Help], "Help"),
+            (&[Action::Quit], "Quit"),
+        ],
+        View::Agenda | View::Review => vec![
+            (&[Action::Help], "Help"),
+            (&[Action::Open], "Open in Page"),
+            (&[Action::Toggle], "Complete"),
+            (&[Action::Back], "Back"),
+            (&[Action::Next, Action::Previous], "Navigate"),
+        ],
+        View::List if app.focus => vec![
+            (&[Action::Toggle], "Done"),
+            (&[Action::Next], "Skip"),
+            (&[Action::Previous], "Previous"),
+            (&[Action::Notes], "Notes"),
+            (&[Action::Focus, Action::Back], "Leave Focus"),
+        ],
+        View::List if app.picking_mode => vec![
+            (&[Action::Next, Action::Previous], "Move Item Down/Up"),
+            (&[Action::Move], "Exit Move Mode"),
+        ],
+        View::List if app.visual_anchor.is_some() => vec![
+            (&[Action::Next, Action::Previous], "Extend"),
+            (&[Action::Delete], "Delete"),
+            (&[Action::Toggle], "Toggle"),
+            (&[Action::MoveToPage], "Move"),
+            (&[Action::Tag], "Tag"),
+            (&[Action::Visual], "Exit"),
+        ],
+        View::List => list_hints(app),
+    }
+}
+
+// Actions named in the help bar, several of them sharing a label
+type Hint = (&'static [Action], &'static str);
+
+// Two lines of the common actions for the arrows preset
+const BEGINNER_HINTS: [Hint; 11] = [
+    (&[Action::Previous, Action::Next], "Select"),
+    (&[Action::Toggle], "Done"),
+    (&[Action::PreviousPage, Action::NextPage], "Page"),
+    (&[Action::Add], "Add"),
+    (&[Action::Delete], "Delete"),
+    (&[Action::Edit], "Edit"),
+    (&[Action::Search], "Search"),
+    (&[Action::DueDate], "Due Date"),
+    (&[Action::Agenda], "Agenda"),
+    (&[Action::Help], "All Keys"),
+    (&[Action::Quit], "Quit"),
+];
+
+// Footer hints of the todo list, only for what can be done with the selected
+// todo, the ones particular to it first
+fn list_hints(app: &App) -> Vec<Hint> {
+    let mut hints: Vec<Hint> = vec![(&[Action::Help], "Help")];
+    if let Some(todo) = app.selected_todo() {
+        hints.push((
+            &[Action::Toggle],
+            if todo.completed { "Reopen" } else { "Done" },
+        ));
+        if !todo.links().is_empty() {
+            hints.push((&[Action::OpenLinks], "Open Link"));
+        }
+        if app.current_page().settings.quantities && todo.quantity.is_some() {
+            hints.push((&[Action::Increase], "More"));
+            hints.push((&[Action::Decrease], "Less"));
+        }
+        if todo.timer_started.is_some() {
+            hints.push((&[Action::Timer], "Stop Timer"));
+        }
+        if todo.due_date.is_some() && !todo.completed {
+            hints.push((&[Action::Reschedule], "Reschedule"));
+        }
+        if !todo.notes.is_empty() || !todo.comments.is_empty() {
+            hints.push((&[Action::Details], "Details"));
+        }
+        hints.push((&[Action::Edit], "Edit"));
+        hints.push((&[Action::Delete], "Delete"));
+    }
+    hints.push((&[Action::Add], "Add"));
+    hints.push((&[Action::Quit], "Quit"));
+    hints
+}
+
+// Quick reschedule options, each with the due date it would set
+fn render_reschedule_menu(f: &mut Frame, app: &App) {
+    let theme = app.config.theme;
+    let Some(todo) = app.selected_todo() else {
+        return;
+    };
+    let due_date = todo.due_date;
+    let today = Local::now().date_naive();
+
+    let lines: Vec<Line> = Reschedule::ALL
+        .iter()
+        .map(|option| {
+            Line::from(vec![
+                Span::styled(
+                    format!(" {}  ", option.key()),
+                    Style::default().fg(theme.heading),
+                ),
+                Span::raw(format!("{:<10}", option.label())),
+                Span::styled(
+                    option
+                        .apply(due_date, today, &app.holidays)
+                        .format("%a %Y-%m-%d")
+                        .to_string(),
+                    Style::default().fg(theme.muted),
+                ),
+            ])
+        })
+        .collect();
+
+    let area = f.area();
+    let popup_width = area.width.min(32);
+    let popup_height = lines.len() as u16 + 2;
+    let popup_area = ratatui::layout::Rect::new(
+        area.width.saturating_sub(popup_width) / 2,
+        area.height.saturating_sub(popup_height) / 2,
+        popup_width,
+        popup_height.min(area.height),
+    );
+
+    f.render_widget(ratatui::widgets::Clear, popup_area);
+    let menu = Paragraph::new(lines).block(bordered(&theme).title("Reschedule"));
+    f.render_widget(menu, popup_area);
+}
+
+// Tabs of the pages Tab cycles through with their open todos, the current one
+// highlighted. Tabs that don't fit are cut off on either side of it, marked
+// with an ellipsis.
+fn render_page_tabs(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.theme;
+    let pages = app.workspace_pages();
+    let mut titles: Vec<Line> = pages
+        .iter()
+        .map(|&index| {
+            let page = &app.pages[index];
+            let open = page.todos.iter().filter(|t| !t.completed).count();
+            Line::from(vec![
+                Span::raw(app.page_title(page)),
+                Span::styled(format!(" {}", open), Style::default().fg(theme.muted)),
+            ])
+        })
+        .collect();
+    let selected = pages
+        .iter()
+        .position(|&i| i == app.current_page_index)
+        .unwrap_or(0);
+
+    // Each tab is padded by a space on both sides and followed by a divider
+    let widths: Vec<usize> = titles.iter().map(|title| title.width() + 3).collect();
+    let visible = tab_window(&widths, selected, area.width as usize);
+    let mut selected = selected - visible.start;
+    let (before, after) = (visible.start > 0, visible.end < titles.len());
+    titles.truncate(visible.end);
+    titles.drain(..visible.start);
+    if before {
+        titles.insert(0, Line::from("…"));
+        selected += 1;
+    }
+    if after {
+        titles.push(Line::from("…"));
+    }
+
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .style(Style::default().fg(theme.title))
+        .highlight_style(
+            Style::default()
+                .fg(theme.selection)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(tabs, area);
+}
+
+// Tabs around `selected` that fit in `room` columns, growing to both sides
+fn tab_window(widths: &[usize], selected: usize, room: usize) -> Range<usize> {
+    if widths.iter().sum::<usize>() <= room {
+        return 0..widths.len();
+    }
+    // Leave space for an ellipsis tab on either side
+    let room = room.saturating_sub(8);
+    let (mut start, mut end) = (selected, selected + 1);
+    let mut used = widths[selected];
+    loop {
+        let mut grew = false;
+        if end < widths.len() && used + widths[end] <= room {
+            used += widths[end];
+            end += 1;
+            grew = true;
+        }
+        if start > 0 && used + widths[start - 1] <= room {
+            start -= 1;
+            used += widths[start];
+            grew = true;
+        }
+        if !grew {
+            return start..end;
+        }
+    }
+}
+
+// Share of the page's todos that are done, as a bar of 10 cells and a count
+fn progress_spans(theme: &Theme, page: &TodoPage) -> Vec<Span<'static>> {
+    let total = page.todos.len();
+    if total == 0 {
+        return Vec::new();
+    }
+    let done = page.todos.iter().filter(|t| t.completed).count();
+    let filled = done * 10 / total;
+    vec![
+        Span::raw(" "),
+        Span::styled("■".repeat(filled), Style::default().fg(theme.completed)),
+        Span::styled("■".repeat(10 - filled), Style::default().fg(theme.muted)),
+        Span::raw(format!(" {}/{}", done, total)),
+    ]
+}
+
+// Centered single line input popup with the cursor before character `cursor`
+fn render_input_popup(f: &mut Frame, theme: &Theme, title: &str, input: &str, cursor: usize) {
+    let area = f.area();
+    let popup_width = area.width.saturating_sub(40);
+    let popup_height = 3;
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+    render_input_at(f, theme, title, input, cursor, popup_area);
+}
+
+// A line of input in `area` with the cursor shown, scrolled sideways to keep
+// the cursor in view
+fn render_input_at(
+    f: &mut Frame,
+    theme: &Theme,
+    title: &str,
+    input: &str,
+    cursor: usize,
+    area: Rect,
+) {
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let column = input::cursor_column(input, cursor);
+    let scroll = column.saturating_sub(area.width.saturating_sub(3));
+    let paragraph = Paragraph::new(input)
+        .scroll((0, scroll))
+        .style(Style::default().fg(theme.popup))
+        .block(bordered(theme).title(title));
+    f.render_widget(paragraph, area);
+
+    f.set_cursor_position((area.x + column - scroll + 1, area.y + 1));
+}
+
+// A yes or no question over the rest of the screen
+fn render_confirm_popup(f: &mut Frame, theme: &Theme, question: &str) {
+    let area = f.area();
+    let popup_width = area.width.saturating_sub(20);
+    let popup_height = 3;
+    let popup_area = Rect::new(
+        (area.width.saturating_sub(popup_width)) / 2,
+        (area.height.saturating_sub(popup_height)) / 2,
+        popup_width,
+        popup_height,
+    );
+    f.render_widget(ratatui::widgets::Clear, popup_area);
+    let paragraph = Paragraph::new(question)
+        .style(Style::default().fg(theme.popup))
+        .block(bordered(theme).title("Confirm (y/n)"));
+    f.render_widget(paragraph, popup_area);
+}
+
+// Full screen list of every key binding, grouped by where it applies
+fn render_help(f: &mut Frame, app: &mut App) {
+    let theme = app.config.theme;
+    // The list and agenda keys as configured by category, with the action's
+    // name in `[keys]` of the config, then the fixed keys of popups
+    let keymap = &app.config.keys;
+    let mut sections: Vec<(&str, Vec<(String, String)>)> = keymap::Category::ALL
+        .iter()
+        .map(|&category| {
+            let bound = keymap::ACTIONS
+                .iter()
+                .filter(|&&(.., c)| c == category)
+                .map(|&(action, name, _, description, _)| {
+                    let keys = keymap.names(action);
+                    (keys.join(", "), format!("{} ({})", description, name))
+                })
+                .collect();
+            (category.title(), bound)
+        })
+        .collect();
+    sections.extend(help::SECTIONS.iter().map(|&(title, keys)| {
+        let keys = keys
+            .iter()
+            .map(|&(key, action)| (key.to_string(), action.to_string()))
+            .collect();
+        (title, keys)
+    }));
+
+    let mut lines = Vec::new();
+    for (section, keys) in sections {
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+        lines.push(Line::from(Span::styled(
+            section,
+            Style::default()
+                .fg(theme.heading)
+                .add_modifier(Modifier::BOLD),
+        )));
+        let width = keys.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+        for (key, action) in keys {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:<width$}  ", key),
+                    Style::default().fg(theme.selection),
+                ),
+                Span::raw(action),
+            ]));
+        }
+    }
+
+    ScrollPopup::new(&theme, "Key Bindings (j/k: Scroll, Esc: Close)")
+        .width(f.area().width)
+        .render_text(f, lines, &mut app.help_scroll);
+}
+
+// Status line: the search while it is typed or the last message on the left,
+// and on the right the file of the current page, ● for unsaved changes and
+// when it was last saved, as long as it fits next to the message
+fn render_status_line(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.theme;
+    let (left, style) = if let (InputMode::Search, Some(query)) = (&app.input_mode, &app.search) {
+        let search = format!("/{}", query);
+        f.set_cursor_position((area.x + search.chars().count() as u16, area.y));
+        (search, Style::default().fg(theme.popup))
+    } else if let Some(message) = &app.status_message {
+        (message.clone(), Style::default().fg(theme.error))
+    } else if let Some(version) = &app.update_available {
+        (
+            format!("ratdo {} is available", version),
+            Style::default().fg(theme.muted),
+        )
+    } else {
+        (String::new(), Style::default())
+    };
+
+    let store = app.stores.get(app.current_page().store);
+    let file = match store {
+        _ if !app.storage.persistent() => "ephemeral, not saved".to_string(),
+        Some(store) => config::shorten_home(&store.path),
+        None => String::new(),
+    };
+    let saved = match store.and_then(|store| store.saved_at) {
+        Some(at) => format!(" · saved {}", at.format("%H:%M")),
+        _ => String::new(),
+    };
+    let mut info = vec![Span::styled(
+        format!("{}{} ", file, saved),
+        Style::default().fg(theme.muted),
+    )];
+    if app.dirty {
+        info.insert(0, Span::styled("● ", Style::default().fg(theme.due_today)));
+    }
+    let info = Line::from(info);
+
+    let fits = Span::raw(left.as_str()).width() + info.width() + 2 <= area.width as usize;
+    let width = if fits { info.width() as u16 } else { 0 };
+    let [left_area, info_area] =
+        Layout::horizontal([Constraint::Min(0), Constraint::Length(width)]).areas(area);
+    f.render_widget(Paragraph::new(left).style(style), left_area);
+    if fits {
+        f.render_widget(Paragraph::new(info), info_area);
+    }
+}
+
+// What was done yesterday, what is due today and what is overdue, with
+// suggestions for the todos that keep slipping
+fn render_day_summary(f: &mut Frame, app: &mut App) {
+    let theme = app.config.theme;
+    let today = app.today;
+    let key = |action| app.config.keys.names(action).join("/");
+    let heading = |title: &str, count: usize| {
+        Line::from(vec![
+            Span::styled(
+                title.to_string(),
+                Style::default()
+                    .fg(theme.heading)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!(" {}", count), Style::default().fg(theme.muted)),
+        ])
+    };
+
+    let done = stats::completed_on(&app.pages, today - chrono::Days::new(1));
+    let agenda = agenda::agenda_entries(&app.pages, today, 0);
+    let (overdue, due): (Vec<&agenda::AgendaEntry>, Vec<_>) = agenda
+        .iter()
+        .partition(|entry| entry.bucket == Bucket::Overdue);
+    let entry_line = |entry: &agenda::AgendaEntry, detail: String| {
+        let todo = &app.pages[entry.page].todos[entry.index];
+        Line::from(vec![
+            Span::raw(format!("  {}", todo.description)),
+            Span::styled(detail, Style::default().fg(theme.muted)),
+        ])
+    };
+
+    let mut lines = vec![heading("Done yesterday", done.len())];
+    lines.extend(
+        done.iter()
+            .map(|todo| Line::from(format!("  ✓ {}", todo.description))),
+    );
+    lines.push(Line::default());
+    lines.push(heading("Due today", due.len()));
+    for entry in &due {
+        let page = app.page_label(&app.pages[entry.page]);
+        lines.push(entry_line(entry, format!("  {}", page)));
+    }
+    lines.push(Line::default());
+    lines.push(heading("Overdue", overdue.len()));
+    let mut late = 0;
+    for entry in &overdue {
+        let todo = &app.pages[entry.page].todos[entry.index];
+        let days = todo
+            .due_date
+            .map_or(0, |due| (today - due.date_naive()).num_days());
+        if days >= 7 {
+            late += 1;
+        }
+        lines.push(entry_line(entry, format!("  {}d late", days)));
+    }
+
+    let mut suggestions = Vec::new();
+    if !overdue.is_empty() {
+        suggestions.push(format!(
+            "Postpone the overdue todos at once with {} in the agenda ({})",
+            key(Action::Postpone),
+            key(Action::Agenda)
+        ));
+    }
+    if late > 0 {
+        suggestions.push(format!(
+            "{} todos are a week or more overdue, reschedule ({}) or drop them",
+            late,
+            key(Action::Reschedule)
+        ));
+    }
+    if due.len() > 5 {
+        suggestions.push(format!(
+            "{} todos are due today, move some to tomorrow with {}",
+            due.len(),
+            key(Action::Reschedule)
+        ));
+    }
+    if !suggestions.is_empty() {
+        lines.push(Line::default());
+        lines.push(heading("Suggestions", suggestions.len()));
+        lines.extend(
+            suggestions
+                .into_iter()
+                .map(|s| Line::from(format!("  {}", s))),
+        );
+    }
+
+    let title = format!("{} (Esc: Close)", today.format("%A, %B %-d"));
+    ScrollPopup::new(&theme, title).render_text(f, lines, &mut app.summary_scroll);
+}
+
+// Pages, or todos of the jump, matching the switcher input, just under its
+// input popup
+fn render_page_switcher(f: &mut Frame, app: &mut App) {
+    let theme = app.config.theme;
+    let jump = matches!(app.input_mode, InputMode::TodoJump);
+    let matches = if jump {
+        app.todo_matches()
+    } else {
+        app.page_matches()
+    };
+    let area = f.area();
+    let popup_width = area.width.saturating_sub(40);
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(3)) / 2 + 3;
+    let popup_height = (matches.len().max(1) as u16 + 2).min(area.height.saturating_sub(popup_y));
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(ratatui::widgets::Clear, popup_area);
+
+    let items: Vec<ListItem> = if matches.is_empty() {
+        let none = if jump {
+            "No matching todo"
+        } else {
+            "No matching page"
+        };
+        vec![ListItem::new(Span::styled(
+            none,
+            Style::default().fg(theme.muted),
+        ))]
+    } else if jump {
+        let todos = app.todos();
+        matches
+            .iter()
+            .map(|&index| ListItem::new(todos[index].description.clone()))
+            .collect()
+    } else {
+        matches
+            .iter()
+            .map(|&index| ListItem::new(app.page_title(&app.pages[index])))
+            .collect()
+    };
+    let list = List::new(items)
+        .block(bordered(&theme))
+        .highlight_style(Style::default().fg(theme.selection))
+        .highlight_symbol(" > ");
+    f.render_stateful_widget(list, popup_area, &mut app.page_switch_state);
+}
+
+// Pages matching the `/page` word being typed, just under the input popup
+fn render_page_completions(f: &mut Frame, app: &mut App) {
+    let theme = app.config.theme;
+    let completions = app.page_completions();
+    if completions.is_empty() {
+        return;
+    }
+
+    let area = f.area();
+    let input_width = area.width.saturating_sub(40);
+    let popup_x = (area.width.saturating_sub(input_width)) / 2;
+    let popup_y = (area.height.saturating_sub(3)) / 2 + 3;
+    let popup_width = input_width.min(30);
+    let popup_height = (completions.len() as u16 + 2).min(area.height.saturating_sub(popup_y));
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(ratatui::widgets::Clear, popup_area);
+
+    let items: Vec<ListItem> = completions
+        .iter()
+        .map(|page| ListItem::new(format!("/{}", page)))
+        .collect();
+    let list = List::new(items)
+        .block(bordered(&theme).title("Pages (Tab)"))
+        .highlight_style(Style::default().fg(theme.selection))
+        .highlight_symbol(" > ");
+    f.render_stateful_widget(list, popup_area, &mut app.page_completion_state);
+}
+
+fn render_todo_list(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.theme;
+    // Leave room for the borders and the highlight symbol
+    let row_width = area.width.saturating_sub(5) as usize;
+    let today = app.today;
+    let now = Local::now();
+    let detailed = app.config.density == Density::Detailed;
+    let rows = app.rows();
+    let collapsed = &app.current_page().settings.collapsed;
+    let todos: Vec<ListItem> = rows
+        .iter()
+        .map(|row| {
+            let index = match *row {
+                Row::Todo(index) => index,
+                Row::Group(ref group, count) => {
+                    let fold = if collapsed.contains(group) {
+                        "▸"
+                    } else {
+                        "▾"
+                    };
+                    return ListItem::new(Line::from(vec![
+                        Span::styled(
+                            format!(" {} {}", fold, group.title()),
+                            Style::default()
+                                .fg(theme.heading)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(format!(" {}", count), Style::default().fg(theme.muted)),
+                    ]));
+                }
+            };
+            let todo = &app.todos()[index];
+            let mut line = todo_line(todo, today, row_width, &theme);
+            // Long todos wrap, without right-aligning the quantity. So do
+            // large text and detailed rows, where it ends up on the second line.
+            if app.large_text || detailed || line.width() > row_width {
+                line = todo_line(todo, today, 0, &theme);
+            }
+            if app.marked.contains(&todo.id) {
+                // A bullet in place of the leading space
+                if let Some(first) = line.spans.first_mut() {
+                    first.content = format!("•{}", &first.content[1..]).into();
+                }
+            }
+            if app.large_text {
+                large_item(line, row_width)
+            } else if detailed {
+                let age = todo::relative_age(todo.created_at, now);
+                detailed_item(line, &age, row_width, &theme)
+            } else {
+                ListItem::new(wrap_line(line, row_width))
+            }
+        })
+        .collect();
+    app.row_heights = todos.iter().map(ListItem::height).collect();
+
+    let list_title = if app.picking_mode {
+        "Moving Todo (Navigate with j/k)".to_string()
+    } else if app.visual_anchor.is_some() {
+        format!("Visual, {} Marked (Esc: Cancel)", app.marked.len())
+    } else if !app.marked.is_empty() {
+        format!("Todos, {} Marked (Esc: Clear Marks)", app.marked.len())
+    } else if let Some(tag) = &app.current_page().tag_filter {
+        format!("Todos +{} (Esc: Clear Filter)", tag)
+    } else {
+        "Todos".to_string()
+    };
+    let total = app.todos().len();
+    let filtered = app.search.is_some() || app.current_page().tag_filter.is_some();
+    let list_title = if filtered {
+        let shown = app.visible_indices().len();
+        format!("{}, Showing {} of {}", list_title, shown, total)
+    } else {
+        list_title
+    };
+
+    // Say why the list is empty and how to go on instead of a blank box
+    if rows.is_empty() {
+        let keys = &app.config.keys;
+        let message = if let Some(query) = &app.search {
+            format!(
+                "No todos match '{}', press {} to clear the search, {} to add one",
+                query,
+                keys.hint(Action::Back),
+                keys.hint(Action::Add)
+            )
+        } else if let Some(tag) = &app.current_page().tag_filter {
+            format!(
+                "No todos tagged +{}, press {} to clear the filter, {} to add one",
+                tag,
+                keys.hint(Action::Back),
+                keys.hint(Action::Add)
+            )
+        } else if total > 0 {
+            format!(
+                "All todos are snoozed, press {} to show them",
+                keys.hint(Action::ShowSnoozed)
+            )
+        } else {
+            format!(
+                "Nothing to do on this page, press {} to add a todo",
+                keys.hint(Action::Add)
+            )
+        };
+        let empty = Paragraph::new(vec![Line::default(), Line::from(message)])
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(bordered(&theme).title(list_title));
+        f.render_widget(empty, area);
+        // Like the list widget does when it has no items
+        app.state.select(None);
+        app.list_area = area;
+        return;
+    }
+
+    let todos = List::new(todos)
+        .block(bordered(&theme).title(list_title))
+        .highlight_style(if app.picking_mode {
+            // Use a different highlight style when picking
+            Style::default().fg(Color::Black).bg(theme.selection)
+        } else if app.large_text {
+            // A solid block over both lines of the todo
+            Style::default()
+                .fg(Color::Black)
+                .bg(theme.selection)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.selection)
+        })
+        .highlight_symbol(if app.picking_mode {
+            " >>" // Different symbol when picking
+        } else if app.large_text {
+            " ▶ "
+        } else {
+            " > "
+        });
+
+    f.render_stateful_widget(todos, area, &mut app.state);
+    app.list_area = area;
+
+    // Scrollbar on the right border once the todos don't fit
+    let lines: usize = app.row_heights.iter().sum();
+    if lines > app.page_lines() {
+        let above: usize = app.row_heights.iter().take(app.state.offset()).sum();
+        let mut scrollbar_state = ScrollbarState::new(lines - app.page_lines())
+            .viewport_content_length(app.page_lines())
+            .position(above);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .style(Style::default().fg(theme.muted)),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+// Focus mode: the selected todo alone in the middle of the list area, bold
+// with its badges below and its notes under them
+fn render_focus(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.theme;
+    let open = app.todos().iter().filter(|t| !t.completed).count();
+    let block = bordered(&theme).title(format!("Focus, {} Open", open));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let Some(todo) = app.selected_index().map(|i| &app.todos()[i]) else {
+        let empty = Paragraph::new("No todo selected")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        f.render_widget(empty, inner);
+        return;
+    };
+
+    let width = inner.width.saturating_sub(4).min(72);
+    let rows = |line: &Line| line.width().div_ceil(width.max(1) as usize).max(1) as u16;
+    let title_style = if todo.completed {
+        Style::default()
+            .fg(theme.completed)
+            .add_modifier(Modifier::CROSSED_OUT)
+    } else {
+        Style::default().add_modifier(Modifier::BOLD)
+    };
+    let title = Line::from(Span::styled(todo.description.clone(), title_style));
+    // The badges of the list row, without its checkbox and description
+    let mut badges = Line::default();
+    for span in todo_line(todo, app.today, 0, &theme)
+        .spans
+        .into_iter()
+        .skip(1)
+    {
+        let content = match badges.spans.is_empty() {
+            true => span.content.trim_start().to_string(),
+            false => span.content.into_owned(),
+        };
+        badges.push_span(Span::styled(content, span.style));
+    }
+    let mut header = vec![title];
+    if badges.width() > 0 {
+        header.push(Line::default());
+        header.push(badges);
+    }
+    let header_rows: u16 = header.iter().map(rows).sum();
+    let notes: Vec<Line> = todo.notes.lines().map(Line::from).collect();
+    let notes_rows: u16 = notes.iter().map(rows).sum();
+
+    // Centered, with the notes below when they fit, otherwise starting a
+    // third down
+    let content = header_rows + if notes.is_empty() { 0 } else { notes_rows + 1 };
+    let top = (inner.height.saturating_sub(content) / 2).min(inner.height / 3);
+    let column = Rect::new(
+        inner.x + (inner.width - width) / 2,
+        inner.y + top,
+        width,
+        inner.height - top,
+    );
+    f.render_widget(
+        Paragraph::new(header)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        column,
+    );
+    if !notes.is_empty() {
+        let below = (header_rows + 1).min(column.height);
+        let notes_area = Rect {
+            y: column.y + below,
+            height: column.height - below,
+            ..column
+        };
+        f.render_widget(Paragraph::new(notes).wrap(Wrap { trim: false }), notes_area);
+    }
+}
+
+// A todo line split for large text: checkbox and description in bold, the
+// badges indented below and a blank line to separate it from the next todo
+fn large_item(line: Line<'static>, width: usize) -> ListItem<'static> {
+    let mut spans = line.spans.into_iter();
+    let main = spans
+        .next()
+        .map(|span| span.patch_style(Style::default().add_modifier(Modifier::BOLD)))
+        .unwrap_or_default();
+    let mut details = Line::from("    ");
+    details.extend(spans);
+    let mut lines = wrap_line(Line::from(main), width);
+    lines.extend(wrap_line(details, width));
+    lines.push(Line::default());
+    ListItem::new(lines)
+}
+
+// A todo line split for detailed rows: the description, with its badges and
+// how long ago it was added on an indented line below
+fn detailed_item(line: Line<'static>, age: &str, width: usize, theme: &Theme) -> ListItem<'static> {
+    let mut spans = line.spans.into_iter();
+    let mut lines = wrap_line(Line::from(spans.next().unwrap_or_default()), width);
+    let mut details = Line::from(" ".repeat(WRAP_INDENT));
+    for span in spans {
+        // The badges are spaced for following the description
+        let content = match details.width() == WRAP_INDENT {
+            true => span.content.trim_start().to_string(),
+            false => span.content.into_owned(),
+        };
+        details.push_span(Span::styled(content, span.style));
+    }
+    if details.width() > WRAP_INDENT {
+        details.push_span(Span::raw("  "));
+    }
+    details.push_span(Span::styled(
+        format!("added {}", age),
+        Style::default().fg(theme.muted),
+    ));
+    lines.extend(wrap_line(details, width));
+    ListItem::new(lines)
+}
+
+// Lines continued after a wrap start below the description, past " [ ] "
+const WRAP_INDENT: usize = 5;
+
+// Break a todo line at spaces into lines of at most `width` columns, keeping
+// the style of each part. Words longer than a line are split anywhere.
+fn wrap_line(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
+    if line.width() <= width || width <= WRAP_INDENT {
+        return vec![line];
+    }
+    let mut lines = Vec::new();
+    let mut current = Line::default();
+    for span in line.spans {
+        // Words keep the space after them, so breaks fall between words
+        for word in span.content.split_inclusive(' ') {
+            let mut word = word.to_string();
+            while !word.is_empty() {
+                let fits = current.width() + Span::raw(word.trim_end()).width() <= width;
+                let fresh = current.width() <= WRAP_INDENT;
+                if fits || fresh {
+                    // On a fresh line take as much of the word as fits
+                    let room = width - current.width();
+                    let mut taken = String::new();
+                    for c in word.chars() {
+                        if Span::raw(format!("{}{}", taken, c)).width() > room {
+                            break;
+                        }
+                        taken.push(c);
+                    }
+                    if taken.is_empty() {
+                        // A wide character with one column left
+                        taken.extend(word.chars().next());
+                    }
+                    word = word[taken.len()..].to_string();
+                    current.push_span(Span::styled(taken, span.style));
+                    if word.is_empty() {
+                        continue;
+                    }
+                }
+                lines.push(std::mem::take(&mut current));
+                current.push_span(Span::raw(" ".repeat(WRAP_INDENT)));
+                word = word.trim_start().to_string();
+            }
+        }
+    }
+    if current.width() > WRAP_INDENT || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+// Open todos of all pages grouped into urgency buckets
+fn render_agenda(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.theme;
+    let row_width = area.width.saturating_sub(5) as usize;
+    let today = app.today;
+    let entries = app.agenda_entries();
+
+    // Interleave bucket headers with the entries, tracking the row of the
+    // selected entry since the headers can't be selected
+    let mut items = Vec::new();
+    let mut selected_row = None;
+    let mut bucket = None;
+    for (i, entry) in entries.iter().enumerate() {
+        // The calendar lists a single day, without headers
+        if app.view != View::Calendar && bucket != Some(entry.bucket) {
+            bucket = Some(entry.bucket);
+            items.push(ListItem::new(Span::styled(
+                entry.bucket.title(),
+                Style::default()
+                    .fg(theme.heading)
+                    .add_modifier(Modifier::BOLD),
+            )));
+        }
+        if app.agenda_state.selected() == Some(i) {
+            selected_row = Some(items.len());
+        }
+
+        let page = &app.pages[entry.page];
+        let mut line = Line::from(Span::styled(
+            format!(" {}:", app.page_title(page)),
+            Style::default().fg(theme.muted),
+        ));
+        line.extend(todo_line(&page.todos[entry.index], today, row_width, &theme).spans);
+        items.push(ListItem::new(line));
+    }
+
+    let title = match app.view {
+        View::Review => "Review".to_string(),
+        View::Calendar => app.calendar_day.format("%A %Y-%m-%d").to_string(),
+        _ => "Agenda".to_string(),
+    };
+    if items.is_empty() {
+        let empty = if app.view == View::Review {
+            "Nothing needs review".to_string()
+        } else if app.view == View::Calendar {
+            "Nothing due on this day".to_string()
+        } else {
+            format!(
+                "Nothing due or scheduled in the next {} days",
+                app.config.agenda_horizon_days
+            )
+        };
+        items.push(ListItem::new(Span::styled(
+            empty,
+            Style::default().fg(theme.muted),
+        )));
+    }
+
+    let agenda = List::new(items)
+        .block(bordered(&theme).title(title))
+        .highlight_style(Style::default().fg(theme.selection))
+        .highlight_symbol(" > ");
+    let mut state = ListState::default().with_selected(selected_row);
+    f.render_stateful_widget(agenda, area, &mut state);
+}
+
+// Month of the selected calendar day, with a dot per open todo due on each day
+// (up to two), next to the todos due on the selected day
+fn render_calendar(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.theme;
+    let counts = agenda::due_counts(&app.pages);
+    let first = app.calendar_day.with_day(1).unwrap_or(app.calendar_day);
+
+    let mut lines = vec![Line::from(Span::styled(
+        ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"]
+            .iter()
+            .map(|name| format!("{:>3}  ", name))
+            .collect::<String>(),
+        Style::default().fg(theme.muted),
+    ))];
+    // Weeks start on Monday, days of the neighbouring months are left blank
+    let mut date = first - Days::new(first.weekday().num_days_from_monday() as u64);
+    while date < first || date.month() == first.month() {
+        let mut spans = Vec::new();
+        for _ in 0..7 {
+            if date.month() != first.month() {
+                spans.push(Span::raw("     "));
+            } else {
+                let count = counts.get(&date).copied().unwrap_or(0);
+                let mut style = match count {
+                    0 => Style::default(),
+                    _ if date < app.today => Style::default().fg(theme.overdue),
+                    _ if date == app.today => Style::default().fg(theme.due_today),
+                    _ => Style::default().fg(theme.heading),
+                };
+                if date == app.today {
+                    style = style.add_modifier(Modifier::UNDERLINED);
+                }
+                if date == app.calendar_day {
+                    style = style.fg(theme.selection).add_modifier(Modifier::REVERSED);
+                }
+                spans.push(Span::styled(format!("{:>3}", date.day()), style));
+                spans.push(Span::styled(
+                    format!("{:<2}", "•".repeat(count.min(2))),
+                    Style::default().fg(theme.muted),
+                ));
+            }
+            date = date + Days::new(1);
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let due: usize = counts
+        .range(first..first + Months::new(1))
+        .map(|(_, count)| count)
+        .sum();
+    let todos = if due == 1 { "todo" } else { "todos" };
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(
+        format!(" {} open {} due this month", due, todos),
+        Style::default().fg(theme.muted),
+    )));
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(37), Constraint::Min(0)])
+        .split(area);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(lines.len() as u16 + 2),
+            Constraint::Min(0),
+        ])
+        .split(columns[0]);
+    let month =
+        Paragraph::new(lines).block(bordered(&theme).title(first.format("%B %Y").to_string()));
+    f.render_widget(month, rows[0]);
+
+    render_agenda(f, app, columns[1]);
+}
+
+// Badge with the assignee's initial, colored by name so each person keeps
+// the same color
+fn assignee_badge(assignee: &str) -> Span<'static> {
+    let name = assignee.to_lowercase();
+    let initial: String = name
+        .chars()
+        .next()
+        .into_iter()
+        .flat_map(char::to_uppercase)
+        .collect();
+
+    Span::styled(
+        format!(" {} ", initial),
+        Style::default().fg(Color::Black).bg(name_color(&name)),
+    )
+}
+
+// A color picked by hashing the name, the same for every run and any case
+fn name_color(name: &str) -> Color {
+    const COLORS: [Color; 6] = [
+        Color::Blue,
+        Color::Green,
+        Color::Magenta,
+        Color::Cyan,
+        Color::LightRed,
+        Color::LightBlue,
+    ];
+    let hash = name.to_lowercase().bytes().fold(0usize, |hash, b| {
+        hash.wrapping_mul(31).wrapping_add(b as usize)
+    });
+    COLORS[hash % COLORS.len()]
+}
+
+// One row of a todo list: checkbox, description, due date, notes marker, tags
+// and a right-aligned quantity
+fn todo_line(todo: &Todo, today: NaiveDate, row_width: usize, theme: &Theme) -> Line<'static> {
+    let status = if todo.completed { "[x]" } else { "[ ]" };
+    let pin = if todo.pinned { "★ " } else { "" };
+    let content = format!(" {} {}{}", status, pin, todo.description);
+
+    let due_status = todo.due_status(today);
+    let style = if todo.completed {
+        Style::default()
+            .fg(theme.completed)
+            .add_modifier(Modifier::CROSSED_OUT)
+    } else {
+        match due_status {
+            Some(DueStatus::Overdue) => Style::default().fg(theme.overdue),
+            Some(DueStatus::Today) => Style::default().fg(theme.due_today),
+            _ => Style::default(),
+        }
+    };
+
+    let mut line = Line::from(Span::styled(content, style));
+    if let (Some(priority), false) = (todo.priority, todo.completed) {
+        let color = match priority {
+            Priority::High => theme.priority_high,
+            Priority::Medium => theme.priority_medium,
+            Priority::Low => theme.priority_low,
+        };
+        line.push_span(Span::styled(
+            format!(" !{}", priority.name()),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(due_date) = todo.due_date {
+        let due_style = match due_status {
+            Some(DueStatus::Overdue) => Style::default()
+                .fg(theme.overdue)
+                .add_modifier(Modifier::BOLD),
+            Some(DueStatus::Today) => Style::default().fg(theme.due_today),
+            _ => Style::default().fg(theme.muted),
+        };
+        // Symbols mark the state for those who can't tell the colors apart
+        let symbol = match due_status {
+            Some(DueStatus::Overdue) => "⚠ ",
+            Some(DueStatus::Today) => "◷ ",
+            _ => "",
+        };
+        line.push_span(Span::styled(
+            format!("  ({}due {})", symbol, todo::format_due_date(due_date)),
+            due_style,
+        ));
+    }
+    if let Some(scheduled) = todo.scheduled {
+        line.push_span(Span::styled(
+            format!("  (scheduled {})", todo::format_due_date(scheduled)),
+            Style::default().fg(theme.scheduled),
+        ));
+    }
+    if let Some(estimate) = todo.estimate {
+        line.push_span(Span::styled(
+            format!("  ~{}", todo::format_estimate(estimate)),
+            Style::default().fg(theme.muted),
+        ));
+    }
+    if todo.timer_started.is_some() {
+        line.push_span(Span::styled(
+            format!("  ⏱ {}", todo::format_duration(todo.tracked_time())),
+            Style::default().fg(theme.timer),
+        ));
+    }
+    if !todo.notes.is_empty() {
+        line.push_span(Span::styled(" ✎", Style::default().fg(theme.muted)));
+    }
+    if !todo.attachments.is_empty() {
+        line.push_span(Span::styled(" 📎", Style::default().fg(theme.muted)));
+    }
+    for tag in &todo.tags {
+        line.push_span(Span::styled(
+            format!(" +{}", tag),
+            Style::default().fg(theme.tag),
+        ));
+    }
+    if let Some(until) = todo
+        .deferred_until
+        .filter(|_| todo.is_deferred(Local::now()))
+    {
+        line.push_span(Span::styled(
+            format!("  (snoozed until {})", todo::format_due_date(until)),
+            Style::default().fg(theme.snoozed),
+        ));
+    }
+    if todo.needs_review {
+        line.push_span(Span::styled(
+            "  (needs review)",
+            Style::default().fg(theme.review),
+        ));
+    }
+    if let Some(assignee) = &todo.assignee {
+        line.push_span(Span::raw(" "));
+        line.push_span(assignee_badge(assignee));
+    }
+    if let Some(quantity) = todo.quantity {
+        // Right-align the quantity within the row
+        let badge = format!("x{} ", quantity);
+        let padding = row_width.saturating_sub(line.width() + badge.len());
+        line.push_span(Span::raw(" ".repeat(padding)));
+        line.push_span(Span::styled(badge, Style::default().fg(theme.quantity)));
+    }
+
+    line
+}
+
+// Totals of each page and the oldest open todos, above the todos completed on
+// each of the last days, as many as fit
+fn render_stats(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.theme;
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[0]);
+
+    // Pages, with the sum of all of them below once there are several
+    let titles: Vec<String> = app.pages.iter().map(|page| app.page_title(page)).collect();
+    let width = titles
+        .iter()
+        .map(|t| t.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+    let stats_line = |name: &str, stats: stats::PageStats| {
+        format!(
+            " {:<width$} {:>5} {:>5} {:>4}%",
+            name,
+            stats.open,
+            stats.done,
+            stats.completion()
+        )
+    };
+    let mut lines = vec![Line::from(Span::styled(
+        format!(" {:<width$} {:>5} {:>5} {:>5}", "", "Open", "Done", "Rate"),
+        Style::default().fg(theme.muted),
+    ))];
+    let mut total = stats::PageStats::default();
+    for (page, title) in app.pages.iter().zip(&titles) {
+        let page_stats = stats::page_stats(page);
+        total.open += page_stats.open;
+        total.done += page_stats.done;
+        lines.push(Line::from(stats_line(title, page_stats)));
+    }
+    if app.pages.len() > 1 {
+        lines.push(Line::from(Span::styled(
+            stats_line("All", total),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+    }
+    f.render_widget(
+        Paragraph::new(lines).block(bordered(&theme).title("Pages")),
+        columns[0],
+    );
+
+    // Open todos by age, the page before each one
+    let oldest: Vec<ListItem> = stats::oldest_open(&app.pages, columns[1].height as usize)
+        .into_iter()
+        .map(|(page, index)| {
+            let todo = &app.pages[page].todos[index];
+            let age = (app.today - todo.created_at.date_naive()).num_days();
+            ListItem::new(Line::from(vec![
+                Span::styled(format!(" {:>4}d ", age), Style::default().fg(theme.muted)),
+                Span::styled(
+                    format!("{}: ", app.page_title(&app.pages[page])),
+                    Style::default().fg(theme.muted),
+                ),
+                Span::raw(todo.description.clone()),
+            ]))
+        })
+        .collect();
+    f.render_widget(
+        List::new(oldest).block(bordered(&theme).title("Oldest Open")),
+        columns[1],
+    );
+
+    // Bars of 3 columns with a gap of one
+    let days = (rows[1].width.saturating_sub(2) / 4).clamp(7, 28) as u64;
+    let completed = stats::completed_per_day(&app.pages, app.today, days);
+    let labels: Vec<String> = completed
+        .iter()
+        .map(|(day, _)| day.format("%d").to_string())
+        .collect();
+    let data: Vec<(&str, u64)> = labels
+        .iter()
+        .map(String::as_str)
+        .zip(completed.iter().map(|&(_, count)| count))
+        .collect();
+    let sum: u64 = completed.iter().map(|&(_, count)| count).sum();
+    let todos = if sum == 1 { "todo" } else { "todos" };
+    let chart = BarChart::default()
+        .block(bordered(&theme).title(format!(
+            "Completed per Day, {} {} in the Last {} Days",
+            sum, todos, days
+        )))
+        .data(&data)
+        .bar_width(3)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(theme.completed))
+        .value_style(Style::default().fg(Color::Black).bg(theme.completed))
+        .label_style(Style::default().fg(theme.muted));
+    f.render_widget(chart, rows[1]);
+}
+
+// Full description, notes and metadata of the selected todo
+fn render_details(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.theme;
+    let block = bordered(&theme).title("Details");
+
+    let Some(todo) = app.selected_index().map(|i| &app.todos()[i]) else {
+        f.render_widget(Paragraph::new("No todo selected").block(block), area);
+        return;
+    };
+
+    let now = Local::now();
+    let label = Style::default().fg(theme.muted);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            todo.description.as_str(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Status:  ", label),
+            Span::raw(if todo.completed { "Done" } else { "Open" }),
+        ]),
+        Line::from(vec![
+            Span::styled("Created: ", label),
+            Span::raw(format!(
+                "{} ({})",
+                todo.created_at.format("%Y-%m-%d %H:%M"),
+                todo::relative_age(todo.created_at, now)
+            )),
+        ]),
+    ];
+    if let Some(completed_at) = todo.completed_at.filter(|_| todo.completed) {
+        lines.push(Line::from(vec![
+            Span::styled("Done:    ", label),
+            Span::raw(format!(
+                "{} ({})",
+                completed_at.format("%Y-%m-%d %H:%M"),
+                todo::relative_age(completed_at, now)
+            )),
+        ]));
+    }
+    if let Some(due_date) = todo.due_date {
+        lines.push(Line::from(vec![
+            Span::styled("Due:     ", label),
+            Span::raw(todo::format_due_date(due_date)),
+        ]));
+    }
+    if let Some(scheduled) = todo.scheduled {
+        lines.push(Line::from(vec![
+            Span::styled("Planned: ", label),
+            Span::raw(todo::format_due_date(scheduled)),
+        ]));
+    }
+    if let Some(reminder) = todo.reminder {
+        lines.push(Line::from(vec![
+            Span::styled("Remind:  ", label),
+            Span::raw(todo::format_due_date(reminder)),
+        ]));
+    }
+    if !todo.tags.is_empty() {
+        let tags: Vec<String> = todo.tags.iter().map(|tag| format!("+{}", tag)).collect();
+        lines.push(Line::from(vec![
+            Span::styled("Tags:    ", label),
+            Span::raw(tags.join(" ")),
+        ]));
+    }
+    if let Some(assignee) = &todo.assignee {
+        lines.push(Line::from(vec![
+            Span::styled("Owner:   ", label),
+            assignee_badge(assignee),
+            Span::raw(format!(" {}", assignee)),
+        ]));
+    }
+    if let Some(priority) = todo.priority {
+        lines.push(Line::from(vec![
+            Span::styled("Prio.:   ", label),
+            Span::raw(priority.name()),
+        ]));
+    }
+    if let Some(estimate) = todo.estimate {
+        lines.push(Line::from(vec![
+            Span::styled("Estim.:  ", label),
+            Span::raw(todo::format_estimate(estimate)),
+        ]));
+    }
+    let tracked = todo.tracked_time();
+    if !tracked.is_zero() {
+        let running = if todo.timer_started.is_some() {
+            " (running)"
+        } else {
+            ""
+        };
+        lines.push(Line::from(vec![
+            Span::styled("Tracked: ", label),
+            Span::raw(format!("{}{}", todo::format_duration(tracked), running)),
+        ]));
+    }
+    if let Some(quantity) = todo.quantity {
+        lines.push(Line::from(vec![
+            Span::styled("Amount:  ", label),
+            Span::raw(quantity.to_string()),
+        ]));
+    }
+    for (i, attachment) in todo.attachments.iter().enumerate() {
+        let heading = if i == 0 { "Files:   " } else { "         " };
+        lines.push(Line::from(vec![
+            Span::styled(heading, label),
+            Span::raw(attachment.as_str()),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("Link:    ", label),
+        Span::styled(todo.link(), label),
+    ]));
+
+    lines.push(Line::from(""));
+    if todo.notes.is_empty() {
+        lines.push(Line::from(Span::styled("No notes (E to add)", label)));
+    } else {
+        lines.extend(todo.notes.lines().map(Line::from));
+    }
+
+    for comment in &todo.comments {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(comment.author.clone(), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!(" {}", comment.created_at.format("%Y-%m-%d %H:%M")),
+                label,
+            ),
+        ]));
+        lines.push(Line::from(comment.text.as_str()));
+    }
+
+    let details = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    f.render_widget(details, area);
 }
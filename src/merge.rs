@@ -0,0 +1,306 @@
+use crate::todo::{Comment, Todo, TodoPage};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+// Counts of what a merge changed, printed by `ratdo merge`
+#[derive(Default, Debug)]
+pub struct MergeReport {
+    pub added_pages: usize,
+    pub added_todos: usize,
+    pub updated_todos: usize,
+    pub removed_todos: usize,
+    pub conflicts: Vec<Conflict>,
+    // Todos whose merged fields didn't make a valid todo, left as they were
+    pub errors: Vec<String>,
+}
+
+// A field of a todo both sides changed differently since the common base. The
+// merge keeps the newest edit, the other value is kept here to pick instead.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Conflict {
+    pub id: Uuid,
+    pub description: String,
+    pub field: String,
+    pub mine: Value,
+    pub theirs: Value,
+    pub kept_mine: bool,
+}
+
+// Fields that identify a todo or are maintained by the merge itself
+pub const UNMERGED_FIELDS: [&str; 3] = ["id", "created_at", "updated_at"];
+
+// Comments are only ever added, so both sides' comments are kept
+fn merge_comments(mine: &[Comment], theirs: &[Comment]) -> Vec<Comment> {
+    let mut comments = mine.to_vec();
+    for comment in theirs {
+        if !comments.contains(comment) {
+            comments.push(comment.clone());
+        }
+    }
+    comments.sort_by_key(|c| c.created_at);
+    comments
+}
+
+// Todos saved before IDs existed get a fresh ID on every load, so fall back
+// to the creation time and description to recognise them
+pub fn same_todo(a: &Todo, b: &Todo) -> bool {
+    a.id == b.id || (a.created_at == b.created_at && a.description == b.description)
+}
+
+fn find_todo<'a>(pages: &'a [TodoPage], todo: &Todo) -> Option<&'a Todo> {
+    pages
+        .iter()
+        .flat_map(|p| p.todos.iter())
+        .find(|t| same_todo(t, todo))
+}
+
+fn is_archived(pages: &[TodoPage], todo: &Todo) -> bool {
+    pages
+        .iter()
+        .flat_map(|p| p.archived.iter())
+        .any(|t| same_todo(t, todo))
+}
+
+fn position_of(pages: &[TodoPage], todo: &Todo) -> Option<(usize, usize)> {
+    pages.iter().enumerate().find_map(|(page_index, page)| {
+        page.todos
+            .iter()
+            .position(|t| same_todo(t, todo))
+            .map(|todo_index| (page_index, todo_index))
+    })
+}
+
+pub fn fields(todo: &Todo) -> Map<String, Value> {
+    match serde_json::to_value(todo) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+// Merge their copy of a todo into mine field by field. A field changed on only
+// one side since `base` keeps that change, otherwise the newest edit wins.
+// Fields changed on both sides are added to the report's conflicts. Returns
+// whether mine was updated.
+fn merge_todo(
+    mine: &mut Todo,
+    theirs: &Todo,
+    base: Option<&Todo>,
+    report: &mut MergeReport,
+) -> bool {
+    let theirs_newer = theirs.last_modified() > mine.last_modified();
+    let mut my_fields = fields(mine);
+    let base_fields = base.map(fields);
+    let mut changed = false;
+
+    for (key, their_value) in fields(theirs) {
+        if UNMERGED_FIELDS.contains(&key.as_str())
+            || key == "comments"
+            || my_fields.get(&key) == Some(&their_value)
+        {
+            continue;
+        }
+
+        let take_theirs = match base_fields.as_ref().and_then(|b| b.get(&key)) {
+            Some(base_value) if my_fields.get(&key) == Some(base_value) => true,
+            Some(base_value) if *base_value == their_value => false,
+            Some(_) => {
+                report.conflicts.push(Conflict {
+                    id: mine.id,
+                    description: mine.description.clone(),
+                    field: key.clone(),
+                    mine: my_fields.get(&key).cloned().unwrap_or_default(),
+                    theirs: their_value.clone(),
+                    kept_mine: !theirs_newer,
+                });
+                theirs_newer
+            }
+            None => theirs_newer,
+        };
+
+        if take_theirs {
+            my_fields.insert(key, their_value);
+            changed = true;
+        }
+    }
+
+    let comments = merge_comments(&mine.comments, &theirs.comments);
+    if comments != mine.comments {
+        if let Ok(comments) = serde_json::to_value(comments) {
+            my_fields.insert("comments".to_string(), comments);
+            changed = true;
+        }
+    }
+
+    if !changed {
+        return false;
+    }
+    match serde_json::from_value::<Todo>(Value::Object(my_fields)) {
+        Ok(mut merged) => {
+            merged.updated_at = Some(mine.last_modified().max(theirs.last_modified()));
+            *mine = merged;
+            true
+        }
+        Err(err) => {
+            report.errors.push(format!("{}: {}", mine.description, err));
+            false
+        }
+    }
+}
+
+// Merge another copy of the todo file into `mine`. Pages are matched by name,
+// new pages and todos from either side are kept. With a common `base` copy,
+// todos and pages deleted on one side without being edited on the other are
+// dropped, without one nothing is ever deleted.
+pub fn merge_pages(
+    mine: &mut Vec<TodoPage>,
+    theirs: Vec<TodoPage>,
+    base: Option<&[TodoPage]>,
+) -> MergeReport {
+    let mut report = MergeReport::default();
+
+    // Drop my todos they deleted, unless I changed them since
+    if let Some(base) = base {
+        for page in mine.iter_mut() {
+            let before = page.todos.len();
+            page.todos.retain(|todo| {
+                let deleted_by_them =
+                    find_todo(&theirs, todo).is_none() && find_todo(base, todo).is_some();
+                let unchanged = find_todo(base, todo)
+                    .is_some_and(|b| b.last_modified() >= todo.last_modified());
+                !(deleted_by_them && unchanged)
+            });
+            report.removed_todos += before - page.todos.len();
+        }
+    }
+
+    for their_page in theirs {
+        let page_index = match mine.iter().position(|p| p.name == their_page.name) {
+            Some(index) => index,
+            None => {
+                // A page I deleted since the common base stays deleted
+                if base.is_some_and(|b| b.iter().any(|p| p.name == their_page.name)) {
+                    continue;
+                }
+
+                let mut page = TodoPage::new(their_page.name.clone());
+                page.settings = their_page.settings.clone();
+                mine.push(page);
+                report.added_pages += 1;
+                mine.len() - 1
+            }
+        };
+
+        // Archives only grow, todos archived on their side join mine, taking
+        // my copy off its page when I still have it there
+        for their_todo in their_page.archived {
+            if is_archived(mine, &their_todo) {
+                continue;
+            }
+            let archived = match position_of(mine, &their_todo) {
+                Some((p, t)) => mine[p].todos.remove(t),
+                None => their_todo,
+            };
+            mine[page_index].archived.push(archived);
+        }
+
+        for their_todo in their_page.todos {
+            let base_todo = base.and_then(|b| find_todo(b, &their_todo));
+
+            match position_of(mine, &their_todo) {
+                Some((p, t)) => {
+                    if merge_todo(&mut mine[p].todos[t], &their_todo, base_todo, &mut report) {
+                        report.updated_todos += 1;
+                    }
+                }
+                None => {
+                    // A todo I deleted stays deleted unless they edited it since,
+                    // one I archived stays archived
+                    let deleted_by_me =
+                        base_todo.is_some_and(|b| b.last_modified() >= their_todo.last_modified());
+                    if !deleted_by_me && !is_archived(mine, &their_todo) {
+                        mine[page_index].todos.push(their_todo);
+                        report.added_todos += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn page(todos: &[&Todo]) -> Vec<TodoPage> {
+        let mut page = TodoPage::new("Work".to_string());
+        page.todos = todos.iter().map(|&todo| todo.clone()).collect();
+        vec![page]
+    }
+
+    // A copy of `todo` edited `minutes` after it was created
+    fn edited(todo: &Todo, minutes: i64, edit: impl FnOnce(&mut Todo)) -> Todo {
+        let mut todo = todo.clone();
+        edit(&mut todo);
+        todo.updated_at = Some(todo.created_at + Duration::minutes(minutes));
+        todo
+    }
+
+    #[test]
+    fn change_on_one_side_is_kept() {
+        let report = Todo::new("Write report".to_string());
+        let plan = Todo::new("Plan sprint".to_string());
+        let base = page(&[&report, &plan]);
+        let mut mine = page(&[
+            &edited(&report, 5, |t| t.description = "Write the report".into()),
+            &plan,
+        ]);
+        let theirs = page(&[&report, &edited(&plan, 1, |t| t.completed = true)]);
+
+        let result = merge_pages(&mut mine, theirs, Some(&base));
+        assert_eq!(result.updated_todos, 1);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(mine[0].todos[0].description, "Write the report");
+        assert!(mine[0].todos[1].completed);
+    }
+
+    #[test]
+    fn deletion_with_a_base_is_kept() {
+        let report = Todo::new("Write report".to_string());
+        let plan = Todo::new("Plan sprint".to_string());
+        let base = page(&[&report, &plan]);
+        let mut mine = page(&[&report, &plan]);
+        let theirs = page(&[&plan]);
+
+        let result = merge_pages(&mut mine, theirs.clone(), Some(&base));
+        assert_eq!(result.removed_todos, 1);
+        assert_eq!(mine[0].todos.len(), 1);
+        assert_eq!(mine[0].todos[0].description, "Plan sprint");
+
+        // Without a base nothing is deleted
+        let mut mine = page(&[&report, &plan]);
+        merge_pages(&mut mine, theirs, None);
+        assert_eq!(mine[0].todos.len(), 2);
+    }
+
+    #[test]
+    fn newest_edit_wins_a_conflict() {
+        let report = Todo::new("Write report".to_string());
+        let base = page(&[&report]);
+        let mut mine = page(&[&edited(&report, 1, |t| t.description = "Mine".into())]);
+        let theirs = page(&[&edited(&report, 2, |t| t.description = "Theirs".into())]);
+
+        let result = merge_pages(&mut mine, theirs, Some(&base));
+        assert_eq!(mine[0].todos[0].description, "Theirs");
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.field, "description");
+        assert_eq!(conflict.mine, "Mine");
+        assert!(!conflict.kept_mine);
+        let newest = report.created_at + Duration::minutes(2);
+        assert_eq!(mine[0].todos[0].updated_at, Some(newest));
+    }
+}
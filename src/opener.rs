@@ -0,0 +1,31 @@
+use crate::config;
+use std::io;
+use std::process::{Command, Stdio};
+
+// Open a file path or URL with the system's default application, without
+// waiting for it so the TUI stays responsive
+pub fn open(target: &str) -> io::Result<()> {
+    let target = if target.contains("://") {
+        target.into()
+    } else {
+        config::expand_home(target)?.into_os_string()
+    };
+
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
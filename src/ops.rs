@@ -0,0 +1,230 @@
+use crate::todo::{parse_due_date, parse_tag, Todo, TodoPage};
+use serde::Deserialize;
+use uuid::Uuid;
+
+// Picks an existing todo by ID, or by description within an optional page
+#[derive(Deserialize, Debug, Clone)]
+pub struct Selector {
+    pub id: Option<Uuid>,
+    pub page: Option<String>,
+    pub description: Option<String>,
+}
+
+// One step of a `ratdo apply` batch file
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    Add {
+        page: String,
+        description: String,
+        due: Option<String>,
+        scheduled: Option<String>,
+    },
+    Complete {
+        #[serde(flatten)]
+        todo: Selector,
+    },
+    Move {
+        #[serde(flatten)]
+        todo: Selector,
+        to: String,
+    },
+    Retag {
+        #[serde(flatten)]
+        todo: Selector,
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+}
+
+impl Operation {
+    // One line description for the audit log
+    pub fn summary(&self) -> String {
+        match self {
+            Operation::Add {
+                page, description, ..
+            } => format!("add [{}] {}", page, description),
+            Operation::Complete { todo } => format!("complete {}", todo.summary()),
+            Operation::Move { todo, to } => format!("move {} to [{}]", todo.summary(), to),
+            Operation::Retag { todo, add, remove } => {
+                let changes = add
+                    .iter()
+                    .map(|tag| format!("+{}", tag))
+                    .chain(remove.iter().map(|tag| format!("-{}", tag)));
+                format!(
+                    "retag {} {}",
+                    todo.summary(),
+                    changes.collect::<Vec<_>>().join(" ")
+                )
+            }
+        }
+    }
+}
+
+impl Selector {
+    fn summary(&self) -> String {
+        match (&self.id, &self.page, &self.description) {
+            (Some(id), _, _) => id.to_string(),
+            (None, Some(page), Some(description)) => format!("[{}] {}", page, description),
+            (None, None, Some(description)) => description.clone(),
+            _ => "<nothing>".to_string(),
+        }
+    }
+
+    fn matches(&self, page: &TodoPage, todo: &Todo) -> bool {
+        if let Some(id) = self.id {
+            return todo.id == id;
+        }
+        self.page.as_ref().is_none_or(|name| *name == page.name)
+            && self.description.as_ref() == Some(&todo.description)
+    }
+
+    // Position of the single todo the selector refers to
+    fn locate(&self, pages: &[TodoPage]) -> Result<(usize, usize), String> {
+        let mut found = pages.iter().enumerate().flat_map(|(p, page)| {
+            page.todos
+                .iter()
+                .enumerate()
+                .filter(move |(_, todo)| self.matches(page, todo))
+                .map(move |(t, _)| (p, t))
+        });
+
+        match (found.next(), found.next()) {
+            (Some(position), None) => Ok(position),
+            (Some(_), Some(_)) => Err(format!("{} matches more than one todo", self.summary())),
+            (None, _) => Err(format!("{} matches no todo", self.summary())),
+        }
+    }
+}
+
+fn page_index(pages: &mut Vec<TodoPage>, name: &str) -> usize {
+    match pages.iter().position(|p| p.name == name) {
+        Some(index) => index,
+        None => {
+            pages.push(TodoPage::new(name.to_string()));
+            pages.len() - 1
+        }
+    }
+}
+
+fn apply_operation(pages: &mut Vec<TodoPage>, operation: &Operation) -> Result<(), String> {
+    match operation {
+        Operation::Add {
+            page,
+            description,
+            due,
+            scheduled,
+        } => {
+            let description = description.trim();
+            if description.is_empty() {
+                return Err("cannot add a todo with an empty description".to_string());
+            }
+
+            let mut todo = Todo::new(description.to_string());
+            if let Some(due) = due {
+                todo.due_date =
+                    Some(parse_due_date(due).ok_or(format!("invalid due date {}", due))?);
+            }
+            if let Some(scheduled) = scheduled {
+                todo.scheduled = Some(
+                    parse_due_date(scheduled)
+                        .ok_or(format!("invalid scheduled date {}", scheduled))?,
+                );
+            }
+
+            let index = page_index(pages, page);
+            pages[index].todos.push(todo);
+        }
+        Operation::Complete { todo } => {
+            let (p, t) = todo.locate(pages)?;
+            pages[p].todos[t].set_completed(true);
+        }
+        Operation::Move { todo, to } => {
+            let (p, t) = todo.locate(pages)?;
+            let mut todo = pages[p].todos.remove(t);
+            todo.touch();
+
+            let index = page_index(pages, to);
+            pages[index].todos.push(todo);
+        }
+        Operation::Retag { todo, add, remove } => {
+            let (p, t) = todo.locate(pages)?;
+            let todo = &mut pages[p].todos[t];
+            for tag in add {
+                let tag = parse_tag(&format!("+{}", tag)).ok_or(format!("invalid tag {}", tag))?;
+                if !todo.tags.contains(&tag) {
+                    todo.tags.push(tag);
+                }
+            }
+            todo.tags.retain(|tag| {
+                !remove
+                    .iter()
+                    .any(|removed| removed.eq_ignore_ascii_case(tag))
+            });
+            todo.touch();
+        }
+    }
+    Ok(())
+}
+
+// Apply all operations or none of them. On failure the pages are left
+// untouched and the error names the operation that failed.
+pub fn apply_operations(pages: &mut Vec<TodoPage>, operations: &[Operation]) -> Result<(), String> {
+    let mut updated = pages.clone();
+
+    for (index, operation) in operations.iter().enumerate() {
+        apply_operation(&mut updated, operation)
+            .map_err(|err| format!("operation {} ({}): {}", index + 1, operation.summary(), err))?;
+    }
+
+    *pages = updated;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operations(json: &str) -> Vec<Operation> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn retag_adds_and_removes_tags() {
+        let mut page = TodoPage::new("Home".to_string());
+        let mut todo = Todo::new("Call plumber".to_string());
+        todo.tags = vec!["later".to_string(), "phone".to_string()];
+        page.todos.push(todo);
+        let mut pages = vec![page];
+
+        let ops = operations(
+            r#"[{ "op": "retag", "description": "Call plumber", "add": ["Urgent", "phone"], "remove": ["later"] }]"#,
+        );
+        apply_operations(&mut pages, &ops).unwrap();
+        assert_eq!(pages[0].todos[0].tags, ["phone", "urgent"]);
+        assert_eq!(ops[0].summary(), "retag Call plumber +Urgent +phone -later");
+    }
+
+    #[test]
+    fn failed_retag_changes_nothing() {
+        let mut page = TodoPage::new("Home".to_string());
+        page.todos.push(Todo::new("Call plumber".to_string()));
+        let mut pages = vec![page];
+
+        let ops = operations(
+            r#"[
+                { "op": "add", "page": "Home", "description": "Fix sink" },
+                { "op": "retag", "description": "Call plumber", "add": ["two words"] }
+            ]"#,
+        );
+        let err = apply_operations(&mut pages, &ops).unwrap_err();
+        assert_eq!(
+            err,
+            "operation 2 (retag Call plumber +two words): invalid tag two words"
+        );
+        assert_eq!(pages[0].todos.len(), 1);
+        assert!(pages[0].todos[0].tags.is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+use crate::bordered;
+use crate::theme::Theme;
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Margin, Rect},
+    style::Style,
+    text::Line,
+    widgets::{
+        Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState,
+    },
+    Frame,
+};
+
+// Rows PageUp and PageDown move by in popups
+const PAGE: usize = 10;
+
+// A popup over the middle of the screen listing entries or lines of text: as
+// wide as its longest entry unless given a width, as tall as its entries up to
+// the screen, with a scrollbar on the right border once they don't fit. Used
+// by the page list, the help and the pickers, which move through it with
+// `navigate` and `scroll`.
+pub struct ScrollPopup<'a> {
+    theme: &'a Theme,
+    title: Line<'a>,
+    width: Option<u16>,
+}
+
+impl<'a> ScrollPopup<'a> {
+    pub fn new(theme: &'a Theme, title: impl Into<Line<'a>>) -> Self {
+        Self {
+            theme,
+            title: title.into(),
+            width: None,
+        }
+    }
+
+    pub fn width(mut self, width: u16) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    // Entries picked with `state`, the selected one kept in view
+    pub fn render_list(self, f: &mut Frame, items: Vec<ListItem<'a>>, state: &mut ListState) {
+        let longest = items.iter().map(ListItem::width).max().unwrap_or(0);
+        // Entries can take several lines
+        let rows = items.iter().map(ListItem::height).sum();
+        let area = self.area(f.area(), longest + 4, rows);
+
+        f.render_widget(Clear, area);
+        let list = List::new(items)
+            .block(bordered(self.theme).title(self.title.clone()))
+            .highlight_style(Style::default().fg(self.theme.selection))
+            .highlight_symbol(" > ");
+        f.render_stateful_widget(list, area, state);
+        self.render_scrollbar(f, area, rows, state.offset());
+    }
+
+    // Lines scrolled down by `scroll`, which stops once the last line is at
+    // the bottom
+    pub fn render_text(self, f: &mut Frame, lines: Vec<Line<'a>>, scroll: &mut u16) {
+        let longest = lines.iter().map(Line::width).max().unwrap_or(0);
+        let rows = lines.len();
+        let area = self.area(f.area(), longest + 1, rows);
+
+        let max_scroll = rows.saturating_sub(area.height.saturating_sub(2) as usize);
+        *scroll = (*scroll).min(max_scroll as u16);
+
+        f.render_widget(Clear, area);
+        let text = Paragraph::new(lines)
+            .scroll((*scroll, 0))
+            .block(bordered(self.theme).title(self.title.clone()));
+        f.render_widget(text, area);
+        self.render_scrollbar(f, area, rows, *scroll as usize);
+    }
+
+    // Centered on the screen, at least 20 columns wide when it fits
+    fn area(&self, screen: Rect, content_width: usize, rows: usize) -> Rect {
+        let width = self
+            .width
+            .unwrap_or((content_width + 2).max(self.title.width() + 4).max(20) as u16)
+            .min(screen.width);
+        let height = (rows as u16).saturating_add(2).min(screen.height);
+        Rect::new(
+            (screen.width - width) / 2,
+            (screen.height - height) / 2,
+            width,
+            height,
+        )
+    }
+
+    fn render_scrollbar(&self, f: &mut Frame, area: Rect, rows: usize, offset: usize) {
+        let visible = area.height.saturating_sub(2) as usize;
+        if rows <= visible {
+            return;
+        }
+        let mut state = ScrollbarState::new(rows - visible)
+            .viewport_content_length(visible)
+            .position(offset);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .style(Style::default().fg(self.theme.muted)),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut state,
+        );
+    }
+}
+
+// Move the selection of a list popup with `count` entries: Down/j and Up/k
+// step and wrap around, PageDown/PageUp jump and Home/End go to either end
+pub fn navigate(state: &mut ListState, count: usize, code: KeyCode) {
+    let Some(last) = count.checked_sub(1) else {
+        return;
+    };
+    let selected = state.selected().map(|i| i.min(last));
+    let i = match code {
+        KeyCode::Down | KeyCode::Char('j') => selected.map_or(0, |i| (i + 1) % count),
+        KeyCode::Up | KeyCode::Char('k') => selected.map_or(last, |i| (i + last) % count),
+        KeyCode::PageDown => selected.map_or(0, |i| (i + PAGE).min(last)),
+        KeyCode::PageUp => selected.map_or(0, |i| i.saturating_sub(PAGE)),
+        KeyCode::Home => 0,
+        KeyCode::End => last,
+        _ => return,
+    };
+    state.select(Some(i));
+}
+
+// Scroll a text popup with the keys of `navigate`, without wrapping around.
+// Rendering stops it at the last line.
+pub fn scroll(scroll: &mut u16, code: KeyCode) {
+    *scroll = match code {
+        KeyCode::Down | KeyCode::Char('j') => scroll.saturating_add(1),
+        KeyCode::Up | KeyCode::Char('k') => scroll.saturating_sub(1),
+        KeyCode::PageDown => scroll.saturating_add(PAGE as u16),
+        KeyCode::PageUp => scroll.saturating_sub(PAGE as u16),
+        KeyCode::Home => 0,
+        KeyCode::End => u16::MAX,
+        _ => return,
+    };
+}
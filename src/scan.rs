@@ -0,0 +1,178 @@
+use crate::todo::{Todo, TodoPage};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// Comment markers picked up by `ratdo scan`, tagged with their lowercase name
+const MARKERS: [&str; 2] = ["TODO:", "FIXME:"];
+
+// Directories of dependencies and build output rather than own code, skipped
+// like hidden ones
+const SKIPPED_DIRS: [&str; 3] = ["target", "node_modules", "vendor"];
+
+// Larger files are taken for data and not read
+const MAX_FILE_SIZE: u64 = 1 << 20;
+
+// A TODO or FIXME comment of a source file
+#[derive(Debug, PartialEq)]
+pub struct Found {
+    pub path: PathBuf,
+    pub line: usize,
+    pub marker: &'static str,
+    pub text: String,
+}
+
+impl Found {
+    // FNV-1a of the marker and text, the same between runs and builds, so a
+    // comment moving to another line or file isn't imported again
+    pub fn hash(&self) -> String {
+        let hash = format!("{} {}", self.marker, self.text)
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, b| {
+                (hash ^ b as u64).wrapping_mul(0x100000001b3)
+            });
+        format!("{:016x}", hash)
+    }
+
+    pub fn into_todo(self) -> Todo {
+        let mut todo = Todo::new(self.text.clone());
+        todo.notes = format!("{}:{}", self.path.display(), self.line);
+        todo.tags = vec![self.marker.trim_end_matches(':').to_lowercase()];
+        todo.scanned = Some(self.hash());
+        todo
+    }
+}
+
+// TODO and FIXME comments of the text files under `dir`, in path and line
+// order. Files that aren't UTF-8 are skipped.
+pub fn scan(dir: &Path) -> io::Result<Vec<Found>> {
+    let mut found = Vec::new();
+    scan_dir(dir, &mut found)?;
+    found.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    Ok(found)
+}
+
+// The found comments that weren't imported into `pages` by an earlier scan,
+// archived todos included
+pub fn unimported(found: Vec<Found>, pages: &[TodoPage]) -> Vec<Found> {
+    let imported: HashSet<&String> = pages
+        .iter()
+        .flat_map(|p| p.todos.iter().chain(&p.archived))
+        .filter_map(|todo| todo.scanned.as_ref())
+        .collect();
+    found
+        .into_iter()
+        .filter(|found| !imported.contains(&found.hash()))
+        .collect()
+}
+
+fn scan_dir(dir: &Path, found: &mut Vec<Found>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let kind = entry.file_type()?;
+        if kind.is_dir() && !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+            scan_dir(&entry.path(), found)?;
+        } else if kind.is_file() && entry.metadata()?.len() <= MAX_FILE_SIZE {
+            let Ok(content) = fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            found.extend(scan_text(&entry.path(), &content));
+        }
+    }
+    Ok(())
+}
+
+fn scan_text(path: &Path, content: &str) -> Vec<Found> {
+    let mut found = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let Some((marker, start)) = MARKERS
+            .iter()
+            .find_map(|marker| Some((*marker, line.find(marker)?)))
+        else {
+            continue;
+        };
+        // Closing comment delimiters aren't part of the text
+        let text = line[start + marker.len()..]
+            .trim()
+            .trim_end_matches("*/")
+            .trim_end_matches("-->")
+            .trim();
+        if !text.is_empty() {
+            found.push(Found {
+                path: path.to_path_buf(),
+                line: index + 1,
+                marker,
+                text: text.to_string(),
+            });
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+
+    fn texts(found: &[Found]) -> Vec<&str> {
+        found.iter().map(|f| f.text.as_str()).collect()
+    }
+
+    #[test]
+    fn markers_in_comments() {
+        let content = "fn main() {\n    // TODO: handle errors\n    /* FIXME: leaks */\n    // TODO:\n}\n<!-- TODO: link docs -->";
+        let found = scan_text(Path::new("main.rs"), content);
+        assert_eq!(texts(&found), ["handle errors", "leaks", "link docs"]);
+        assert_eq!(found[0].line, 2);
+        assert_eq!(found[1].marker, "FIXME:");
+
+        let todo = scan_text(Path::new("main.rs"), content)
+            .remove(1)
+            .into_todo();
+        assert_eq!(todo.description, "leaks");
+        assert_eq!(todo.tags, ["fixme"]);
+        assert_eq!(todo.notes, "main.rs:3");
+    }
+
+    #[test]
+    fn rescan_skips_imported_comments() {
+        let dir = TempDir::new("scan-rescan");
+        dir.write("a.rs", "// TODO: first\n// TODO: second\n");
+        let mut page = TodoPage::new("Code".to_string());
+        page.todos = scan(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(Found::into_todo)
+            .collect();
+        let archived = page.todos.remove(1);
+        page.archived.push(archived);
+
+        // Moved to another line and file, with a new one next to it
+        dir.write("a.rs", "// TODO: third\n");
+        dir.write("b.rs", "\n\n// TODO: first\n// TODO: second\n");
+        let new = unimported(scan(dir.path()).unwrap(), &[page]);
+        assert_eq!(texts(&new), ["third"]);
+    }
+
+    #[test]
+    fn hidden_and_dependency_dirs_are_skipped() {
+        let dir = TempDir::new("scan-skipped");
+        dir.write("src/lib.rs", "// TODO: own code");
+        dir.write(".git/hooks/pre-commit", "# TODO: hook");
+        dir.write("target/debug/out.rs", "// TODO: generated");
+        dir.write("node_modules/pkg/index.js", "// TODO: dependency");
+        dir.write(
+            "big.txt",
+            &format!("TODO: data\n{}", "x".repeat(MAX_FILE_SIZE as usize)),
+        );
+        fs::write(dir.join("image.bin"), [0xff, 0xfe, b'T']).unwrap();
+
+        let found = scan(dir.path()).unwrap();
+        assert_eq!(texts(&found), ["own code"]);
+        assert_eq!(found[0].path, dir.join("src/lib.rs"));
+    }
+}
@@ -0,0 +1,44 @@
+use crate::config;
+use crate::insights::Usage;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+// TUI state carried over between runs in ~/.config/ratdo/session.json
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Session {
+    // Name of the active workspace, all pages are shown without one
+    pub workspace: Option<String>,
+    // Page labels by last visit, most recent first
+    pub recent_pages: Vec<String>,
+    // Day the carry-over rule last ran on
+    pub carried_over: Option<NaiveDate>,
+    // Day the start-of-day summary was last opened on
+    pub summarized: Option<NaiveDate>,
+    // Actions used, for `ratdo insights`
+    pub usage: Usage,
+}
+
+impl Session {
+    fn path() -> io::Result<PathBuf> {
+        Ok(config::config_dir()?.join("session.json"))
+    }
+
+    // Read the session, the defaults when there is none or it can't be parsed
+    pub fn load() -> io::Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?).unwrap_or_default())
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+    }
+}
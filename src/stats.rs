@@ -0,0 +1,158 @@
+use crate::todo::{Todo, TodoPage};
+use chrono::{Days, NaiveDate};
+
+// Open and completed todos of a page, archived ones counted as completed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageStats {
+    pub open: usize,
+    pub done: usize,
+}
+
+impl PageStats {
+    // Share of the todos that are done, in percent
+    pub fn completion(&self) -> usize {
+        match self.open + self.done {
+            0 => 0,
+            total => self.done * 100 / total,
+        }
+    }
+}
+
+pub fn page_stats(page: &TodoPage) -> PageStats {
+    let done = page.todos.iter().filter(|t| t.completed).count();
+    let archived = page.archived.iter().filter(|t| t.completed).count();
+    PageStats {
+        open: page.todos.len() - done,
+        done: done + archived,
+    }
+}
+
+// Todos of all pages completed on each of the `days` days up to today, the
+// oldest day first. Archived todos count too.
+pub fn completed_per_day(pages: &[TodoPage], today: NaiveDate, days: u64) -> Vec<(NaiveDate, u64)> {
+    let first = today - Days::new(days.saturating_sub(1));
+    let mut counts: Vec<(NaiveDate, u64)> = (0..days).map(|i| (first + Days::new(i), 0)).collect();
+
+    let todos = pages.iter().flat_map(|p| p.todos.iter().chain(&p.archived));
+    for done in todos.filter_map(|t| t.completed_at) {
+        let day = done.date_naive();
+        if day < first || day > today {
+            continue;
+        }
+        if let Some((_, count)) = counts.get_mut((day - first).num_days() as usize) {
+            *count += 1;
+        }
+    }
+    counts
+}
+
+// Page and index of the `count` open todos created longest ago, oldest first
+pub fn oldest_open(pages: &[TodoPage], count: usize) -> Vec<(usize, usize)> {
+    let mut open: Vec<(usize, usize)> = pages
+        .iter()
+        .enumerate()
+        .flat_map(|(page_index, page)| {
+            page.todos
+                .iter()
+                .enumerate()
+                .filter(|(_, todo)| !todo.completed)
+                .map(move |(index, _)| (page_index, index))
+        })
+        .collect();
+    open.sort_by_key(|&(page, index)| pages[page].todos[index].created_at);
+    open.truncate(count);
+    open
+}
+
+// Todos of all pages completed on `day`, archived ones included
+pub fn completed_on(pages: &[TodoPage], day: NaiveDate) -> Vec<&Todo> {
+    pages
+        .iter()
+        .flat_map(|p| p.todos.iter().chain(&p.archived))
+        .filter(|t| t.completed && t.completed_at.is_some_and(|done| done.date_naive() == day))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Local};
+
+    fn todo(description: &str, completed_days_ago: Option<i64>) -> Todo {
+        let mut todo = Todo::new(description.to_string());
+        if let Some(days) = completed_days_ago {
+            todo.completed = true;
+            todo.completed_at = Some(Local::now() - Duration::days(days));
+        }
+        todo
+    }
+
+    fn page(todos: Vec<Todo>, archived: Vec<Todo>) -> TodoPage {
+        let mut page = TodoPage::new("Work".to_string());
+        page.todos = todos;
+        page.archived = archived;
+        page
+    }
+
+    #[test]
+    fn page_stats_count_the_archive_as_done() {
+        let empty = page(Vec::new(), Vec::new());
+        assert_eq!(page_stats(&empty), PageStats::default());
+        assert_eq!(page_stats(&empty).completion(), 0);
+
+        let work = page(
+            vec![
+                todo("Open", None),
+                todo("Done", Some(0)),
+                todo("Open too", None),
+            ],
+            vec![todo("Archived", Some(3))],
+        );
+        assert_eq!(page_stats(&work), PageStats { open: 2, done: 2 });
+        assert_eq!(page_stats(&work).completion(), 50);
+    }
+
+    #[test]
+    fn completed_per_day_within_the_range() {
+        let today = Local::now().date_naive();
+        let pages = [page(
+            vec![
+                todo("Today", Some(0)),
+                todo("Too old", Some(7)),
+                todo("Open", None),
+            ],
+            vec![todo("Yesterday", Some(1)), todo("Also today", Some(0))],
+        )];
+
+        let counts: Vec<u64> = completed_per_day(&pages, today, 7)
+            .iter()
+            .map(|&(_, n)| n)
+            .collect();
+        assert_eq!(counts, [0, 0, 0, 0, 0, 1, 2]);
+        assert_eq!(completed_per_day(&pages, today, 7)[6].0, today);
+        assert_eq!(completed_per_day(&pages, today, 1), [(today, 2)]);
+        assert!(completed_per_day(&pages, today, 0).is_empty());
+        assert_eq!(completed_on(&pages, today).len(), 2);
+    }
+
+    #[test]
+    fn oldest_open_first_ties_in_page_order() {
+        let mut first = todo("First", None);
+        let mut second = todo("Second", None);
+        let third = todo("Third", None);
+        second.created_at = first.created_at;
+        first.created_at -= Duration::days(1);
+        let pages = [
+            page(vec![todo("Done", Some(0)), second], Vec::new()),
+            page(vec![third, first], Vec::new()),
+        ];
+        // Sorting is stable, so todos created at the same time keep the page order
+        let mut tied = pages.clone();
+        tied[1].todos[0].created_at = tied[0].todos[1].created_at;
+
+        assert_eq!(oldest_open(&pages, 2), [(1, 1), (0, 1)]);
+        assert_eq!(oldest_open(&tied, 3), [(1, 1), (0, 1), (1, 0)]);
+        assert!(oldest_open(&pages, 0).is_empty());
+        assert!(oldest_open(&[], 5).is_empty());
+    }
+}
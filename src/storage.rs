@@ -0,0 +1,308 @@
+use crate::agenda::Postponed;
+use crate::config;
+use crate::daemon;
+use crate::merge::{self, Conflict};
+use crate::session::Session;
+use crate::summary::Summary;
+use crate::template::{self, Template};
+use crate::todo::{self, TodoPage};
+use chrono::{DateTime, Local};
+use std::{
+    fs::{self, OpenOptions},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+// A todo file loaded into the session
+pub struct Store {
+    pub name: String,
+    pub path: PathBuf,
+    // Pages as last read or saved, the base a merge of the next save with
+    // changes made elsewhere starts from
+    pub base: Option<Vec<TodoPage>>,
+    // Hash of the file as last read or saved, to tell whether something else
+    // wrote it since
+    pub hash: Option<u64>,
+    // When the file was last written
+    pub saved_at: Option<DateTime<Local>>,
+}
+
+// Where a session keeps what outlives it: the todo files, the session state,
+// templates and settings changed from the TUI. Everything read or written
+// goes through here, so no path can forget about throwaway sessions.
+pub trait Storage {
+    // Read the pages of a store
+    fn load(&mut self, store: &mut Store) -> io::Result<Vec<TodoPage>>;
+
+    // Save the pages of a store, merged with what was saved there since it was
+    // read. Returns the pages as saved and the fields changed on both sides.
+    fn save(
+        &mut self,
+        store: &mut Store,
+        pages: Vec<TodoPage>,
+    ) -> io::Result<(Vec<TodoPage>, Vec<Conflict>)>;
+
+    fn save_summary(&mut self, summary: &Summary) -> io::Result<()>;
+
+    fn load_session(&self) -> io::Result<Session>;
+
+    fn save_session(&mut self, session: &Session) -> io::Result<()>;
+
+    fn load_templates(&self) -> io::Result<Vec<Template>>;
+
+    fn save_templates(&mut self, templates: &[Template]) -> io::Result<()>;
+
+    // Change a top-level setting in the config file
+    fn set_config(&mut self, key: &str, value: &str) -> io::Result<()>;
+
+    // Add lines to the record of changes made by `ratdo apply`
+    fn append_log(&mut self, lines: &[String]) -> io::Result<()>;
+
+    // Due dates moved by the last `ratdo postpone`, None once undone
+    fn load_postponed(&self) -> io::Result<Option<Vec<Postponed>>>;
+
+    fn save_postponed(&mut self, postponed: Option<&[Postponed]>) -> io::Result<()>;
+
+    // Shown in the title for sessions that don't keep everything
+    fn label(&self) -> Option<&'static str> {
+        None
+    }
+
+    // Whether the todos outlive the session
+    fn persistent(&self) -> bool {
+        true
+    }
+}
+
+// The files in the config directory, through `ratdo daemon` when it runs
+pub struct Files {
+    // Started without the config file (--safe), which is then left alone
+    // along with the session
+    pub safe: bool,
+}
+
+impl Storage for Files {
+    fn load(&mut self, store: &mut Store) -> io::Result<Vec<TodoPage>> {
+        let pages = match daemon::read(&store.path) {
+            Some(read) => read?,
+            None => {
+                let pages = todo::read_pages(&store.path)?;
+                store.hash = file_hash(&store.path)?;
+                pages
+            }
+        };
+        store.saved_at = fs::metadata(&store.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(DateTime::from);
+        Ok(pages)
+    }
+
+    // A file written by something else since it was read, another ratdo or a
+    // sync tool, isn't overwritten: its changes are merged in field by field.
+    // With `ratdo daemon` running it does the merging and writing.
+    fn save(
+        &mut self,
+        store: &mut Store,
+        mut pages: Vec<TodoPage>,
+    ) -> io::Result<(Vec<TodoPage>, Vec<Conflict>)> {
+        let mut conflicts = Vec::new();
+        match daemon::write(&store.path, store.base.clone(), pages.clone()) {
+            Some(written) => {
+                let (merged, found) = written?;
+                pages = merged;
+                conflicts = found;
+            }
+            None => {
+                if file_hash(&store.path)? != store.hash {
+                    let theirs = todo::read_pages(&store.path)?;
+                    let report = merge::merge_pages(&mut pages, theirs, store.base.as_deref());
+                    conflicts = report.conflicts;
+                }
+                todo::write_pages(&store.path, &pages.iter().collect::<Vec<_>>())?;
+                store.hash = file_hash(&store.path)?;
+            }
+        }
+        store.saved_at = Some(Local::now());
+        Ok((pages, conflicts))
+    }
+
+    fn save_summary(&mut self, summary: &Summary) -> io::Result<()> {
+        summary.save()
+    }
+
+    fn load_session(&self) -> io::Result<Session> {
+        Session::load()
+    }
+
+    fn save_session(&mut self, session: &Session) -> io::Result<()> {
+        match self.safe {
+            true => Ok(()),
+            false => session.save(),
+        }
+    }
+
+    fn load_templates(&self) -> io::Result<Vec<Template>> {
+        template::load()
+    }
+
+    fn save_templates(&mut self, templates: &[Template]) -> io::Result<()> {
+        template::save(templates)
+    }
+
+    fn set_config(&mut self, key: &str, value: &str) -> io::Result<()> {
+        match self.safe {
+            true => Ok(()),
+            false => config::set(key, value),
+        }
+    }
+
+    fn append_log(&mut self, lines: &[String]) -> io::Result<()> {
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(config::config_dir()?.join("apply.log"))?;
+        for line in lines {
+            writeln!(log, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    fn load_postponed(&self) -> io::Result<Option<Vec<Postponed>>> {
+        match fs::read_to_string(config::config_dir()?.join("postpone.json")) {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn save_postponed(&mut self, postponed: Option<&[Postponed]>) -> io::Result<()> {
+        let path = config::config_dir()?.join("postpone.json");
+        match postponed {
+            Some(postponed) => fs::write(path, serde_json::to_string_pretty(postponed)?),
+            None => fs::remove_file(path),
+        }
+    }
+
+    fn label(&self) -> Option<&'static str> {
+        self.safe.then_some("safe mode")
+    }
+}
+
+// A throwaway session (--ephemeral) starting blank, what it saves stays in
+// memory until it ends
+#[derive(Default)]
+pub struct Memory {
+    session: Session,
+    templates: Vec<Template>,
+    postponed: Option<Vec<Postponed>>,
+}
+
+impl Storage for Memory {
+    fn load(&mut self, _store: &mut Store) -> io::Result<Vec<TodoPage>> {
+        Ok(Vec::new())
+    }
+
+    fn save(
+        &mut self,
+        _store: &mut Store,
+        pages: Vec<TodoPage>,
+    ) -> io::Result<(Vec<TodoPage>, Vec<Conflict>)> {
+        Ok((pages, Vec::new()))
+    }
+
+    fn save_summary(&mut self, _summary: &Summary) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn load_session(&self) -> io::Result<Session> {
+        Ok(self.session.clone())
+    }
+
+    fn save_session(&mut self, session: &Session) -> io::Result<()> {
+        self.session = session.clone();
+        Ok(())
+    }
+
+    fn load_templates(&self) -> io::Result<Vec<Template>> {
+        Ok(self.templates.clone())
+    }
+
+    fn save_templates(&mut self, templates: &[Template]) -> io::Result<()> {
+        self.templates = templates.to_vec();
+        Ok(())
+    }
+
+    fn set_config(&mut self, _key: &str, _value: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn append_log(&mut self, _lines: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn load_postponed(&self) -> io::Result<Option<Vec<Postponed>>> {
+        Ok(self.postponed.clone())
+    }
+
+    fn save_postponed(&mut self, postponed: Option<&[Postponed]>) -> io::Result<()> {
+        self.postponed = postponed.map(<[Postponed]>::to_vec);
+        Ok(())
+    }
+
+    fn label(&self) -> Option<&'static str> {
+        Some("ephemeral")
+    }
+
+    fn persistent(&self) -> bool {
+        false
+    }
+}
+
+// Hash of a file's content, None for a missing file
+fn file_hash(path: &Path) -> io::Result<Option<u64>> {
+    match fs::read(path) {
+        Ok(content) => {
+            let mut hasher = DefaultHasher::new();
+            content.hash(&mut hasher);
+            Ok(Some(hasher.finish()))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+
+    fn store(path: &Path) -> Store {
+        Store {
+            name: "global".to_string(),
+            path: path.to_path_buf(),
+            base: None,
+            hash: None,
+            saved_at: None,
+        }
+    }
+
+    #[test]
+    fn ids_are_written_on_save_only() {
+        let dir = TempDir::new("storage-ids");
+        let file = dir.join("todos.json");
+        let old = r#"[{"name":"Work","todos":[{"description":"Write report","completed":false,"created_at":"2025-03-03T09:00:00+01:00"}]}]"#;
+        fs::write(&file, old).unwrap();
+
+        let mut files = Files { safe: false };
+        let mut store = store(&file);
+        let pages = files.load(&mut store).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), old);
+
+        let id = pages[0].todos[0].id;
+        store.base = Some(pages.clone());
+        files.save(&mut store, pages).unwrap();
+        assert_eq!(todo::read_pages(&file).unwrap()[0].todos[0].id, id);
+    }
+}
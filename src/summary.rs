@@ -0,0 +1,100 @@
+use crate::config;
+use crate::todo::TodoPage;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+// Open and completed counts of a page or of the whole store
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Counts {
+    pub open: usize,
+    pub completed: usize,
+    // Due dates of open todos rather than counts, so due-today and overdue
+    // numbers stay right on the days after the summary was written
+    pub open_due_dates: Vec<NaiveDate>,
+}
+
+impl Counts {
+    fn add_page(&mut self, page: &TodoPage) {
+        for todo in &page.todos {
+            if todo.completed {
+                self.completed += 1;
+            } else {
+                self.open += 1;
+                if let Some(due_date) = todo.due_date {
+                    self.open_due_dates.push(due_date.date_naive());
+                }
+            }
+        }
+    }
+
+    pub fn due_today(&self, today: NaiveDate) -> usize {
+        self.open_due_dates.iter().filter(|&&d| d == today).count()
+    }
+
+    pub fn overdue(&self, today: NaiveDate) -> usize {
+        self.open_due_dates.iter().filter(|&&d| d < today).count()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PageSummary {
+    pub name: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+// Small sidecar of the todo store written on every save, so quick status
+// queries like `ratdo prompt` and `ratdo status` don't have to parse the
+// whole store
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Summary {
+    pub generated_at: DateTime<Local>,
+    #[serde(flatten)]
+    pub totals: Counts,
+    #[serde(default)]
+    pub pages: Vec<PageSummary>,
+}
+
+impl Summary {
+    pub fn from_pages<'a>(pages: impl IntoIterator<Item = &'a TodoPage>) -> Self {
+        let mut summary = Self {
+            generated_at: Local::now(),
+            totals: Counts::default(),
+            pages: Vec::new(),
+        };
+
+        for page in pages {
+            let mut counts = Counts::default();
+            counts.add_page(page);
+            summary.totals.add_page(page);
+            summary.pages.push(PageSummary {
+                name: page.name.clone(),
+                counts,
+            });
+        }
+
+        summary
+    }
+
+    fn path() -> io::Result<PathBuf> {
+        Ok(config::config_dir()?.join("summary.json"))
+    }
+
+    // Read the summary, None when it hasn't been written yet or can't be parsed
+    pub fn load() -> io::Result<Option<Self>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?).ok())
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+    }
+}
@@ -0,0 +1,40 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// A directory of its own for a test, removed with everything in it when the
+// test is done
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    // `name` keeps tests running at the same time apart, e.g. "daemon-merge"
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("ratdo-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    // Path of a file in the directory, which may not exist yet
+    pub fn join(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+
+    // Write a file, creating the directories it is in
+    pub fn write(&self, file: &str, content: &str) {
+        let path = self.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::config;
+use crate::todo::{Priority, Todo};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+// A todo as kept in a template, without dates or progress
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TemplateTodo {
+    pub description: String,
+    #[serde(default)]
+    pub quantity: Option<u32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+}
+
+impl TemplateTodo {
+    pub fn from_todo(todo: &Todo) -> Self {
+        Self {
+            description: todo.description.clone(),
+            quantity: todo.quantity,
+            tags: todo.tags.clone(),
+            notes: todo.notes.clone(),
+            assignee: todo.assignee.clone(),
+            estimate: todo.estimate,
+            attachments: todo.attachments.clone(),
+            priority: todo.priority,
+        }
+    }
+
+    // A fresh open todo with the template's fields
+    pub fn instantiate(&self) -> Todo {
+        let mut todo = Todo::new(self.description.clone());
+        todo.quantity = self.quantity;
+        todo.tags = self.tags.clone();
+        todo.notes = self.notes.clone();
+        todo.assignee = self.assignee.clone();
+        todo.estimate = self.estimate;
+        todo.attachments = self.attachments.clone();
+        todo.priority = self.priority;
+        todo
+    }
+}
+
+// Named set of todos added to a page in one go, e.g. a release checklist
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Template {
+    pub name: String,
+    pub todos: Vec<TemplateTodo>,
+}
+
+fn path() -> io::Result<PathBuf> {
+    Ok(config::config_dir()?.join("templates.json"))
+}
+
+// Read the saved templates, none when the file doesn't exist yet
+pub fn load() -> io::Result<Vec<Template>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    serde_json::from_str(&content).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid templates file {}: {}", path.display(), err),
+        )
+    })
+}
+
+pub fn save(templates: &[Template]) -> io::Result<()> {
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(templates)?)
+}
+
+// Add a todo to the template with this name, which is created when there is
+// none yet. Saving several todos under one name builds up a checklist.
+pub fn add(templates: &mut Vec<Template>, name: &str, todo: TemplateTodo) {
+    match templates
+        .iter_mut()
+        .find(|t| t.name.eq_ignore_ascii_case(name))
+    {
+        Some(template) => template.todos.push(todo),
+        None => templates.push(Template {
+            name: name.to_string(),
+            todos: vec![todo],
+        }),
+    }
+}
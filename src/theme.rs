@@ -0,0 +1,232 @@
+use ratatui::style::Color;
+use serde::{de::Error, Deserialize, Deserializer};
+use std::collections::BTreeMap;
+
+pub const PRESETS: [&str; 5] = [
+    "default",
+    "gruvbox",
+    "solarized",
+    "deuteranopia",
+    "protanopia",
+];
+
+// Colors of the TUI, read from `theme` in the config file: either the name of
+// a preset or a table with an optional `preset` and colors replacing its own
+//
+//     [theme]
+//     preset = "gruvbox"
+//     selection = "#fe8019"
+//
+// Every todo state also has a symbol or word in the list, so none of them is
+// told apart by color alone.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    pub title: Color,
+    // Highlighted row of lists and popups
+    pub selection: Color,
+    pub completed: Color,
+    // Text typed into popups
+    pub popup: Color,
+    pub border: Color,
+    // Section headers and keys in menus
+    pub heading: Color,
+    // Labels, hints and other secondary text
+    pub muted: Color,
+    pub error: Color,
+    pub overdue: Color,
+    pub due_today: Color,
+    pub scheduled: Color,
+    pub priority_high: Color,
+    pub priority_medium: Color,
+    pub priority_low: Color,
+    pub timer: Color,
+    pub tag: Color,
+    pub snoozed: Color,
+    pub review: Color,
+    pub quantity: Color,
+}
+
+// Okabe-Ito colors, told apart with red-green color blindness
+const ORANGE: Color = Color::Rgb(230, 159, 0);
+const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+const YELLOW: Color = Color::Rgb(240, 228, 66);
+const BLUE: Color = Color::Rgb(0, 114, 178);
+const VERMILLION: Color = Color::Rgb(213, 94, 0);
+const PURPLE: Color = Color::Rgb(204, 121, 167);
+
+const DEFAULT: Theme = Theme {
+    title: Color::Yellow,
+    selection: Color::LightYellow,
+    completed: Color::Gray,
+    popup: Color::Yellow,
+    border: Color::Reset,
+    heading: Color::Yellow,
+    muted: Color::Gray,
+    error: Color::Red,
+    overdue: Color::Red,
+    due_today: Color::Yellow,
+    scheduled: Color::Cyan,
+    priority_high: Color::Red,
+    priority_medium: Color::Yellow,
+    priority_low: Color::Blue,
+    timer: Color::Green,
+    tag: Color::Magenta,
+    snoozed: Color::DarkGray,
+    review: Color::LightMagenta,
+    quantity: Color::Cyan,
+};
+
+const GRUVBOX: Theme = Theme {
+    title: Color::Rgb(250, 189, 47),
+    selection: Color::Rgb(254, 128, 25),
+    completed: Color::Rgb(146, 131, 116),
+    popup: Color::Rgb(235, 219, 178),
+    border: Color::Rgb(102, 92, 84),
+    heading: Color::Rgb(250, 189, 47),
+    muted: Color::Rgb(146, 131, 116),
+    error: Color::Rgb(251, 73, 52),
+    overdue: Color::Rgb(251, 73, 52),
+    due_today: Color::Rgb(250, 189, 47),
+    scheduled: Color::Rgb(142, 192, 124),
+    priority_high: Color::Rgb(251, 73, 52),
+    priority_medium: Color::Rgb(250, 189, 47),
+    priority_low: Color::Rgb(131, 165, 152),
+    timer: Color::Rgb(184, 187, 38),
+    tag: Color::Rgb(211, 134, 155),
+    snoozed: Color::Rgb(102, 92, 84),
+    review: Color::Rgb(211, 134, 155),
+    quantity: Color::Rgb(142, 192, 124),
+};
+
+const SOLARIZED: Theme = Theme {
+    title: Color::Rgb(181, 137, 0),
+    selection: Color::Rgb(38, 139, 210),
+    completed: Color::Rgb(88, 110, 117),
+    popup: Color::Rgb(147, 161, 161),
+    border: Color::Rgb(88, 110, 117),
+    heading: Color::Rgb(181, 137, 0),
+    muted: Color::Rgb(101, 123, 131),
+    error: Color::Rgb(220, 50, 47),
+    overdue: Color::Rgb(220, 50, 47),
+    due_today: Color::Rgb(181, 137, 0),
+    scheduled: Color::Rgb(42, 161, 152),
+    priority_high: Color::Rgb(220, 50, 47),
+    priority_medium: Color::Rgb(203, 75, 22),
+    priority_low: Color::Rgb(108, 113, 196),
+    timer: Color::Rgb(133, 153, 0),
+    tag: Color::Rgb(211, 54, 130),
+    snoozed: Color::Rgb(88, 110, 117),
+    review: Color::Rgb(108, 113, 196),
+    quantity: Color::Rgb(42, 161, 152),
+};
+
+impl Theme {
+    pub fn preset(name: &str) -> Option<Self> {
+        let theme = match name {
+            "default" => DEFAULT,
+            "gruvbox" => GRUVBOX,
+            "solarized" => SOLARIZED,
+            // Both avoid red/green pairs. Protanopes see red darker, so theirs
+            // leans orange.
+            "deuteranopia" => Theme {
+                overdue: VERMILLION,
+                priority_high: VERMILLION,
+                ..Self::okabe_ito()
+            },
+            "protanopia" => Theme {
+                overdue: ORANGE,
+                priority_high: ORANGE,
+                ..Self::okabe_ito()
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    fn okabe_ito() -> Self {
+        Theme {
+            due_today: YELLOW,
+            scheduled: SKY_BLUE,
+            priority_medium: YELLOW,
+            priority_low: SKY_BLUE,
+            timer: BLUE,
+            tag: PURPLE,
+            review: PURPLE,
+            quantity: SKY_BLUE,
+            ..DEFAULT
+        }
+    }
+
+    // Replace the color called `name` in the config, false for unknown names
+    fn set(&mut self, name: &str, color: Color) -> bool {
+        let field = match name {
+            "title" => &mut self.title,
+            "selection" => &mut self.selection,
+            "completed" => &mut self.completed,
+            "popup" => &mut self.popup,
+            "border" => &mut self.border,
+            "heading" => &mut self.heading,
+            "muted" => &mut self.muted,
+            "error" => &mut self.error,
+            "overdue" => &mut self.overdue,
+            "due_today" => &mut self.due_today,
+            "scheduled" => &mut self.scheduled,
+            "priority_high" => &mut self.priority_high,
+            "priority_medium" => &mut self.priority_medium,
+            "priority_low" => &mut self.priority_low,
+            "timer" => &mut self.timer,
+            "tag" => &mut self.tag,
+            "snoozed" => &mut self.snoozed,
+            "review" => &mut self.review,
+            "quantity" => &mut self.quantity,
+            _ => return false,
+        };
+        *field = color;
+        true
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        DEFAULT
+    }
+}
+
+impl<'de> Deserialize<'de> for Theme {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Preset(String),
+            Table(BTreeMap<String, String>),
+        }
+
+        let (preset, colors) = match Raw::deserialize(deserializer)? {
+            Raw::Preset(name) => (name, BTreeMap::new()),
+            Raw::Table(mut table) => (
+                table
+                    .remove("preset")
+                    .unwrap_or_else(|| "default".to_string()),
+                table,
+            ),
+        };
+        let mut theme = Theme::preset(&preset).ok_or_else(|| {
+            D::Error::custom(format!(
+                "unknown theme `{}`, expected one of {}",
+                preset,
+                PRESETS.join(", ")
+            ))
+        })?;
+        // Colors are names like "yellow" or "light-blue", "#rrggbb" or an
+        // index into the terminal's 256 colors
+        for (name, value) in colors {
+            let color = value
+                .parse()
+                .map_err(|_| D::Error::custom(format!("invalid color `{}` for {}", value, name)))?;
+            if !theme.set(&name, color) {
+                return Err(D::Error::custom(format!("unknown theme color `{}`", name)));
+            }
+        }
+        Ok(theme)
+    }
+}
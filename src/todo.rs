@@ -1,403 +1,4509 @@
-use chrono::{DateTime, Local};
-use ratatui::widgets::ListState;
+use crate::agenda::{self, AgendaEntry};
+use crate::calendar::Holidays;
+use crate::clipboard;
+use crate::config::{self, CarryOverAction, Config, ConfirmDelete, Density};
+use crate::history::{History, Snapshot};
+use crate::input;
+use crate::insights::Usage;
+use crate::keymap::Action;
+use crate::merge::{self, Conflict};
+use crate::opener;
+use crate::session::Session;
+use crate::storage::{Files, Storage, Store};
+use crate::summary::Summary;
+use crate::template::{self, Template, TemplateTodo};
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveTime, Weekday};
+use crossterm::event::KeyEvent;
+use notify_rust::Notification;
+use ratatui::{layout::Rect, widgets::ListState};
 use serde::{Deserialize, Serialize};
-use std::{env, fs, io, path::PathBuf};
+use std::{
+    collections::HashSet,
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Todo {
+    // Stable identifier used to match todos between copies of a file.
+    // Todos saved before IDs existed read as nil and get one on load.
+    #[serde(default)]
+    pub id: Uuid,
     pub description: String,
     pub completed: bool,
     pub created_at: DateTime<Local>,
+    // How many of the item are needed, only set on pages that track quantities
+    #[serde(default)]
+    pub quantity: Option<u32>,
+    // Time of the last change, missing for todos untouched since creation
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Local>>,
+    // Hard deadline, overdue once it has passed
+    #[serde(default)]
+    pub due_date: Option<DateTime<Local>>,
+    // Softer date for when to work on the todo, missed ones roll forward to today
+    #[serde(default)]
+    pub scheduled: Option<DateTime<Local>>,
+    // Labels entered as `+tag` words, stored without the plus
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Free-form, possibly multi-line details shown in the detail pane
+    #[serde(default)]
+    pub notes: String,
+    // When the todo was last checked off, cleared again when unchecking it
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Local>>,
+    // Time tracked on the todo, not counting a timer that is still running
+    #[serde(default)]
+    pub time_spent: Duration,
+    // Start of the running timer, kept across sessions until it is stopped
+    #[serde(default)]
+    pub timer_started: Option<DateTime<Local>>,
+    // Flagged for someone else to check, listed in the review view
+    #[serde(default)]
+    pub needs_review: bool,
+    // Pinned todos are listed above the others of their page
+    #[serde(default)]
+    pub pinned: bool,
+    // Snoozed todos are hidden until this time
+    #[serde(default)]
+    pub deferred_until: Option<DateTime<Local>>,
+    // Discussion of a shared todo, only ever appended to
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+    // Who is working on the todo, entered as an `@name` word
+    #[serde(default)]
+    pub assignee: Option<String>,
+    // Expected effort in minutes, entered as `~30m`, `~2h` or a t-shirt size
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    // When to show a desktop notification, cleared once it was shown
+    #[serde(default)]
+    pub reminder: Option<DateTime<Local>>,
+    // File paths and URLs opened with `o`
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    // Set with a `!high`, `!med` or `!low` word
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    // Hash of the code comment `ratdo scan` imported the todo from, so later
+    // scans skip it
+    #[serde(default)]
+    pub scanned: Option<String>,
 }
 
-impl Todo {
-    pub fn new(description: String) -> Self {
-        Self {
-            description,
-            completed: false,
-            created_at: Local::now(),
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Comment {
+    pub author: String,
+    pub created_at: DateTime<Local>,
+    pub text: String,
+}
+
+// How a due date relates to the current day
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DueStatus {
+    Overdue,
+    Today,
+    Upcoming,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    // `!high`, `!med` or `!low`, or just their first letter
+    pub fn parse(word: &str) -> Option<Self> {
+        match word.strip_prefix('!')?.to_lowercase().as_str() {
+            "high" | "h" => Some(Priority::High),
+            "medium" | "med" | "m" => Some(Priority::Medium),
+            "low" | "l" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "med",
+            Priority::Low => "low",
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TodoPage {
-    pub name: String,
-    pub todos: Vec<Todo>,
+// Parse a date typed into a date popup: "YYYY-MM-DD", an offset like "+3d" or
+// words like "tomorrow", "fri", "in 3 days" or "next monday", optionally
+// followed by a time of day ("tomorrow 14:30"). Dates without a time are at
+// the start of the day.
+pub fn parse_due_date(input: &str) -> Option<DateTime<Local>> {
+    let input = input.trim();
+    let (date, time) = match input.rsplit_once(' ') {
+        Some((date, time)) => match NaiveTime::parse_from_str(time.trim(), "%H:%M") {
+            Ok(time) => (date.trim(), time),
+            Err(_) => (input, NaiveTime::MIN),
+        },
+        None => (input, NaiveTime::MIN),
+    };
+    parse_date(date, Local::now().date_naive())?
+        .and_time(time)
+        .and_local_timezone(Local)
+        .earliest()
 }
 
-impl TodoPage {
-    pub fn new(name: String) -> Self {
-        Self {
-            name,
-            todos: Vec::new(),
+// The day of a date popup input without its time, relative to `today`
+pub fn parse_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Some(date);
+    }
+    if let Some(days) = parse_offset(input) {
+        return today.checked_add_days(days);
+    }
+
+    let input = input.to_lowercase();
+    let words: Vec<&str> = input.split_whitespace().collect();
+    match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] | ["tmrw"] => today.checked_add_days(Days::new(1)),
+        ["yesterday"] => today.checked_sub_days(Days::new(1)),
+        // The first such day after today
+        [day] => {
+            let weekday = parse_weekday(day)?;
+            let days =
+                (weekday.num_days_from_monday() + 6 - today.weekday().num_days_from_monday()) % 7
+                    + 1;
+            today.checked_add_days(Days::new(days.into()))
+        }
+        // Monday of the coming week
+        ["next", "week"] => {
+            let days = 7 - today.weekday().num_days_from_monday();
+            today.checked_add_days(Days::new(days.into()))
+        }
+        ["next", "month"] => today.checked_add_months(Months::new(1)),
+        // That day in the coming week, which starts on Monday
+        ["next", day] => {
+            let weekday = parse_weekday(day)?;
+            let monday = 7 - today.weekday().num_days_from_monday();
+            today.checked_add_days(Days::new((monday + weekday.num_days_from_monday()).into()))
+        }
+        ["in", count, unit] => {
+            let count: u32 = match *count {
+                "a" | "an" | "one" => 1,
+                count => count.parse().ok()?,
+            };
+            match unit.trim_end_matches('s') {
+                "day" => today.checked_add_days(Days::new(count.into())),
+                "week" => today.checked_add_days(Days::new(u64::from(count) * 7)),
+                "month" => today.checked_add_months(Months::new(count)),
+                "year" => today.checked_add_months(Months::new(count.checked_mul(12)?)),
+                _ => None,
+            }
         }
+        _ => None,
     }
 }
 
-pub enum InputMode {
-    Normal,
-    Editing,
-    PageSelect,
+// "mon", "monday", "tue", "tues", ...
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    const DAYS: [(&str, Weekday); 7] = [
+        ("monday", Weekday::Mon),
+        ("tuesday", Weekday::Tue),
+        ("wednesday", Weekday::Wed),
+        ("thursday", Weekday::Thu),
+        ("friday", Weekday::Fri),
+        ("saturday", Weekday::Sat),
+        ("sunday", Weekday::Sun),
+    ];
+    DAYS.iter()
+        .find(|(name, _)| word.len() >= 3 && name.starts_with(word))
+        .map(|&(_, weekday)| weekday)
 }
 
-// Modify the App struct to track when we're in "pick mode"
-pub struct App {
-    pub pages: Vec<TodoPage>,
-    pub current_page_index: usize,
-    pub state: ListState,
-    pub page_select_state: ListState,
-    pub input_mode: InputMode,
-    pub current_input: String,
-    pub edit_mode: bool,
-    pub picking_mode: bool,
-    pub show_page_selector: bool,
+// Dates at midnight have no time of day and are shown without one
+pub fn format_due_date(date: DateTime<Local>) -> String {
+    if date.time() == NaiveTime::MIN {
+        date.format("%Y-%m-%d").to_string()
+    } else {
+        date.format("%Y-%m-%d %H:%M").to_string()
+    }
 }
 
-impl App {
-    pub fn new() -> Self {
-        let mut state = ListState::default();
-        state.select(Some(0));
+// Parse a day offset like "+1d" or "+2w", the plus is optional
+pub fn parse_offset(input: &str) -> Option<Days> {
+    let input = input.trim();
+    let input = input.strip_prefix('+').unwrap_or(input);
+    if let Some(days) = input.strip_suffix('d') {
+        Some(Days::new(days.parse().ok()?))
+    } else {
+        let weeks: u64 = input.strip_suffix('w')?.parse().ok()?;
+        Some(Days::new(weeks.checked_mul(7)?))
+    }
+}
 
-        let mut page_select_state = ListState::default();
-        page_select_state.select(Some(0));
+// The date of a `due:` word in the add popup, written like in the date popup
+// with dashes for spaces: `due:2025-03-04`, `due:+3d`, `due:fri` or
+// `due:next-monday`
+fn parse_due_word(word: &str) -> Option<Result<DateTime<Local>, String>> {
+    let value = word.strip_prefix("due:")?;
+    let date = parse_due_date(value).or_else(|| parse_due_date(&value.replace('-', " ")));
+    Some(date.ok_or_else(|| {
+        format!(
+            "Invalid due date '{}', expected YYYY-MM-DD, +Nd or e.g. tomorrow, fri, in-3-days",
+            value
+        )
+    }))
+}
 
-        // Create a default page
-        let default_page = TodoPage::new("Default".to_string());
-        let pages = vec![default_page];
+// Common due date shifts offered by the quick reschedule menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reschedule {
+    Tomorrow,
+    NextWorkday,
+    NextWeek,
+    Weekend,
+    NextMonday,
+}
 
-        Self {
-            pages,
-            current_page_index: 0,
-            state,
-            page_select_state,
-            input_mode: InputMode::Normal,
-            current_input: String::new(),
-            edit_mode: false,
-            picking_mode: false,
-            show_page_selector: false,
-        }
-    }
+impl Reschedule {
+    pub const ALL: [Reschedule; 5] = [
+        Reschedule::Tomorrow,
+        Reschedule::NextWorkday,
+        Reschedule::NextWeek,
+        Reschedule::Weekend,
+        Reschedule::NextMonday,
+    ];
 
-    // Current page accessor
-    pub fn current_page(&self) -> &TodoPage {
-        &self.pages[self.current_page_index]
+    pub fn key(self) -> char {
+        match self {
+            Reschedule::Tomorrow => 'd',
+            Reschedule::NextWorkday => 'n',
+            Reschedule::NextWeek => 'w',
+            Reschedule::Weekend => 's',
+            Reschedule::NextMonday => 'm',
+        }
     }
 
-    // Current todos accessor
-    pub fn todos(&self) -> &Vec<Todo> {
-        &self.current_page().todos
+    pub fn from_key(key: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|option| option.key() == key)
     }
 
-    // Current todos mutable accessor
-    pub fn todos_mut(&mut self) -> &mut Vec<Todo> {
-        &mut self.pages[self.current_page_index].todos
+    pub fn label(self) -> &'static str {
+        match self {
+            Reschedule::Tomorrow => "+1 day",
+            Reschedule::NextWorkday => "Workday",
+            Reschedule::NextWeek => "+1 week",
+            Reschedule::Weekend => "Weekend",
+            Reschedule::NextMonday => "Next week",
+        }
     }
 
-    // Add a new page
-    pub fn add_page(&mut self, name: String) {
-        if !name.is_empty() && !self.pages.iter().any(|p| p.name == name) {
-            let new_page = TodoPage::new(name);
-            self.pages.push(new_page);
-            self.current_page_index = self.pages.len() - 1;
+    // The new due date. Day offsets count from the current due date, or from
+    // today when there is none or it has already passed. The next workday and
+    // next week skip weekends and holidays.
+    pub fn apply(
+        self,
+        due_date: Option<DateTime<Local>>,
+        today: NaiveDate,
+        holidays: &Holidays,
+    ) -> NaiveDate {
+        let base = due_date.map_or(today, |due| due.date_naive().max(today));
+        let days_until = |weekday: Weekday| {
+            let days =
+                (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+            // Never "reschedule" to today
+            today + Days::new(if days == 0 { 7 } else { days.into() })
+        };
 
-            // Update page select state
-            self.page_select_state.select(Some(self.current_page_index));
+        match self {
+            Reschedule::Tomorrow => base + Days::new(1),
+            Reschedule::NextWorkday => holidays.workday_from(base + Days::new(1)),
+            Reschedule::NextWeek => base + Days::new(7),
+            Reschedule::Weekend => days_until(Weekday::Sat),
+            Reschedule::NextMonday => holidays.workday_from(days_until(Weekday::Mon)),
         }
     }
+}
 
-    // Select a page by name
-    pub fn select_page_by_name(&mut self, name: &str) -> bool {
-        if let Some(index) = self.pages.iter().position(|p| p.name == name) {
-            self.current_page_index = index;
-            self.page_select_state.select(Some(index));
+// Words of `text` that look like web links, without trailing punctuation
+pub fn extract_urls(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .filter_map(|word| {
+            let start = word.find("https://").or_else(|| word.find("http://"))?;
+            let url = word[start..].trim_end_matches(['.', ',', ';', ':', ')', '>', '"', '\'']);
+            Some(url.to_string())
+        })
+        .collect()
+}
 
-            // Reset todo selection for the new page
-            let todo_count = self.todos().len();
-            if todo_count > 0 {
-                self.state.select(Some(0));
-            } else {
-                self.state.select(None);
-            }
+fn running_time(started: DateTime<Local>) -> Duration {
+    Local::now()
+        .signed_duration_since(started)
+        .to_std()
+        .unwrap_or_default()
+}
 
-            true
-        } else {
-            false
-        }
+// Tracked time as h:mm:ss
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// Short human readable time since `time`, e.g. "just now", "5m ago" or "3d ago"
+pub fn relative_age(time: DateTime<Local>, now: DateTime<Local>) -> String {
+    let elapsed = now.signed_duration_since(time);
+    if elapsed.num_minutes() < 1 {
+        "just now".to_string()
+    } else if elapsed.num_hours() < 1 {
+        format!("{}m ago", elapsed.num_minutes())
+    } else if elapsed.num_days() < 1 {
+        format!("{}h ago", elapsed.num_hours())
+    } else if elapsed.num_weeks() < 5 {
+        format!("{}d ago", elapsed.num_days())
+    } else if elapsed.num_days() < 365 {
+        format!("{}mo ago", elapsed.num_days() / 30)
+    } else {
+        format!("{}y ago", elapsed.num_days() / 365)
     }
+}
 
-    // Navigate to next page
-    pub fn next_page(&mut self) {
-        if !self.pages.is_empty() {
-            let i = match self.page_select_state.selected() {
-                Some(i) => {
-                    if i >= self.pages.len() - 1 {
-                        0
-                    } else {
-                        i + 1
-                    }
-                }
-                None => 0,
-            };
+// Stable link to a todo by its ID, for referencing it from notes elsewhere
+pub const LINK_PREFIX: &str = "ratdo://todo/";
 
-            self.current_page_index = i;
-            self.page_select_state.select(Some(i));
+// ID of a `ratdo://todo/<id>` link, or of a bare ID
+pub fn parse_link(link: &str) -> Option<Uuid> {
+    let id = link.strip_prefix(LINK_PREFIX).unwrap_or(link);
+    Uuid::parse_str(id.trim_end_matches('/')).ok()
+}
 
-            // Reset todo selection for the new page
-            let todo_count = self.todos().len();
-            if todo_count > 0 {
-                self.state.select(Some(0));
-            } else {
-                self.state.select(None);
-            }
+impl Todo {
+    pub fn link(&self) -> String {
+        format!("{}{}", LINK_PREFIX, self.id)
+    }
+
+    pub fn new(description: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            description,
+            completed: false,
+            created_at: Local::now(),
+            quantity: None,
+            updated_at: None,
+            due_date: None,
+            scheduled: None,
+            tags: Vec::new(),
+            notes: String::new(),
+            completed_at: None,
+            time_spent: Duration::ZERO,
+            timer_started: None,
+            needs_review: false,
+            pinned: false,
+            deferred_until: None,
+            comments: Vec::new(),
+            assignee: None,
+            estimate: None,
+            reminder: None,
+            attachments: Vec::new(),
+            priority: None,
+            scanned: None,
         }
     }
 
-    // Navigate to previous page
-    pub fn previous_page(&mut self) {
-        if !self.pages.is_empty() {
-            let i = match self.page_select_state.selected() {
-                Some(i) => {
-                    if i == 0 {
-                        self.pages.len() - 1
-                    } else {
-                        i - 1
-                    }
-                }
-                None => 0,
-            };
+    // Where the due date falls relative to `today`, completed todos are never overdue
+    pub fn due_status(&self, today: NaiveDate) -> Option<DueStatus> {
+        let due = self.due_date?.date_naive();
+        if due < today && !self.completed {
+            Some(DueStatus::Overdue)
+        } else if due == today {
+            Some(DueStatus::Today)
+        } else {
+            Some(DueStatus::Upcoming)
+        }
+    }
 
-            self.current_page_index = i;
-            self.page_select_state.select(Some(i));
+    // Record that the todo was just changed
+    pub fn touch(&mut self) {
+        self.updated_at = Some(Local::now());
+    }
 
-            // Reset todo selection for the new page
-            let todo_count = self.todos().len();
-            if todo_count > 0 {
-                self.state.select(Some(0));
-            } else {
-                self.state.select(None);
-            }
+    // Check or uncheck the todo, keeping track of when it was completed
+    pub fn set_completed(&mut self, completed: bool) {
+        self.completed = completed;
+        self.completed_at = completed.then(Local::now);
+        if completed {
+            self.stop_timer();
         }
+        self.touch();
     }
 
-    // Toggle page selector visibility
-    pub fn toggle_page_selector(&mut self) {
-        self.show_page_selector = !self.show_page_selector;
+    pub fn start_timer(&mut self) {
+        if self.timer_started.is_none() {
+            self.timer_started = Some(Local::now());
+            self.touch();
+        }
+    }
 
-        if self.show_page_selector {
-            self.input_mode = InputMode::PageSelect;
-            self.page_select_state.select(Some(self.current_page_index));
-        } else {
-            self.input_mode = InputMode::Normal;
+    // Add the running timer to the time spent
+    pub fn stop_timer(&mut self) {
+        if let Some(started) = self.timer_started.take() {
+            self.time_spent += running_time(started);
+            self.touch();
         }
     }
 
-    // Toggle picking mode
-    pub fn toggle_picking_mode(&mut self) {
-        self.picking_mode = !self.picking_mode;
+    // URLs in the description followed by the attachments, everything `o` can open
+    pub fn links(&self) -> Vec<String> {
+        let mut links = extract_urls(&self.description);
+        links.extend(self.attachments.iter().cloned());
+        links
     }
 
-    // Override next and previous to handle moving todos when in picking mode
-    pub fn next(&mut self) {
-        let todos = self.todos();
-        if todos.is_empty() {
-            return;
-        }
+    pub fn is_deferred(&self, now: DateTime<Local>) -> bool {
+        self.deferred_until.is_some_and(|until| until > now)
+    }
 
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i >= todos.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
-        };
+    // Time spent including the running timer
+    pub fn tracked_time(&self) -> Duration {
+        self.time_spent + self.timer_started.map_or(Duration::ZERO, running_time)
+    }
 
-        // Move the todo if we're in picking mode
-        if self.picking_mode && i != self.state.selected().unwrap_or(0) {
-            let current = self.state.selected().unwrap_or(0);
-            let todos = self.todos_mut();
+    pub fn last_modified(&self) -> DateTime<Local> {
+        self.updated_at.unwrap_or(self.created_at)
+    }
 
-            // Don't attempt to move if there's only one item
-            if todos.len() > 1 {
-                // Handle wrap-around case
-                if current == todos.len() - 1 && i == 0 {
-                    // Move from end to beginning
-                    let todo = todos.remove(current);
-                    todos.insert(0, todo);
-                } else {
-                    // Standard case - swap with the next item
-                    todos.swap(current, i);
-                }
-            }
-        }
+    pub fn increment_quantity(&mut self) {
+        self.quantity = Some(self.quantity.unwrap_or(1).saturating_add(1));
+        self.touch();
+    }
 
-        self.state.select(Some(i));
+    // Quantities of one are not shown, so dropping below two clears the field
+    pub fn decrement_quantity(&mut self) {
+        self.quantity = match self.quantity {
+            Some(quantity) if quantity > 2 => Some(quantity - 1),
+            _ => None,
+        };
+        self.touch();
     }
 
-    pub fn previous(&mut self) {
-        let todos = self.todos();
-        if todos.is_empty() {
-            return;
+    // Description as shown in the input popup, with the tags appended and the
+    // quantity on pages that track them
+    pub fn input_text(&self, quantities: bool) -> String {
+        let mut text = self.description.clone();
+        if let Some(quantity) = self.quantity.filter(|_| quantities) {
+            text.push_str(&format!(" x{}", quantity));
+        }
+        for tag in &self.tags {
+            text.push_str(&format!(" +{}", tag));
+        }
+        if let Some(assignee) = &self.assignee {
+            text.push_str(&format!(" @{}", assignee));
         }
+        if let Some(estimate) = self.estimate {
+            text.push_str(&format!(" ~{}", format_estimate(estimate)));
+        }
+        if let Some(priority) = self.priority {
+            text.push_str(&format!(" !{}", priority.name()));
+        }
+        text
+    }
+}
 
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    todos.len() - 1
-                } else {
-                    i - 1
-                }
+// Fields extracted from the add/edit popup input
+#[derive(Debug, Default)]
+pub struct ParsedInput {
+    pub description: String,
+    pub quantity: Option<u32>,
+    pub tags: Vec<String>,
+    pub assignee: Option<String>,
+    pub estimate: Option<u32>,
+    // Page named with a `/page` word, for adding to another page
+    pub page: Option<String>,
+    pub due_date: Option<DateTime<Local>>,
+    pub priority: Option<Priority>,
+    // Problem with a `due:` word, reported instead of adding the todo
+    pub error: Option<String>,
+}
+
+// Non-empty lines of pasted text without the bullets, numbers and checkboxes of
+// the list they came from
+pub fn list_items(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = ["- ", "* ", "+ ", "• "]
+                .iter()
+                .find_map(|bullet| line.strip_prefix(bullet))
+                .unwrap_or(line)
+                .trim_start();
+            let line = ["[ ] ", "[x] ", "[X] "]
+                .iter()
+                .find_map(|checkbox| line.strip_prefix(checkbox))
+                .unwrap_or(line);
+            // "1. " and "1) " numbering
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            match line[digits..]
+                .strip_prefix(". ")
+                .or(line[digits..].strip_prefix(") "))
+            {
+                Some(rest) if digits > 0 => rest.trim_start(),
+                _ => line,
             }
-            None => 0,
-        };
+            .to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
 
-        // Move the todo if we're in picking mode
-        if self.picking_mode && i != self.state.selected().unwrap_or(0) {
-            let current = self.state.selected().unwrap_or(0);
-            let todos = self.todos_mut();
+// A `+tag` word, tags start with a letter so "+1" stays part of the description
+pub fn parse_tag(word: &str) -> Option<String> {
+    let tag = word.strip_prefix('+')?;
+    let valid = tag.starts_with(char::is_alphabetic)
+        && tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| tag.to_lowercase())
+}
 
-            // Don't attempt to move if there's only one item
-            if todos.len() > 1 {
-                // Handle wrap-around case
-                if current == 0 && i == todos.len() - 1 {
-                    // Move from beginning to end
-                    let todo = todos.remove(0);
-                    todos.push(todo);
-                } else {
-                    // Standard case - swap with the previous item
-                    todos.swap(current, i);
-                }
-            }
-        }
+// An `@name` word assigning the todo to someone
+pub fn parse_assignee(word: &str) -> Option<String> {
+    let name = word.strip_prefix('@')?;
+    let valid = name.starts_with(char::is_alphabetic)
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.');
+    valid.then(|| name.to_string())
+}
 
-        self.state.select(Some(i));
-    }
+// A `/page` word naming one of `pages`, names with spaces are written with
+// dashes ("/shopping-list"). Other words starting with a slash, like paths,
+// are left alone.
+fn parse_page(word: &str, pages: &[String]) -> Option<String> {
+    let name = word.strip_prefix('/')?;
+    let valid = name.starts_with(char::is_alphabetic)
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    let known = pages.iter().any(|page| page.eq_ignore_ascii_case(name));
+    (valid && known).then(|| name.to_string())
+}
 
-    pub fn add_todo(&mut self) {
-        let todo = Todo::new(self.current_input.clone());
-        let insertion_index = match self.state.selected() {
-            Some(index) => index + 1,   // Insert after current selection
-            None => self.todos().len(), // If nothing selected, append to end
-        };
-        self.todos_mut().insert(insertion_index, todo);
-        self.state.select(Some(insertion_index)); // Move selection to the new todo
-        self.current_input.clear();
-    }
+// How a page label is written as a `/page` word
+pub fn page_token(label: &str) -> String {
+    label.split_whitespace().collect::<Vec<_>>().join("-")
+}
 
-    pub fn delete_todo(&mut self) {
-        if let Some(selected) = self.state.selected() {
-            let todos = self.todos_mut();
-            if !todos.is_empty() && selected < todos.len() {
-                todos.remove(selected);
-                if selected > 0 && selected == todos.len() {
-                    self.state.select(Some(selected - 1));
-                }
+// How well `query` fuzzy matches `name`: its letters have to appear in order,
+// runs of letters and letters starting a word score higher. None without a match.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous_matched = false;
+    let mut word_start = true;
+    for c in name.chars().flat_map(char::to_lowercase) {
+        let matched = query.peek() == Some(&c);
+        if matched {
+            query.next();
+            score += 1;
+            if previous_matched {
+                score += 5;
+            }
+            if word_start {
+                score += 10;
             }
         }
+        previous_matched = matched;
+        word_start = !c.is_alphanumeric();
     }
+    query.peek().is_none().then_some(score)
+}
 
-    pub fn toggle_todo(&mut self) {
-        if let Some(selected) = self.state.selected() {
-            let todos = self.todos_mut();
-            if !todos.is_empty() && selected < todos.len() {
-                // Toggle the completion status
-                todos[selected].completed = !todos[selected].completed;
-            }
-        }
+// A `~` estimate in minutes: `~45m`, `~2h`, `~1h30m` or a t-shirt size from
+// `~xs` (15 minutes) through `~s`, `~m` and `~l` to `~xl` (4 hours)
+fn parse_estimate(word: &str) -> Option<u32> {
+    let estimate = word.strip_prefix('~')?.to_lowercase();
+    match estimate.as_str() {
+        "xs" => return Some(15),
+        "s" => return Some(30),
+        "m" => return Some(60),
+        "l" => return Some(120),
+        "xl" => return Some(240),
+        _ => {}
     }
 
-    pub fn start_editing(&mut self) {
-        if let Some(selected) = self.state.selected() {
-            let todos = self.todos();
-            if !todos.is_empty() && selected < todos.len() {
-                self.current_input = todos[selected].description.clone();
-                self.input_mode = InputMode::Editing;
-                self.edit_mode = true;
-            }
-        }
+    let (hours, rest) = match estimate.split_once('h') {
+        Some((hours, rest)) => (hours.parse::<u32>().ok()?, rest),
+        None => (0, estimate.as_str()),
+    };
+    let minutes = match rest {
+        "" => 0,
+        rest => rest.strip_suffix('m')?.parse::<u32>().ok()?,
+    };
+    hours
+        .checked_mul(60)?
+        .checked_add(minutes)
+        .filter(|total| *total > 0)
+}
+
+// Minutes as shown after a `~`, e.g. "45m", "2h" or "1h30m"
+pub fn format_estimate(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h{}m", hours, minutes),
     }
+}
 
-    pub fn update_todo(&mut self) {
-        if let Some(selected) = self.state.selected() {
-            // Clone first to avoid borrowing issues
-            let current_input_clone = self.current_input.clone();
-            self.current_input.clear();
+// Split the popup input into the description, its tags, an assignee, an
+// estimate, a target page out of `pages` (written as `/page` words), a due
+// date, a priority and, on pages that track them, a trailing quantity
+pub fn parse_input(input: &str, quantities: bool, pages: &[String]) -> ParsedInput {
+    let mut tags: Vec<String> = Vec::new();
+    let mut assignee = None;
+    let mut estimate = None;
+    let mut page = None;
+    let mut due_date = None;
+    let mut priority = None;
+    let mut error = None;
+    let mut words = Vec::new();
 
-            let todos = self.todos_mut();
-            if !todos.is_empty() && selected < todos.len() {
-                todos[selected].description = current_input_clone;
+    for word in input.split_whitespace() {
+        if let Some(tag) = parse_tag(word) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
             }
+        } else if let Some(name) = parse_assignee(word) {
+            assignee = Some(name);
+        } else if let Some(minutes) = parse_estimate(word) {
+            estimate = Some(minutes);
+        } else if let Some(name) = parse_page(word, pages) {
+            page = Some(name);
+        } else if let Some(date) = parse_due_word(word) {
+            match date {
+                Ok(date) => due_date = Some(date),
+                Err(message) => error = Some(message),
+            }
+        } else if let Some(level) = Priority::parse(word) {
+            priority = Some(level);
+        } else {
+            words.push(word);
         }
     }
 
-    fn get_config_path() -> io::Result<PathBuf> {
-        let home = env::var("HOME")
-            .or_else(|_| env::var("USERPROFILE"))
-            .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "Home directory not found"))?;
+    let text = words.join(" ");
+    let (description, quantity) = if quantities {
+        parse_quantity(&text)
+    } else {
+        (text, None)
+    };
 
-        Ok(PathBuf::from(home)
-            .join(".config")
-            .join("ratdo")
-            .join("todos.json"))
+    ParsedInput {
+        description,
+        quantity,
+        tags,
+        assignee,
+        estimate,
+        page,
+        due_date,
+        priority,
+        error,
     }
+}
 
-    pub fn load_todos(&mut self) -> io::Result<()> {
-        let path = Self::get_config_path()?;
-
-        if path.exists() {
-            let content = fs::read_to_string(path)?;
-            self.pages = serde_json::from_str(&content).unwrap_or_else(|_| {
-                // Handle backward compatibility with old format
-                let old_todos: Vec<Todo> = serde_json::from_str(&content).unwrap_or_default();
-                let default_page = TodoPage {
-                    name: "Default".to_string(),
-                    todos: old_todos,
-                };
-                vec![default_page]
-            });
+// Split a trailing quantity ("milk x2", "eggs 12") off a description
+pub fn parse_quantity(input: &str) -> (String, Option<u32>) {
+    let input = input.trim();
 
-            // Ensure we have at least one page
-            if self.pages.is_empty() {
-                self.pages.push(TodoPage::new("Default".to_string()));
+    if let Some((rest, last)) = input.rsplit_once(char::is_whitespace) {
+        let digits = last.strip_prefix(['x', 'X']).unwrap_or(last);
+        if let Ok(quantity) = digits.parse::<u32>() {
+            let rest = rest.trim_end();
+            if quantity > 0 && !rest.is_empty() {
+                let quantity = if quantity > 1 { Some(quantity) } else { None };
+                return (rest.to_string(), quantity);
             }
+        }
+    }
 
-            // Set initial selection
-            if !self.todos().is_empty() {
-                self.state.select(Some(0));
-            }
-            self.page_select_state.select(Some(0));
+    (input.to_string(), None)
+}
 
-            // Reset current page index in case it's invalid
-            self.current_page_index = 0;
+// Kind of page picked on creation, used to preconfigure its settings
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PageType {
+    #[default]
+    Project,
+    Checklist,
+    Shopping,
+    Habits,
+}
+
+impl PageType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PageType::Project => "Project",
+            PageType::Checklist => "Checklist",
+            PageType::Shopping => "Shopping",
+            PageType::Habits => "Habits",
+        }
+    }
+
+    // Cycle through the page types in the page creation popup
+    pub fn next(&self) -> Self {
+        match self {
+            PageType::Project => PageType::Checklist,
+            PageType::Checklist => PageType::Shopping,
+            PageType::Shopping => PageType::Habits,
+            PageType::Habits => PageType::Project,
         }
-        Ok(())
     }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    // Items stay where they were added or moved to
+    #[default]
+    Manual,
+    // Completed items sink below the open ones when toggled
+    CompletedLast,
+}
 
-    pub fn save_todos(&self) -> io::Result<()> {
-        let path = Self::get_config_path()?;
+// Section headers the todo list of a page is grouped under, in place of the
+// page's own order
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Grouping {
+    #[default]
+    None,
+    // Today, this week and older by when the todo was added
+    Created,
+    // Overdue, today, this week, later and without a due date
+    Due,
+    // High, medium, low and without a priority
+    Priority,
+    // By the first tag of each todo, in the order of the tag names
+    Tag,
+}
 
-        // Ensure the directory exists
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+impl Grouping {
+    pub fn next(&self) -> Self {
+        match self {
+            Grouping::None => Grouping::Created,
+            Grouping::Created => Grouping::Due,
+            Grouping::Due => Grouping::Priority,
+            Grouping::Priority => Grouping::Tag,
+            Grouping::Tag => Grouping::None,
         }
+    }
 
-        let json = serde_json::to_string(&self.pages)?;
-        fs::write(path, json)?;
-        Ok(())
+    pub fn group(&self, todo: &Todo, today: NaiveDate) -> Option<Group> {
+        match self {
+            Grouping::None => None,
+            Grouping::Created => Some(match (today - todo.created_at.date_naive()).num_days() {
+                ..=0 => Group::Today,
+                1..=6 => Group::ThisWeek,
+                _ => Group::Older,
+            }),
+            Grouping::Due => Some(match todo.due_date {
+                None => Group::NoDueDate,
+                Some(due) => match (due.date_naive() - today).num_days() {
+                    ..=-1 => Group::Overdue,
+                    0 => Group::Today,
+                    1..=6 => Group::ThisWeek,
+                    _ => Group::Later,
+                },
+            }),
+            Grouping::Priority => Some(match todo.priority {
+                Some(Priority::High) => Group::High,
+                Some(Priority::Medium) => Group::Medium,
+                Some(Priority::Low) => Group::Low,
+                None => Group::NoPriority,
+            }),
+            Grouping::Tag => Some(match todo.tags.first() {
+                Some(tag) => Group::Tag(tag.clone()),
+                None => Group::NoTags,
+            }),
+        }
     }
+}
 
-    // Get a list of page names - helpful for CLI "show" command
-    pub fn page_names(&self) -> Vec<String> {
-        self.pages.iter().map(|p| p.name.clone()).collect()
+// Sections of a grouped todo list, in the order they are shown
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Group {
+    Overdue,
+    Today,
+    ThisWeek,
+    Later,
+    Older,
+    NoDueDate,
+    High,
+    Medium,
+    Low,
+    NoPriority,
+    Tag(String),
+    NoTags,
+    // Completed todos of a page with a done section, always last
+    Done,
+}
+
+impl Group {
+    pub fn title(&self) -> String {
+        let title = match self {
+            Group::Overdue => "Overdue",
+            Group::Today => "Today",
+            Group::ThisWeek => "This Week",
+            Group::Later => "Later",
+            Group::Older => "Older",
+            Group::NoDueDate => "No Due Date",
+            Group::High => "High Priority",
+            Group::Medium => "Medium Priority",
+            Group::Low => "Low Priority",
+            Group::NoPriority => "No Priority",
+            Group::Tag(tag) => return format!("+{}", tag),
+            Group::NoTags => "No Tags",
+            Group::Done => "Done",
+        };
+        title.to_string()
     }
+}
 
-    // Create a new page if it doesn't exist and select it
+// A row of the todo list: a todo by its index into `todos()`, or the header
+// of a group with the number of todos in it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Row {
+    Todo(usize),
+    Group(Group, usize),
+}
+
+// Per-page behaviour switches, stored alongside the page
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PageSettings {
+    pub page_type: PageType,
+    pub sort: SortOrder,
+    // Space toggles and advances to the next item, for working through lists quickly
+    pub checklist_mode: bool,
+    // Parse trailing quantities from new items and show them right-aligned
+    pub quantities: bool,
+    // Completed items are unchecked again at the start of each day
+    pub habit_mode: bool,
+    // Day the habit items were last reset
+    pub last_reset: Option<NaiveDate>,
+    // Todos completed on an earlier day are moved to the archive
+    pub auto_archive: bool,
+    // Emoji or short symbol shown before the page name
+    pub icon: Option<String>,
+    pub grouping: Grouping,
+    // Groups folded down to their header with z a
+    pub collapsed: Vec<Group>,
+    // Completed todos are listed under a Done header at the bottom
+    pub done_section: bool,
+}
+
+impl PageSettings {
+    pub fn preset(page_type: PageType) -> Self {
+        let mut settings = Self {
+            page_type,
+            ..Self::default()
+        };
+
+        match page_type {
+            PageType::Project => {}
+            PageType::Checklist => {
+                settings.sort = SortOrder::CompletedLast;
+                settings.checklist_mode = true;
+            }
+            PageType::Shopping => {
+                settings.sort = SortOrder::CompletedLast;
+                settings.checklist_mode = true;
+                settings.quantities = true;
+                settings.auto_archive = true;
+            }
+            PageType::Habits => {
+                settings.habit_mode = true;
+                settings.last_reset = Some(Local::now().date_naive());
+            }
+        }
+
+        settings
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TodoPage {
+    pub name: String,
+    pub todos: Vec<Todo>,
+    #[serde(default)]
+    pub settings: PageSettings,
+    // Index into `App::stores` of the file this page is saved to
+    #[serde(skip)]
+    pub store: usize,
+    // Only todos with this tag are shown while set
+    #[serde(skip)]
+    pub tag_filter: Option<String>,
+    // Todos put away with x or X, kept for the record and restorable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archived: Vec<Todo>,
+}
+
+impl TodoPage {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            todos: Vec::new(),
+            settings: PageSettings::default(),
+            store: 0,
+            tag_filter: None,
+            archived: Vec::new(),
+        }
+    }
+
+    // All tags used on the page, sorted
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.todos.iter().flat_map(|t| t.tags.clone()).collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    // Summed estimate of the open todos in minutes
+    pub fn remaining_estimate(&self) -> u32 {
+        self.todos
+            .iter()
+            .filter(|t| !t.completed)
+            .filter_map(|t| t.estimate)
+            .sum()
+    }
+
+    // Uncheck habit items when the page is first seen on a new day
+    pub fn reset_habits(&mut self, today: NaiveDate) -> bool {
+        if !self.settings.habit_mode || self.settings.last_reset == Some(today) {
+            return false;
+        }
+        self.settings.last_reset = Some(today);
+        let mut reset = false;
+        for todo in self.todos.iter_mut().filter(|t| t.completed) {
+            todo.set_completed(false);
+            reset = true;
+        }
+        reset
+    }
+
+    // Archive the todos completed before `today` on auto-archiving pages.
+    // Returns whether any were.
+    pub fn auto_archive(&mut self, today: NaiveDate) -> bool {
+        if !self.settings.auto_archive {
+            return false;
+        }
+        let (done, open): (Vec<Todo>, Vec<Todo>) = std::mem::take(&mut self.todos)
+            .into_iter()
+            .partition(|todo| {
+                todo.completed
+                    && todo
+                        .completed_at
+                        .is_some_and(|time| time.date_naive() < today)
+            });
+        self.todos = open;
+        let archived = !done.is_empty();
+        self.archived.extend(done);
+        archived
+    }
+}
+
+// Whether the pages of a store are just the empty page it was given for lack
+// of its own
+fn is_placeholder(pages: &[TodoPage], store: &str) -> bool {
+    matches!(pages, [page] if page.name == store && page.todos.is_empty())
+}
+
+// Read the pages of a todo file, a missing file has no pages. Todos without
+// an ID (or with one already taken by another todo) are given a new one, which
+// sticks once the file is saved.
+pub fn read_pages(path: &Path) -> io::Result<Vec<TodoPage>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    let mut pages = serde_json::from_str(&content).unwrap_or_else(|_| {
+        // Handle backward compatibility with old format
+        let old_todos: Vec<Todo> = serde_json::from_str(&content).unwrap_or_default();
+        let mut default_page = TodoPage::new("Default".to_string());
+        default_page.todos = old_todos;
+        vec![default_page]
+    });
+
+    assign_ids(&mut pages);
+    Ok(pages)
+}
+
+fn assign_ids(pages: &mut [TodoPage]) {
+    let mut seen = HashSet::new();
+    for todo in pages.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+        if todo.id.is_nil() || !seen.insert(todo.id) {
+            todo.id = Uuid::new_v4();
+            seen.insert(todo.id);
+        }
+    }
+}
+
+// Write pages to a todo file, creating its directory when needed
+pub fn write_pages(path: &Path, pages: &[&TodoPage]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let json = serde_json::to_string(pages)?;
+    fs::write(path, json)
+}
+
+// Top-level view shown in the main area
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    List,
+    Agenda,
+    // Todos of all pages flagged as needing review
+    Review,
+    // Month of due dates with the todos due on the selected day
+    Calendar,
+    // Totals, completions per day and the oldest open todos
+    Stats,
+}
+
+pub enum InputMode {
+    Normal,
+    Editing,
+    PageSelect,
+    DueDate,
+    Scheduled,
+    Reminder,
+    Defer,
+    Reschedule,
+    Postpone,
+    Attach,
+    Links,
+    CompletionNote,
+    Comment,
+    TemplateName,
+    Templates,
+    PageIcon,
+    Archive,
+    Help,
+    PageSwitch,
+    TodoJump,
+    TagFilter,
+    Search,
+    Notes,
+    Tag,
+    ConfirmDelete,
+    Import,
+    DaySummary,
+    Conflicts,
+}
+
+// Seconds a toast stays up, errors twice as long
+const TOAST_SECONDS: u64 = 3;
+
+// Short-lived note in the corner confirming an action or reporting an error
+pub struct Toast {
+    pub message: String,
+    pub error: bool,
+    pub shown_at: Instant,
+}
+
+impl Toast {
+    pub fn expired(&self) -> bool {
+        let seconds = if self.error {
+            TOAST_SECONDS * 2
+        } else {
+            TOAST_SECONDS
+        };
+        self.shown_at.elapsed() >= Duration::from_secs(seconds)
+    }
+}
+
+// A deletion waiting for a yes in the confirmation popup
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PendingDelete {
+    // The marked todos, or the selected one
+    Todos,
+    // From the page list
+    Page(usize),
+}
+
+// Modify the App struct to track when we're in "pick mode"
+pub struct App {
+    pub stores: Vec<Store>,
+    pub pages: Vec<TodoPage>,
+    pub current_page_index: usize,
+    pub state: ListState,
+    pub page_select_state: ListState,
+    pub tag_select_state: ListState,
+    pub link_select_state: ListState,
+    // Highlighted entry of the quick page switcher, or of the todo jump
+    pub page_switch_state: ListState,
+    // Highlighted entry of the page completions for a `/page` word
+    pub page_completion_state: ListState,
+    // Templates shown in the picker, read when it opens
+    pub templates: Vec<Template>,
+    pub template_select_state: ListState,
+    // Lines of the clipboard previewed before they are added as todos
+    pub import: Vec<String>,
+    pub import_select_state: ListState,
+    // Fields changed both here and elsewhere, found when saving
+    pub conflicts: Vec<Conflict>,
+    pub conflict_select_state: ListState,
+    // Highlighted entry of the archive browser, most recent first
+    pub archive_select_state: ListState,
+    pub view: View,
+    // Selection among the agenda entries, section headers not counted
+    pub agenda_state: ListState,
+    pub input_mode: InputMode,
+    pub current_input: String,
+    // Characters before the cursor in `current_input`, past its end at the end
+    pub input_cursor: usize,
+    pub edit_mode: bool,
+    pub picking_mode: bool,
+    pub show_page_selector: bool,
+    // Detail pane with the full description and notes of the selected todo
+    pub show_details: bool,
+    // List snoozed todos too instead of hiding them until their date
+    pub show_deferred: bool,
+    // Each todo on two lines with a blank one after, for presenting and for
+    // reading from further away
+    pub large_text: bool,
+    // Only the selected todo with its notes, stepping to the next open one
+    // once it is done or skipped
+    pub focus: bool,
+    // Page labels by last visit, most recent first
+    pub recent_pages: Vec<String>,
+    // Actions used, kept in the session for `ratdo insights`
+    pub usage: Usage,
+    // How far back ` went while it is pressed repeatedly
+    pub recent_cycle: Option<usize>,
+    // Date the list and agenda are drawn for, moved on by `refresh_date`
+    pub today: NaiveDate,
+    // Day selected in the calendar view
+    pub calendar_day: NaiveDate,
+    // Day the carry-over rule last ran on, kept in the session
+    pub carried_over: Option<NaiveDate>,
+    // Day the start-of-day summary was last opened on
+    pub summarized: Option<NaiveDate>,
+    // First line of the help overlay shown
+    pub help_scroll: u16,
+    pub summary_scroll: u16,
+    // Where the todo list was last drawn, for finding the row under the mouse
+    pub list_area: Rect,
+    // Lines each row of the todo list took when it was last drawn
+    pub row_heights: Vec<usize>,
+    // Time and row of the last click, a second one on the row soon after toggles it
+    pub last_click: Option<(Instant, usize)>,
+    // First key of a chord, waiting for the second one
+    pub pending_key: Option<KeyEvent>,
+    // Number typed before a key of the list, like the 5 of 5j
+    pub count: Option<usize>,
+    // Ids of the todos marked for a bulk action on the current page
+    pub marked: HashSet<Uuid>,
+    // Todo where visual mode (V) started, the range to the selection is marked
+    pub visual_anchor: Option<Uuid>,
+    pub pending_delete: Option<PendingDelete>,
+    // The page switcher picks where to move todos instead of opening a page
+    pub moving_to_page: bool,
+    // Undo and redo steps of the todo and page changes
+    pub history: History,
+    // Whether the current move (p) already recorded its undo step
+    pub moved_while_picking: bool,
+    // Type preset applied to the page being created in the page selector
+    pub new_page_type: PageType,
+    pub config: Config,
+    // Days off from the configured holiday calendar
+    pub holidays: Holidays,
+    // Index into the configured workspaces, None shows all pages
+    pub workspace: Option<usize>,
+    // Text the list of the current page is narrowed down to with `/`
+    pub search: Option<String>,
+    pub toast: Option<Toast>,
+    // Saving on quit failed once, quitting again leaves without saving
+    pub save_failed: bool,
+    // Changes to the pages since they were loaded or last saved
+    pub dirty: bool,
+    // Where the todos, session, templates and settings are kept
+    pub storage: Box<dyn Storage>,
+    // Transient feedback shown on the status line until the next key press
+    pub status_message: Option<String>,
+    // Version of a newer release, noted on the status line
+    pub update_available: Option<String>,
+    // Overly long description the user has already been warned about
+    pub confirmed_long_input: Option<String>,
+}
+
+impl App {
+    pub fn new() -> Self {
+        let mut state = ListState::default();
+        state.select(Some(0));
+
+        let mut page_select_state = ListState::default();
+        page_select_state.select(Some(0));
+
+        // Create a default page
+        let default_page = TodoPage::new("Default".to_string());
+        let pages = vec![default_page];
+
+        Self {
+            stores: Vec::new(),
+            pages,
+            current_page_index: 0,
+            state,
+            page_select_state,
+            tag_select_state: ListState::default(),
+            link_select_state: ListState::default(),
+            page_switch_state: ListState::default(),
+            page_completion_state: ListState::default(),
+            templates: Vec::new(),
+            template_select_state: ListState::default(),
+            import: Vec::new(),
+            import_select_state: ListState::default(),
+            conflicts: Vec::new(),
+            conflict_select_state: ListState::default(),
+            archive_select_state: ListState::default(),
+            view: View::List,
+            agenda_state: ListState::default(),
+            input_mode: InputMode::Normal,
+            current_input: String::new(),
+            input_cursor: usize::MAX,
+            edit_mode: false,
+            picking_mode: false,
+            show_page_selector: false,
+            show_details: false,
+            large_text: false,
+            focus: false,
+            recent_pages: Vec::new(),
+            usage: Usage::default(),
+            recent_cycle: None,
+            today: Local::now().date_naive(),
+            calendar_day: Local::now().date_naive(),
+            carried_over: None,
+            summarized: None,
+            help_scroll: 0,
+            summary_scroll: 0,
+            list_area: Rect::default(),
+            row_heights: Vec::new(),
+            last_click: None,
+            pending_key: None,
+            count: None,
+            marked: HashSet::new(),
+            visual_anchor: None,
+            moving_to_page: false,
+            pending_delete: None,
+            history: History::default(),
+            moved_while_picking: false,
+            show_deferred: false,
+            new_page_type: PageType::default(),
+            config: Config::default(),
+            holidays: Holidays::default(),
+            workspace: None,
+            search: None,
+            toast: None,
+            save_failed: false,
+            dirty: false,
+            storage: Box::new(Files { safe: false }),
+            status_message: None,
+            update_available: None,
+            confirmed_long_input: None,
+        }
+    }
+
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.status_message = Some(message.into());
+    }
+
+    // Confirm an action with a toast
+    pub fn notify(&mut self, message: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: message.into(),
+            error: false,
+            shown_at: Instant::now(),
+        });
+    }
+
+    pub fn notify_error(&mut self, message: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: message.into(),
+            error: true,
+            shown_at: Instant::now(),
+        });
+    }
+
+    // " — press u to undo" with the configured undo key
+    fn undo_hint(&self) -> String {
+        match self.config.keys.names(Action::Undo).first() {
+            Some(key) => format!(" — press {} to undo", key),
+            None => String::new(),
+        }
+    }
+
+    // Check the input popup contents before they become a todo description.
+    // Whitespace-only input is rejected and descriptions over the configured
+    // length need a second Enter to be accepted.
+    pub fn validate_todo_input(&mut self) -> bool {
+        let parsed = self.parsed_input();
+        if let Some(error) = parsed.error {
+            self.set_status(error);
+            return false;
+        }
+        if parsed.description.is_empty() {
+            self.set_status("Todo description cannot be empty");
+            return false;
+        }
+
+        let description = parsed.description;
+        let length = description.chars().count();
+        let limit = self.config.max_description_length;
+        if length > limit && self.confirmed_long_input.as_ref() != Some(&description) {
+            self.confirmed_long_input = Some(description);
+            self.set_status(format!(
+                "Description is {} characters (limit {}), consider moving the detail into notes. Press Enter again to keep it",
+                length, limit
+            ));
+            return false;
+        }
+
+        self.confirmed_long_input = None;
+        true
+    }
+
+    // Current page accessor
+    // The app always has at least one page. Code that moves
+    // `current_page_index` is expected to keep it in range; debug builds stop
+    // on a desync while release builds fall back to the last page.
+    fn page_index(&self) -> usize {
+        debug_assert!(!self.pages.is_empty(), "no pages");
+        debug_assert!(
+            self.current_page_index < self.pages.len(),
+            "current page {} out of range ({} pages)",
+            self.current_page_index,
+            self.pages.len()
+        );
+        self.current_page_index
+            .min(self.pages.len().saturating_sub(1))
+    }
+
+    pub fn current_page(&self) -> &TodoPage {
+        &self.pages[self.page_index()]
+    }
+
+    // Taking the page for writing counts as a change to save
+    pub fn current_page_mut(&mut self) -> &mut TodoPage {
+        self.dirty = true;
+        let index = self.page_index();
+        &mut self.pages[index]
+    }
+
+    // Checked lookup of a todo by page and index, e.g. from `selected_location()`
+    pub fn todo_at(&self, (page, index): (usize, usize)) -> Option<&Todo> {
+        self.pages.get(page)?.todos.get(index)
+    }
+
+    pub fn todo_at_mut(&mut self, (page, index): (usize, usize)) -> Option<&mut Todo> {
+        let todo = self.pages.get_mut(page)?.todos.get_mut(index)?;
+        self.dirty = true;
+        Some(todo)
+    }
+
+    pub fn selected_todo(&self) -> Option<&Todo> {
+        self.todo_at(self.selected_location()?)
+    }
+
+    pub fn selected_todo_mut(&mut self) -> Option<&mut Todo> {
+        self.todo_at_mut(self.selected_location()?)
+    }
+
+    // Assert the invariants the accessors rely on, checked after every key
+    // press in debug builds
+    pub fn debug_check(&self) {
+        debug_assert!(!self.pages.is_empty(), "no pages");
+        debug_assert!(
+            self.current_page_index < self.pages.len(),
+            "current page {} out of range ({} pages)",
+            self.current_page_index,
+            self.pages.len()
+        );
+        debug_assert!(
+            self.page_select_state
+                .selected()
+                .is_none_or(|i| i < self.pages.len()),
+            "page selector out of range"
+        );
+    }
+
+    // Current todos accessor
+    pub fn todos(&self) -> &Vec<Todo> {
+        &self.current_page().todos
+    }
+
+    // Current todos mutable accessor
+    pub fn todos_mut(&mut self) -> &mut Vec<Todo> {
+        &mut self.current_page_mut().todos
+    }
+
+    // Whether a todo of the current page passes the active view filters
+    fn is_visible(&self, todo: &Todo) -> bool {
+        if !self.show_deferred && todo.is_deferred(Local::now()) {
+            return false;
+        }
+        if let Some(query) = &self.search {
+            if !todo
+                .description
+                .to_lowercase()
+                .contains(&query.to_lowercase())
+            {
+                return false;
+            }
+        }
+        match &self.current_page().tag_filter {
+            Some(tag) => todo.tags.contains(tag),
+            None => true,
+        }
+    }
+
+    // Indices into `todos()` of the rows shown in the list, in display order
+    // with pinned todos first
+    pub fn visible_indices(&self) -> Vec<usize> {
+        self.rows()
+            .into_iter()
+            .filter_map(|row| match row {
+                Row::Todo(index) => Some(index),
+                Row::Group(..) => None,
+            })
+            .collect()
+    }
+
+    // Rows of the todo list, pinned todos first. Grouped pages list their
+    // todos under group headers, leaving out the todos of collapsed groups.
+    pub fn rows(&self) -> Vec<Row> {
+        let todos = self.todos();
+        let settings = &self.current_page().settings;
+        let group_of = |index: usize| self.group(&todos[index]);
+        let mut shown: Vec<usize> = todos
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| self.is_visible(todo))
+            .map(|(index, _)| index)
+            .collect();
+        shown.sort_by_key(|&index| (group_of(index), !todos[index].pinned));
+
+        let mut rows = Vec::new();
+        for (i, &index) in shown.iter().enumerate() {
+            if let Some(group) = group_of(index) {
+                if i == 0 || group_of(shown[i - 1]).as_ref() != Some(&group) {
+                    let count = shown[i..]
+                        .iter()
+                        .take_while(|&&next| group_of(next).as_ref() == Some(&group))
+                        .count();
+                    rows.push(Row::Group(group.clone(), count));
+                }
+                if settings.collapsed.contains(&group) {
+                    continue;
+                }
+            }
+            rows.push(Row::Todo(index));
+        }
+        rows
+    }
+
+    // Group of a todo on the current page, the done section taking completed
+    // todos before the grouping does
+    fn group(&self, todo: &Todo) -> Option<Group> {
+        let settings = &self.current_page().settings;
+        match settings.done_section && todo.completed {
+            true => Some(Group::Done),
+            false => settings.grouping.group(todo, self.today),
+        }
+    }
+
+    // Todos and the headers of collapsed groups can be selected, the headers
+    // of open groups are passed over
+    fn is_selectable(&self, row: &Row) -> bool {
+        match row {
+            Row::Todo(_) => true,
+            Row::Group(group, _) => self.current_page().settings.collapsed.contains(group),
+        }
+    }
+
+    // Select `row`, or the next row that can be selected. The last row is
+    // taken for rows past the end.
+    fn select_row(&mut self, row: usize) {
+        let rows = self.rows();
+        let Some(last) = rows.len().checked_sub(1) else {
+            self.state.select(None);
+            return;
+        };
+        let row = row.min(last);
+        let row = (row..rows.len())
+            .chain((0..row).rev())
+            .find(|&r| self.is_selectable(&rows[r]))
+            .unwrap_or(row);
+        self.state.select(Some(row));
+    }
+
+    // Index into `todos()` of the selected row, None on a group header
+    pub fn selected_index(&self) -> Option<usize> {
+        let row = self.state.selected()?;
+        match self.rows().get(row) {
+            Some(&Row::Todo(index)) => Some(index),
+            _ => None,
+        }
+    }
+
+    // Group of the selected todo or header on a grouped page
+    fn selected_group(&self) -> Option<Group> {
+        match self.rows().get(self.state.selected()?)? {
+            Row::Group(group, _) => Some(group.clone()),
+            Row::Todo(index) => self.group(&self.todos()[*index]),
+        }
+    }
+
+    // Show the page without groups, grouped by when todos were added, by
+    // their due date, priority or first tag
+    pub fn cycle_grouping(&mut self) {
+        let selected = self.selected_index();
+        self.checkpoint("Group");
+        let settings = &mut self.current_page_mut().settings;
+        settings.grouping = settings.grouping.next();
+        let status = match settings.grouping {
+            Grouping::None => "Not grouped",
+            Grouping::Created => "Grouped by when todos were added",
+            Grouping::Due => "Grouped by due date",
+            Grouping::Priority => "Grouped by priority",
+            Grouping::Tag => "Grouped by tag",
+        };
+        self.set_status(status);
+        match selected {
+            Some(index) if self.visible_indices().contains(&index) => self.select_index(index),
+            _ => self.reset_selection(),
+        }
+    }
+
+    // List the completed todos of the page under a Done header at the bottom,
+    // or back in between the open ones
+    pub fn toggle_done_section(&mut self) {
+        let selected = self.selected_index();
+        self.checkpoint("Done section");
+        let settings = &mut self.current_page_mut().settings;
+        settings.done_section = !settings.done_section;
+        let status = match settings.done_section {
+            true => "Done todos at the bottom",
+            false => "Done todos in between",
+        };
+        self.set_status(status);
+        match selected {
+            Some(index) if self.visible_indices().contains(&index) => self.select_index(index),
+            _ => self.reset_selection(),
+        }
+        self.update_visual();
+    }
+
+    // Fold the group of the selected todo down to its header, or open it up
+    // again on its header
+    pub fn toggle_group(&mut self) {
+        let Some(group) = self.selected_group() else {
+            return;
+        };
+        let selected = self.selected_index();
+        self.checkpoint("Collapse");
+        let collapsed = &mut self.current_page_mut().settings.collapsed;
+        if let Some(at) = collapsed.iter().position(|g| *g == group) {
+            collapsed.remove(at);
+        } else {
+            collapsed.push(group.clone());
+        }
+        let rows = self.rows();
+        let header = rows
+            .iter()
+            .position(|row| matches!(row, Row::Group(g, _) if *g == group));
+        match (selected, header) {
+            (Some(index), _) if rows.contains(&Row::Todo(index)) => self.select_index(index),
+            (_, Some(header)) => self.select_row(header),
+            _ => self.reset_selection(),
+        }
+        self.update_visual();
+    }
+
+    // Lines of the todo list as it was last drawn, inside its border
+    pub fn page_lines(&self) -> usize {
+        (self.list_area.height.saturating_sub(2) as usize).max(1)
+    }
+
+    // Move the selection by as many rows as fit in `lines` lines, taking
+    // them as high as they were last drawn, and at least one. Stops at the
+    // first and last todo instead of wrapping around like next and previous.
+    pub fn jump_lines(&mut self, lines: isize) {
+        let count = self.rows().len();
+        if count == 0 || self.picking_mode {
+            return;
+        }
+        let height = |row: usize| self.row_heights.get(row).copied().unwrap_or(1);
+        let current = self.state.selected().unwrap_or(0).min(count - 1);
+        let mut row = current;
+        let mut taken = 0;
+        if lines > 0 {
+            while row + 1 < count && taken + height(row + 1) <= lines as usize {
+                row += 1;
+                taken += height(row);
+            }
+        } else {
+            while row > 0 && taken + height(row - 1) <= lines.unsigned_abs() {
+                row -= 1;
+                taken += height(row);
+            }
+        }
+        if row == current {
+            row = (current as isize + lines.signum()).clamp(0, count as isize - 1) as usize;
+        }
+        self.select_row(row);
+        self.update_visual();
+    }
+
+    // Row of the todo list drawn at this screen position, None on the border
+    // and below the last todo
+    pub fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        let inside =
+            column > area.x && column + 1 < area.right() && row > area.y && row + 1 < area.bottom();
+        if !inside {
+            return None;
+        }
+        // Rows are as high as they were drawn, wrapped or in large text
+        let mut top = 0;
+        for (index, &height) in self
+            .row_heights
+            .iter()
+            .enumerate()
+            .skip(self.state.offset())
+        {
+            top += height;
+            if ((row - area.y - 1) as usize) < top {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    // Select the row showing `todos()[index]`
+    fn select_index(&mut self, index: usize) {
+        if let Some(row) = self.rows().iter().position(|r| *r == Row::Todo(index)) {
+            self.state.select(Some(row));
+        }
+    }
+
+    // Page and todo index of the todo the user is pointing at in the current view
+    pub fn selected_location(&self) -> Option<(usize, usize)> {
+        match self.view {
+            View::List => Some((self.page_index(), self.selected_index()?)),
+            View::Agenda | View::Review | View::Calendar | View::Stats => {
+                let entry = self.selected_agenda_entry()?;
+                Some((entry.page, entry.index))
+            }
+        }
+    }
+
+    // Entries of the agenda, review or calendar view, whichever is open
+    pub fn agenda_entries(&self) -> Vec<AgendaEntry> {
+        match self.view {
+            View::Review => agenda::review_entries(&self.pages),
+            View::Calendar => agenda::day_entries(&self.pages, self.calendar_day, self.today),
+            // Nothing to select in the stats
+            View::Stats => Vec::new(),
+            _ => agenda::agenda_entries(&self.pages, self.today, self.config.agenda_horizon_days),
+        }
+    }
+
+    pub fn selected_agenda_entry(&self) -> Option<AgendaEntry> {
+        let selected = self.agenda_state.selected()?;
+        self.agenda_entries().get(selected).copied()
+    }
+
+    // Open one of the views across all pages, or go back to the page list
+    // when it is already open
+    pub fn toggle_view(&mut self, view: View) {
+        if self.view == view {
+            self.view = View::List;
+        } else {
+            self.view = view;
+            // The calendar always opens on today
+            self.calendar_day = self.today;
+            let has_entries = !self.agenda_entries().is_empty();
+            self.agenda_state.select(has_entries.then_some(0));
+        }
+    }
+
+    // Move the calendar by `days`, or by `months` keeping the day of the
+    // month where the month has it, selecting the first todo due on the new day
+    pub fn move_calendar(&mut self, days: i64, months: i32) {
+        let day = self.calendar_day + chrono::Duration::days(days);
+        let day = if months < 0 {
+            day.checked_sub_months(Months::new(months.unsigned_abs()))
+        } else {
+            day.checked_add_months(Months::new(months as u32))
+        };
+        if let Some(day) = day {
+            self.calendar_day = day;
+        }
+        let has_entries = !self.agenda_entries().is_empty();
+        self.agenda_state.select(has_entries.then_some(0));
+    }
+
+    // Flag or unflag the selected todo as needing review
+    pub fn toggle_review(&mut self) {
+        if let Some((page, index)) = self.selected_location() {
+            self.checkpoint("Review");
+            let Some(todo) = self.todo_at_mut((page, index)) else {
+                return;
+            };
+            todo.needs_review = !todo.needs_review;
+            todo.touch();
+
+            if self.view != View::List {
+                self.reselect_agenda((page, index));
+            }
+        }
+    }
+
+    pub fn agenda_next(&mut self) {
+        let count = self.agenda_entries().len();
+        if count > 0 {
+            let i = self.agenda_state.selected().map_or(0, |i| (i + 1) % count);
+            self.agenda_state.select(Some(i));
+        }
+    }
+
+    // Select the first or last entry of the agenda
+    pub fn agenda_edge(&mut self, last: bool) {
+        let count = self.agenda_entries().len();
+        if count > 0 {
+            self.agenda_state
+                .select(Some(if last { count - 1 } else { 0 }));
+        }
+    }
+
+    pub fn agenda_previous(&mut self) {
+        let count = self.agenda_entries().len();
+        if count > 0 {
+            let i = self
+                .agenda_state
+                .selected()
+                .map_or(0, |i| (i + count - 1) % count);
+            self.agenda_state.select(Some(i));
+        }
+    }
+
+    // Keep the agenda selection on `location` after a change reordered the
+    // entries, or on the same row when the todo left the agenda
+    fn reselect_agenda(&mut self, location: (usize, usize)) {
+        let entries = self.agenda_entries();
+        let row = entries
+            .iter()
+            .position(|e| (e.page, e.index) == location)
+            .or_else(|| {
+                let row = self.agenda_state.selected()?;
+                Some(row.min(entries.len().checked_sub(1)?))
+            });
+        self.agenda_state.select(row);
+    }
+
+    // Complete the selected agenda todo on the page that owns it
+    pub fn complete_agenda_todo(&mut self) {
+        if self.requires_completion_note() {
+            self.input_mode = InputMode::CompletionNote;
+            return;
+        }
+        self.finish_agenda_completion();
+    }
+
+    fn finish_agenda_completion(&mut self) {
+        if let Some(entry) = self.selected_agenda_entry() {
+            self.checkpoint("Complete");
+            if let Some(todo) = self.todo_at_mut((entry.page, entry.index)) {
+                todo.set_completed(true);
+            }
+            self.reselect_agenda((entry.page, entry.index));
+        }
+    }
+
+    pub fn start_postpone(&mut self) {
+        self.current_input = "+1d".to_string();
+        self.input_mode = InputMode::Postpone;
+    }
+
+    // Move all overdue todos by the offset typed into the postpone popup,
+    // counted from today. Returns false when the offset is invalid.
+    pub fn postpone_overdue(&mut self) -> bool {
+        let today = Local::now().date_naive();
+        let Some(date) =
+            parse_offset(&self.current_input).and_then(|days| today.checked_add_days(days))
+        else {
+            self.set_status("Invalid offset, expected e.g. +1d or +2w");
+            return false;
+        };
+
+        let selected = self.selected_agenda_entry();
+        self.checkpoint("Postpone");
+        let postponed = agenda::postpone_overdue(&mut self.pages, today, date);
+        if let Some(entry) = selected {
+            self.reselect_agenda((entry.page, entry.index));
+        }
+
+        let message = match postponed.len() {
+            0 => "Nothing is overdue".to_string(),
+            moved => format!(
+                "Postponed {} overdue todos to {} (u to undo)",
+                moved,
+                date.format("%Y-%m-%d")
+            ),
+        };
+        self.set_status(message);
+        self.current_input.clear();
+        true
+    }
+
+    // Leave the agenda for the page of the selected todo, with it selected
+    pub fn open_agenda_todo(&mut self) {
+        if let Some(entry) = self.selected_agenda_entry() {
+            self.current_page_index = entry.page;
+            self.page_select_state.select(Some(entry.page));
+            self.pages[entry.page].tag_filter = None;
+            self.select_index(entry.index);
+            self.view = View::List;
+        }
+    }
+
+    // Open the page of the todo with this ID and select it, showing it even
+    // when snoozed or filtered out. False when no page has it.
+    pub fn select_todo_by_id(&mut self, id: Uuid) -> bool {
+        let found = self.pages.iter().enumerate().find_map(|(page, p)| {
+            let index = p.todos.iter().position(|t| t.id == id)?;
+            Some((page, index))
+        });
+        let Some((page, index)) = found else {
+            return false;
+        };
+        self.current_page_index = page;
+        self.page_select_state.select(Some(page));
+        self.pages[page].tag_filter = None;
+        if self.pages[page].todos[index].is_deferred(Local::now()) {
+            self.show_deferred = true;
+        }
+        self.view = View::List;
+        self.select_index(index);
+        true
+    }
+
+    // Select the first row, or nothing when no todos are shown
+    pub fn reset_selection(&mut self) {
+        self.select_row(0);
+    }
+
+    // Add a new page
+    pub fn add_page(&mut self, name: String, page_type: PageType) {
+        if !name.is_empty() && !self.pages.iter().any(|p| p.name == name) {
+            self.checkpoint("New page");
+            let mut new_page = TodoPage::new(name);
+            new_page.settings = PageSettings::preset(page_type);
+            // New pages live in the same file as the page they were created from
+            new_page.store = self.current_page().store;
+            self.pages.push(new_page);
+            self.current_page_index = self.pages.len() - 1;
+            self.set_status("Page created");
+
+            // Update page select state
+            self.page_select_state.select(Some(self.current_page_index));
+            self.reset_selection();
+        }
+    }
+
+    fn snapshot(&self, action: &'static str) -> Snapshot {
+        Snapshot {
+            action,
+            pages: self.pages.clone(),
+            current_page_index: self.current_page_index,
+            selected: self.state.selected(),
+        }
+    }
+
+    // Remember the pages before a change so it can be undone
+    fn checkpoint(&mut self, action: &'static str) {
+        let snapshot = self.snapshot(action);
+        self.history.record(snapshot);
+        self.dirty = true;
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.dirty = true;
+        self.pages = snapshot.pages;
+        self.current_page_index = snapshot.current_page_index;
+        self.page_select_state.select(Some(self.page_index()));
+        self.picking_mode = false;
+        self.state.select(snapshot.selected);
+        self.clamp_selection();
+    }
+
+    pub fn undo(&mut self) {
+        let current = self.snapshot("");
+        match self.history.undo(current) {
+            Some(snapshot) => {
+                self.notify(format!("Undid {}", snapshot.action));
+                self.restore(snapshot);
+            }
+            None => self.set_status("Nothing to undo"),
+        }
+    }
+
+    pub fn redo(&mut self) {
+        let current = self.snapshot("");
+        match self.history.redo(current) {
+            Some(snapshot) => {
+                self.notify(format!("Redid {}", snapshot.action));
+                self.restore(snapshot);
+            }
+            None => self.set_status("Nothing to redo"),
+        }
+    }
+
+    // Delete a page unless it is the last one, its neighbour becomes current
+    pub fn delete_page(&mut self, index: usize) {
+        if self.pages.len() <= 1 || index >= self.pages.len() {
+            return;
+        }
+        self.checkpoint("Delete Page");
+        self.pages.remove(index);
+
+        let current = index.min(self.pages.len() - 1);
+        self.page_select_state.select(Some(current));
+        self.current_page_index = current;
+
+        // Reset todo selection for the new page
+        self.reset_selection();
+    }
+
+    // Delete a page from the page list, asking first unless configured not to
+    pub fn request_delete_page(&mut self, index: usize) {
+        if self.pages.len() <= 1 || index >= self.pages.len() {
+            return;
+        }
+        if self.config.confirm_delete == ConfirmDelete::Never {
+            self.delete_page(index);
+        } else {
+            self.pending_delete = Some(PendingDelete::Page(index));
+            self.input_mode = InputMode::ConfirmDelete;
+        }
+    }
+
+    // Delete the marked todos or the selected one, asking first when
+    // configured to
+    pub fn request_delete_todo(&mut self) {
+        if self.targets().is_empty() {
+            return;
+        }
+        if self.config.confirm_delete == ConfirmDelete::All {
+            self.pending_delete = Some(PendingDelete::Todos);
+            self.input_mode = InputMode::ConfirmDelete;
+        } else {
+            self.delete_todo();
+        }
+    }
+
+    // The answer to the confirmation popup, going back to where it was asked
+    pub fn confirm_delete(&mut self, yes: bool) {
+        match self.pending_delete.take() {
+            Some(PendingDelete::Todos) => {
+                self.input_mode = InputMode::Normal;
+                if yes {
+                    self.delete_todo();
+                }
+            }
+            Some(PendingDelete::Page(index)) => {
+                self.input_mode = InputMode::PageSelect;
+                if yes {
+                    self.delete_page(index);
+                }
+            }
+            None => self.input_mode = InputMode::Normal,
+        }
+    }
+
+    // What the confirmation popup asks
+    pub fn delete_question(&self) -> String {
+        match self.pending_delete {
+            Some(PendingDelete::Page(index)) => {
+                let page = &self.pages[index];
+                let count = page.todos.len();
+                let todos = if count == 1 { "todo" } else { "todos" };
+                format!(
+                    "Delete page {} and its {} {}?",
+                    self.page_label(page),
+                    count,
+                    todos
+                )
+            }
+            Some(PendingDelete::Todos) => match self.targets().as_slice() {
+                [index] => format!("Delete \"{}\"?", self.todos()[*index].description),
+                targets => format!("Delete {} todos?", targets.len()),
+            },
+            None => String::new(),
+        }
+    }
+
+    // Select a page by name
+    pub fn select_page_by_name(&mut self, name: &str) -> bool {
+        if let Some(index) = self.pages.iter().position(|p| p.name == name) {
+            self.current_page_index = index;
+            self.page_select_state.select(Some(index));
+
+            // Reset todo selection for the new page
+            self.reset_selection();
+
+            true
+        } else {
+            false
+        }
+    }
+
+    // Indices of the pages in the active workspace, all pages without one
+    // or when none of its pages exist
+    pub fn workspace_pages(&self) -> Vec<usize> {
+        let in_workspace: Vec<usize> =
+            match self.workspace.and_then(|w| self.config.workspaces.get(w)) {
+                Some(workspace) => (0..self.pages.len())
+                    .filter(|&i| {
+                        let label = self.page_label(&self.pages[i]);
+                        workspace.pages.iter().any(|name| {
+                            name.eq_ignore_ascii_case(&self.pages[i].name)
+                                || name.eq_ignore_ascii_case(&label)
+                        })
+                    })
+                    .collect(),
+                None => Vec::new(),
+            };
+
+        if in_workspace.is_empty() {
+            (0..self.pages.len()).collect()
+        } else {
+            in_workspace
+        }
+    }
+
+    pub fn select_page(&mut self, index: usize) {
+        if index >= self.pages.len() {
+            return;
+        }
+        // Searches and marks only apply to the page they were made on
+        self.search = None;
+        self.clear_marks();
+        self.current_page_index = index;
+        self.page_select_state.select(Some(index));
+
+        // Reset todo selection for the new page
+        self.reset_selection();
+    }
+
+    // Navigate to next page of the workspace
+    pub fn next_page(&mut self) {
+        if self.pages.is_empty() {
+            return;
+        }
+        let pages = self.workspace_pages();
+        let next = match pages.iter().position(|&i| i == self.current_page_index) {
+            Some(position) => pages[(position + 1) % pages.len()],
+            None => pages[0],
+        };
+        self.select_page(next);
+    }
+
+    // Navigate to previous page of the workspace
+    pub fn previous_page(&mut self) {
+        if self.pages.is_empty() {
+            return;
+        }
+        let pages = self.workspace_pages();
+        let previous = match pages.iter().position(|&i| i == self.current_page_index) {
+            Some(position) => pages[(position + pages.len() - 1) % pages.len()],
+            None => pages[pages.len() - 1],
+        };
+        self.select_page(previous);
+    }
+
+    // Open the page with this number in `ratdo show`, counted from 1, with 0
+    // for the last page
+    pub fn go_to_page_number(&mut self, number: usize) {
+        let index = match number {
+            0 => self.pages.len() - 1,
+            number => number - 1,
+        };
+        if index < self.pages.len() {
+            self.select_page(index);
+        } else {
+            self.set_status(format!("No page {}", number));
+        }
+    }
+
+    pub fn workspace_name(&self) -> Option<&str> {
+        let workspace = self.config.workspaces.get(self.workspace?)?;
+        Some(&workspace.name)
+    }
+
+    // Cycle through the configured workspaces and back to all pages, moving
+    // to the first page of the new workspace unless the current one is in it
+    pub fn next_workspace(&mut self) {
+        if self.config.workspaces.is_empty() {
+            self.set_status("No workspaces configured");
+            return;
+        }
+
+        self.workspace = match self.workspace {
+            None => Some(0),
+            Some(w) if w + 1 < self.config.workspaces.len() => Some(w + 1),
+            Some(_) => None,
+        };
+        self.enter_workspace();
+    }
+
+    fn enter_workspace(&mut self) {
+        let pages = self.workspace_pages();
+        if !pages.is_empty() && !pages.contains(&self.current_page_index) {
+            self.select_page(pages[0]);
+        }
+        let name = self.workspace_name().unwrap_or("all pages").to_string();
+        self.set_status(format!("Workspace: {}", name));
+    }
+
+    // Start in the first workspace whose rules match the time, host and
+    // directory, otherwise in the one active when the TUI was last closed
+    pub fn restore_session(&mut self) -> io::Result<()> {
+        let now = Local::now().time();
+        let host = config::hostname();
+        let cwd = env::current_dir()?;
+        let automatic = self
+            .config
+            .workspaces
+            .iter()
+            .position(|w| w.matches(now, &host, &cwd));
+
+        let session = self.storage.load_session()?;
+        self.recent_pages = session.recent_pages;
+        self.usage = session.usage;
+        self.carried_over = session.carried_over;
+        self.summarized = session.summarized;
+        self.workspace = match automatic {
+            Some(index) => Some(index),
+            None => session.workspace.and_then(|name| {
+                self.config
+                    .workspaces
+                    .iter()
+                    .position(|w| w.name.eq_ignore_ascii_case(&name))
+            }),
+        };
+        if self.workspace.is_some() {
+            self.enter_workspace();
+        }
+        Ok(())
+    }
+
+    pub fn save_session(&mut self) -> io::Result<()> {
+        let session = Session {
+            workspace: self.workspace_name().map(str::to_string),
+            recent_pages: self.recent_pages.clone(),
+            carried_over: self.carried_over,
+            summarized: self.summarized,
+            usage: self.usage.clone(),
+        };
+        self.storage.save_session(&session)
+    }
+
+    // Move the current page to the front of the recent pages, except while
+    // cycling through them so repeated presses of ` keep going back
+    pub fn note_page_visit(&mut self) {
+        if self.recent_cycle.is_some() {
+            return;
+        }
+        let label = self.page_label(self.current_page());
+        if self.recent_pages.first() != Some(&label) {
+            self.recent_pages.retain(|l| *l != label);
+            self.recent_pages.insert(0, label);
+            self.recent_pages.truncate(20);
+        }
+    }
+
+    // Go to the previously visited page, pressed again right away to the one
+    // before it and so on. Any other key ends the cycle at the page reached.
+    pub fn cycle_recent_page(&mut self) {
+        let labels: Vec<String> = self.pages.iter().map(|p| self.page_label(p)).collect();
+        self.recent_pages.retain(|label| labels.contains(label));
+        if self.recent_pages.len() < 2 {
+            self.set_status("No other recently used page");
+            return;
+        }
+
+        let depth = self.recent_cycle.map_or(1, |depth| depth + 1) % self.recent_pages.len();
+        let label = self.recent_pages[depth].clone();
+        self.recent_cycle = Some(depth);
+        self.view = View::List;
+        self.select_page_by_label(&label);
+    }
+
+    pub fn end_recent_cycle(&mut self) {
+        if self.recent_cycle.take().is_some() {
+            self.note_page_visit();
+        }
+    }
+
+    // Toggle page selector visibility
+    pub fn toggle_page_selector(&mut self) {
+        self.show_page_selector = !self.show_page_selector;
+
+        if self.show_page_selector {
+            self.input_mode = InputMode::PageSelect;
+            self.page_select_state.select(Some(self.current_page_index));
+        } else {
+            self.input_mode = InputMode::Normal;
+        }
+    }
+
+    // Toggle checklist mode for the current page
+    pub fn toggle_checklist_mode(&mut self) {
+        self.checkpoint("Checklist mode");
+        let settings = &mut self.current_page_mut().settings;
+        settings.checklist_mode = !settings.checklist_mode;
+    }
+
+    // URLs in the description followed by the attachments of the selected todo
+    pub fn links(&self) -> Vec<String> {
+        self.selected_todo().map(Todo::links).unwrap_or_default()
+    }
+
+    pub fn start_attaching(&mut self) {
+        if self.selected_location().is_some() {
+            self.current_input.clear();
+            self.input_mode = InputMode::Attach;
+        }
+    }
+
+    // Attach the path or URL typed into the attach popup to the selected todo
+    pub fn add_attachment(&mut self) {
+        let target = self.current_input.trim().to_string();
+        if !target.is_empty() && self.selected_location().is_some() {
+            self.checkpoint("Attach");
+        }
+        if let (false, Some(todo)) = (target.is_empty(), self.selected_todo_mut()) {
+            todo.attachments.push(target);
+            todo.touch();
+        }
+        self.current_input.clear();
+    }
+
+    // Open the only link of the selected todo right away, or let the user
+    // pick one when there are several
+    pub fn open_links(&mut self) {
+        match self.links().len() {
+            0 => self.set_status("No links, press L to attach a file or URL"),
+            1 => self.open_link(0),
+            _ => {
+                self.link_select_state.select(Some(0));
+                self.input_mode = InputMode::Links;
+            }
+        }
+    }
+
+    // Copy the selected todo's description to the clipboard, followed by its
+    // notes after a blank line
+    pub fn yank(&mut self, with_notes: bool) {
+        let Some(todo) = self.selected_location().and_then(|at| self.todo_at(at)) else {
+            return;
+        };
+        let mut text = todo.description.clone();
+        if with_notes && !todo.notes.is_empty() {
+            text = format!("{}\n\n{}", text, todo.notes);
+        }
+        match clipboard::copy(&text) {
+            Ok(()) if with_notes => self.notify("Copied the description and notes"),
+            Ok(()) => self.notify("Copied the description"),
+            Err(err) => self.notify_error(format!("Could not copy: {}", err)),
+        }
+    }
+
+    // Preview the lines of the clipboard to add them as todos
+    pub fn start_import(&mut self) {
+        match clipboard::paste() {
+            Ok(text) => self.preview_import(&text),
+            Err(err) => self.notify_error(format!("Could not read the clipboard: {}", err)),
+        }
+    }
+
+    pub fn preview_import(&mut self, text: &str) {
+        self.import = list_items(text);
+        if self.import.is_empty() {
+            self.set_status("Nothing to import on the clipboard");
+            return;
+        }
+        self.import_select_state.select(Some(0));
+        self.input_mode = InputMode::Import;
+    }
+
+    // Leave the highlighted line out of the import, closing the preview with
+    // the last one
+    pub fn drop_import_line(&mut self) {
+        let Some(selected) = self.import_select_state.selected() else {
+            return;
+        };
+        if selected < self.import.len() {
+            self.import.remove(selected);
+        }
+        if self.import.is_empty() {
+            self.input_mode = InputMode::Normal;
+        } else {
+            self.import_select_state
+                .select(Some(selected.min(self.import.len() - 1)));
+        }
+    }
+
+    // Add the previewed lines after the selected todo, each read like the
+    // input of the add popup
+    pub fn import_todos(&mut self) {
+        let lines = std::mem::take(&mut self.import);
+        if lines.is_empty() {
+            return;
+        }
+        self.checkpoint("Import");
+        let quantities = self.current_page().settings.quantities;
+        let tag_filter = self.current_page().tag_filter.clone();
+        let insertion_index = match self.selected_index() {
+            Some(index) => index + 1,
+            None => self.todos().len(),
+        };
+        for (offset, line) in lines.iter().enumerate() {
+            let parsed = parse_input(line, quantities, &[]);
+            let mut todo = match parsed.error.is_some() || parsed.description.is_empty() {
+                true => Todo::new(line.clone()),
+                false => {
+                    let mut todo = Todo::new(parsed.description);
+                    todo.quantity = parsed.quantity;
+                    todo.tags = parsed.tags;
+                    todo.assignee = parsed.assignee;
+                    todo.estimate = parsed.estimate;
+                    todo.due_date = parsed.due_date;
+                    todo.priority = parsed.priority;
+                    todo
+                }
+            };
+            if let Some(tag) = &tag_filter {
+                if !todo.tags.contains(tag) {
+                    todo.tags.push(tag.clone());
+                }
+            }
+            self.todos_mut().insert(insertion_index + offset, todo);
+        }
+        self.select_index(insertion_index);
+        self.notify(format!("Imported {} todos", lines.len()));
+    }
+
+    pub fn open_link(&mut self, index: usize) {
+        if let Some(target) = self.links().get(index).cloned() {
+            match opener::open(&target) {
+                Ok(()) => self.set_status(format!("Opened {}", target)),
+                Err(err) => self.notify_error(format!("Could not open {}: {}", target, err)),
+            }
+        }
+    }
+
+    // Remove the attachment highlighted in the chooser, closing it with the
+    // last link. URLs from the description stay until the description changes.
+    pub fn remove_attachment(&mut self) {
+        let (Some(selected), Some((page, index))) =
+            (self.link_select_state.selected(), self.selected_location())
+        else {
+            return;
+        };
+
+        let Some(todo) = self.todo_at((page, index)) else {
+            return;
+        };
+        let Some(attachment) = selected.checked_sub(extract_urls(&todo.description).len()) else {
+            self.set_status("Edit the description to remove this link");
+            return;
+        };
+        if attachment < todo.attachments.len() {
+            self.checkpoint("Remove link");
+        }
+        let Some(todo) = self.todo_at_mut((page, index)) else {
+            return;
+        };
+        if attachment < todo.attachments.len() {
+            todo.attachments.remove(attachment);
+            todo.touch();
+        }
+
+        match todo.links().len() {
+            0 => self.input_mode = InputMode::Normal,
+            count => self.link_select_state.select(Some(selected.min(count - 1))),
+        }
+    }
+
+    // Open the tag picker for the current page
+    pub fn start_tag_filter(&mut self) {
+        let tags = self.current_page().tags();
+        if tags.is_empty() {
+            self.set_status("No tags on this page, add them as +tag words");
+            return;
+        }
+
+        let current = self.current_page().tag_filter.as_ref();
+        let selected = current.and_then(|tag| tags.iter().position(|t| t == tag));
+        self.tag_select_state.select(Some(selected.unwrap_or(0)));
+        self.input_mode = InputMode::TagFilter;
+    }
+
+    // Show only todos with the tag highlighted in the tag picker
+    pub fn apply_tag_filter(&mut self) {
+        let tags = self.current_page().tags();
+        if let Some(tag) = self.tag_select_state.selected().and_then(|i| tags.get(i)) {
+            // View state only, the file doesn't change
+            let index = self.page_index();
+            self.pages[index].tag_filter = Some(tag.clone());
+            self.reset_selection();
+        }
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn start_search(&mut self) {
+        self.search = Some(String::new());
+        self.reset_selection();
+        self.input_mode = InputMode::Search;
+    }
+
+    // Narrow the list down as the search text is typed, selecting the first match
+    pub fn search_push(&mut self, c: char) {
+        self.search.get_or_insert_with(String::new).push(c);
+        self.reset_selection();
+    }
+
+    pub fn search_pop(&mut self) {
+        if let Some(query) = &mut self.search {
+            query.pop();
+        }
+        self.reset_selection();
+    }
+
+    // Keep the list narrowed down after Enter, an empty search shows everything
+    pub fn finish_search(&mut self) {
+        if self.search.as_deref() == Some("") {
+            self.search = None;
+        }
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn clear_search(&mut self) {
+        let row = self.selected_index();
+        self.search = None;
+        self.input_mode = InputMode::Normal;
+
+        // Stay on the todo that was selected among the matches
+        match row {
+            Some(index) => self.select_index(index),
+            None => self.reset_selection(),
+        }
+    }
+
+    // Jump to the next or previous match of the search, wrapping around
+    pub fn search_next(&mut self, forward: bool) {
+        if self.search.is_none() {
+            return;
+        }
+        match self.state.selected() {
+            Some(_) => self.step(forward),
+            None => self.select_row(0),
+        }
+    }
+
+    pub fn clear_tag_filter(&mut self) {
+        if self.current_page().tag_filter.is_some() {
+            let index = self.page_index();
+            self.pages[index].tag_filter = None;
+            self.reset_selection();
+        }
+    }
+
+    // Toggle quantity tracking for the current page
+    pub fn toggle_quantities(&mut self) {
+        self.checkpoint("Quantities");
+        let settings = &mut self.current_page_mut().settings;
+        settings.quantities = !settings.quantities;
+    }
+
+    // Change the quantity of the selected todo by one in either direction
+    pub fn adjust_quantity(&mut self, increment: bool) {
+        if let Some(selected) = self.selected_index() {
+            self.checkpoint("Quantity");
+            if let Some(todo) = self.todos_mut().get_mut(selected) {
+                if increment {
+                    todo.increment_quantity();
+                } else {
+                    todo.decrement_quantity();
+                }
+            }
+        }
+    }
+
+    // Parse the popup input with the settings of the current page
+    fn parsed_input(&self) -> ParsedInput {
+        let pages: Vec<String> = self
+            .pages
+            .iter()
+            .flat_map(|page| [page_token(&page.name), page_token(&self.page_label(page))])
+            .collect();
+        // Quantities are split off as the page the todo goes to tracks them
+        let parsed = parse_input(&self.current_input, false, &pages);
+        let target = parsed
+            .page
+            .as_deref()
+            .and_then(|name| self.page_for_token(name))
+            .unwrap_or(self.page_index());
+        match self.pages[target].settings.quantities {
+            true => parse_input(&self.current_input, true, &pages),
+            false => parsed,
+        }
+    }
+
+    // Toggle picking mode
+    pub fn toggle_picking_mode(&mut self) {
+        // Grouped pages are in date order, moving would not show
+        if !self.picking_mode && self.current_page().settings.grouping != Grouping::None {
+            self.set_status("Grouped pages can't be reordered");
+            return;
+        }
+        self.picking_mode = !self.picking_mode;
+        self.moved_while_picking = false;
+    }
+
+    // Record the undo step of a move once, so undo puts the todo back where
+    // it was before moving started
+    fn checkpoint_move(&mut self) {
+        if !self.moved_while_picking {
+            self.checkpoint("Move");
+            self.moved_while_picking = true;
+        }
+    }
+
+    // Select the next or previous row that can be selected, wrapping around
+    fn step(&mut self, forward: bool) {
+        let rows = self.rows();
+        if rows.is_empty() {
+            return;
+        }
+        let count = rows.len();
+        let current = self.state.selected().unwrap_or(0).min(count - 1);
+        let row = (1..=count)
+            .map(|i| match forward {
+                true => (current + i) % count,
+                false => (current + count - i) % count,
+            })
+            .find(|&r| self.is_selectable(&rows[r]));
+        self.state.select(row);
+    }
+
+    // Go `count` rows down or up, stopping at the last or first todo instead
+    // of wrapping around. In move mode the todo moves along.
+    pub fn move_by(&mut self, count: usize, forward: bool) {
+        for _ in 0..count {
+            let rows = self.rows();
+            let Some(current) = self.state.selected() else {
+                return;
+            };
+            let rest = match forward {
+                true => rows.get(current + 1..).unwrap_or_default(),
+                false => &rows[..current.min(rows.len())],
+            };
+            if !rest.iter().any(|row| self.is_selectable(row)) {
+                return;
+            }
+            if forward {
+                self.next();
+            } else {
+                self.previous();
+            }
+        }
+    }
+
+    // Select the first todo that isn't done, past the ones ticked off at the top
+    pub fn first_open(&mut self) {
+        let todos = self.todos();
+        match self
+            .visible_indices()
+            .into_iter()
+            .find(|&index| !todos[index].completed)
+        {
+            Some(index) => {
+                self.select_index(index);
+                self.update_visual();
+            }
+            None => self.set_status("Everything here is done"),
+        }
+    }
+
+    // Go to the `number`th todo of the list, counted from 1, moving the todo
+    // there in move mode
+    pub fn go_to_todo(&mut self, number: usize) {
+        let shown = self.visible_indices();
+        let Some(current) = self
+            .selected_index()
+            .and_then(|index| shown.iter().position(|&i| i == index))
+        else {
+            return;
+        };
+        let target = number.clamp(1, shown.len()) - 1;
+        self.move_by(target.abs_diff(current), target > current);
+    }
+
+    // Todos a picked todo moves between, the shown todos of its section, and
+    // its position among them
+    fn picking_rows(&self) -> Option<(Vec<usize>, usize)> {
+        let selected = self.selected_index()?;
+        let todos = self.todos();
+        let group = self.group(&todos[selected]);
+        let rows: Vec<usize> = self
+            .visible_indices()
+            .into_iter()
+            .filter(|&index| self.group(&todos[index]) == group)
+            .collect();
+        let current = rows.iter().position(|&index| index == selected)?;
+        Some((rows, current))
+    }
+
+    // Override next and previous to handle moving todos when in picking mode.
+    // Both work on the visible rows, so with a filter active a picked todo
+    // swaps places with the next shown todo.
+    pub fn next(&mut self) {
+        if !self.picking_mode {
+            self.step(true);
+            self.update_visual();
+            return;
+        }
+        let Some((rows, current)) = self.picking_rows() else {
+            return;
+        };
+        let i = if current >= rows.len() - 1 {
+            0
+        } else {
+            current + 1
+        };
+
+        // Move the todo if we're in picking mode
+        if i != current {
+            self.checkpoint_move();
+            let todos = self.todos_mut();
+
+            // Handle wrap-around case
+            if i == 0 {
+                // Move from end to beginning
+                let todo = todos.remove(rows[current]);
+                todos.insert(rows[0], todo);
+            } else {
+                // Standard case - swap with the next item
+                todos.swap(rows[current], rows[i]);
+            }
+        }
+
+        self.select_index(rows[i]);
+        self.update_visual();
+    }
+
+    pub fn previous(&mut self) {
+        if !self.picking_mode {
+            self.step(false);
+            self.update_visual();
+            return;
+        }
+        let Some((rows, current)) = self.picking_rows() else {
+            return;
+        };
+        let i = if current == 0 {
+            rows.len() - 1
+        } else {
+            current - 1
+        };
+
+        // Move the todo if we're in picking mode
+        if i != current {
+            self.checkpoint_move();
+            let todos = self.todos_mut();
+
+            // Handle wrap-around case
+            if current == 0 {
+                // Move from beginning to end
+                let todo = todos.remove(rows[current]);
+                todos.insert(rows[i], todo);
+            } else {
+                // Standard case - swap with the previous item
+                todos.swap(rows[current], rows[i]);
+            }
+        }
+
+        self.select_index(rows[i]);
+        self.update_visual();
+    }
+
+    pub fn add_todo(&mut self) {
+        self.checkpoint("Add");
+        let parsed = self.parsed_input();
+        let mut todo = Todo::new(parsed.description);
+        todo.quantity = parsed.quantity;
+        todo.tags = parsed.tags;
+        todo.assignee = parsed.assignee;
+        todo.estimate = parsed.estimate;
+        todo.due_date = parsed.due_date;
+        todo.priority = parsed.priority;
+        self.current_input.clear();
+
+        // Quick-add to another page
+        if let Some(page) = parsed.page.and_then(|name| self.page_for_token(&name)) {
+            if page != self.current_page_index {
+                let label = self.page_label(&self.pages[page]);
+                self.pages[page].todos.push(todo);
+                self.set_status(format!("Added to {}", label));
+                return;
+            }
+        }
+
+        // Keep new todos visible under the active tag filter
+        if let Some(tag) = &self.current_page().tag_filter {
+            if !todo.tags.contains(tag) {
+                todo.tags.push(tag.clone());
+            }
+        }
+
+        let insertion_index = match self.selected_index() {
+            Some(index) => index + 1,   // Insert after current selection
+            None => self.todos().len(), // If nothing selected, append to end
+        };
+        self.todos_mut().insert(insertion_index, todo);
+        self.select_index(insertion_index); // Move selection to the new todo
+    }
+
+    // Index of the page a `/page` word refers to
+    fn page_for_token(&self, name: &str) -> Option<usize> {
+        self.pages.iter().position(|page| {
+            page_token(&page.name).eq_ignore_ascii_case(name)
+                || page_token(&self.page_label(page)).eq_ignore_ascii_case(name)
+        })
+    }
+
+    // The `/page` word being typed at the end of the input, if any
+    fn page_prefix(&self) -> Option<&str> {
+        if self.current_input.ends_with(char::is_whitespace) {
+            return None;
+        }
+        let word = self.current_input.split_whitespace().last()?;
+        word.strip_prefix('/')
+    }
+
+    // Pages fuzzy matching the switcher input, best match first
+    pub fn page_matches(&self) -> Vec<usize> {
+        let mut matches: Vec<(i32, usize)> = self
+            .pages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, page)| {
+                fuzzy_score(&self.current_input, &self.page_label(page)).map(|score| (score, index))
+            })
+            .collect();
+        // Stable, so equally good matches keep the page order
+        matches.sort_by_key(|&(score, _)| -score);
+        matches.into_iter().map(|(_, index)| index).collect()
+    }
+
+    pub fn start_page_switcher(&mut self) {
+        self.moving_to_page = false;
+        self.current_input.clear();
+        self.page_switch_state.select(Some(0));
+        self.input_mode = InputMode::PageSwitch;
+    }
+
+    // Keep the best match highlighted while typing
+    pub fn reset_page_switch(&mut self) {
+        let selected = (self.switcher_len() > 0).then_some(0);
+        self.page_switch_state.select(selected);
+    }
+
+    // Entries of the open switcher, pages or the todos of the jump
+    fn switcher_len(&self) -> usize {
+        match self.input_mode {
+            InputMode::TodoJump => self.todo_matches().len(),
+            _ => self.page_matches().len(),
+        }
+    }
+
+    pub fn select_page_match(&mut self, forward: bool) {
+        let count = self.switcher_len();
+        if count == 0 {
+            return;
+        }
+        let current = self.page_switch_state.selected().unwrap_or(0);
+        let next = if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        };
+        self.page_switch_state.select(Some(next));
+    }
+
+    // Open the highlighted match, or move the todos there, and close the switcher
+    pub fn switch_to_page_match(&mut self) {
+        let matches = self.page_matches();
+        let selected = self.page_switch_state.selected().unwrap_or(0);
+        self.current_input.clear();
+        self.input_mode = InputMode::Normal;
+        if let Some(&index) = matches.get(selected) {
+            if self.moving_to_page {
+                self.move_to_page(index);
+            } else {
+                self.view = View::List;
+                self.select_page(index);
+            }
+        }
+    }
+
+    // Shown todos of the page fuzzy matching the jump input, best match first
+    pub fn todo_matches(&self) -> Vec<usize> {
+        let todos = self.todos();
+        let mut matches: Vec<(i32, usize)> = self
+            .visible_indices()
+            .into_iter()
+            .filter_map(|index| {
+                fuzzy_score(&self.current_input, &todos[index].description)
+                    .map(|score| (score, index))
+            })
+            .collect();
+        matches.sort_by_key(|&(score, _)| -score);
+        matches.into_iter().map(|(_, index)| index).collect()
+    }
+
+    // Apply a key to the text of the open input popup, true when it was an
+    // editing key
+    pub fn edit_input(&mut self, key: KeyEvent) -> bool {
+        input::edit(&mut self.current_input, &mut self.input_cursor, key)
+    }
+
+    pub fn paste_input(&mut self, text: &str) {
+        input::insert(&mut self.current_input, &mut self.input_cursor, text);
+    }
+
+    pub fn start_todo_jump(&mut self) {
+        self.current_input.clear();
+        self.page_switch_state.select(Some(0));
+        self.input_mode = InputMode::TodoJump;
+        self.reset_page_switch();
+    }
+
+    // Select the highlighted match and close the jump, the list stays as it is
+    pub fn jump_to_todo_match(&mut self) {
+        let matches = self.todo_matches();
+        let selected = self.page_switch_state.selected().unwrap_or(0);
+        self.current_input.clear();
+        self.input_mode = InputMode::Normal;
+        if let Some(&index) = matches.get(selected) {
+            self.select_index(index);
+        }
+    }
+
+    // Pages matching the `/page` word being typed, prefix matches first
+    pub fn page_completions(&self) -> Vec<String> {
+        let Some(prefix) = self.page_prefix() else {
+            return Vec::new();
+        };
+        let prefix = prefix.to_lowercase();
+
+        let tokens: Vec<String> = self
+            .pages
+            .iter()
+            .map(|page| page_token(&self.page_label(page)))
+            .collect();
+        let (mut completions, rest): (Vec<String>, Vec<String>) = tokens
+            .into_iter()
+            .filter(|token| token.to_lowercase().contains(&prefix))
+            .partition(|token| token.to_lowercase().starts_with(&prefix));
+        completions.extend(rest);
+
+        // Nothing left to complete once the word names a page exactly
+        if completions.len() == 1 && completions[0].to_lowercase() == prefix {
+            completions.clear();
+        }
+        completions
+    }
+
+    // Restart the completion list after the input changed
+    pub fn reset_page_completion(&mut self) {
+        let selected = (!self.page_completions().is_empty()).then_some(0);
+        self.page_completion_state.select(selected);
+    }
+
+    pub fn select_page_completion(&mut self, forward: bool) {
+        let count = self.page_completions().len();
+        if count == 0 {
+            return;
+        }
+        let current = self.page_completion_state.selected().unwrap_or(0);
+        let next = if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        };
+        self.page_completion_state.select(Some(next));
+    }
+
+    // Replace the `/page` word with the highlighted completion
+    pub fn complete_page(&mut self) -> bool {
+        let completions = self.page_completions();
+        let Some(prefix) = self.page_prefix() else {
+            return false;
+        };
+        let selected = self.page_completion_state.selected().unwrap_or(0);
+        let Some(completion) = completions.get(selected) else {
+            return false;
+        };
+
+        let start = self.current_input.len() - prefix.len();
+        self.current_input.truncate(start);
+        self.current_input.push_str(completion);
+        self.current_input.push(' ');
+        self.input_cursor = usize::MAX;
+        self.page_completion_state.select(None);
+        true
+    }
+
+    // Pin or unpin the selected todo, which stays selected as it moves
+    pub fn toggle_pin(&mut self) {
+        if let Some(selected) = self.selected_index() {
+            self.checkpoint("Pin");
+            let todo = &mut self.todos_mut()[selected];
+            todo.pinned = !todo.pinned;
+            todo.touch();
+            self.select_index(selected);
+        }
+    }
+
+    // Keep the selected row in range after todos left the list
+    fn clamp_selection(&mut self) {
+        if let Some(row) = self.state.selected() {
+            self.select_row(row);
+        }
+    }
+
+    // Take the selected todo off the page, keeping the selection on its row
+    fn remove_selected(&mut self) -> Option<Todo> {
+        let row = self.state.selected()?;
+        let selected = self.selected_index()?;
+        let todo = self.todos_mut().remove(selected);
+        self.select_row(row);
+        Some(todo)
+    }
+
+    pub fn delete_todo(&mut self) {
+        if !self.marked.is_empty() {
+            self.checkpoint("Delete");
+            let count = self.take_marked().len();
+            let todos = if count == 1 { "todo" } else { "todos" };
+            self.notify(format!("Deleted {} {}{}", count, todos, self.undo_hint()));
+        } else if self.selected_index().is_some() {
+            self.checkpoint("Delete");
+            if let Some(todo) = self.remove_selected() {
+                self.notify(format!(
+                    "Deleted '{}'{}",
+                    todo.description,
+                    self.undo_hint()
+                ));
+            }
+        }
+    }
+
+    // Mark or unmark the selected todo for a bulk action, then go on to the
+    // next one
+    pub fn toggle_mark(&mut self) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        if self.picking_mode {
+            return;
+        }
+        let id = self.todos()[index].id;
+        if !self.marked.remove(&id) {
+            self.marked.insert(id);
+        }
+        self.next();
+    }
+
+    // Also ends visual mode
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.visual_anchor = None;
+    }
+
+    // Start visual mode on the selected todo, next and previous then mark the
+    // range up to it. Pressed again it ends, dropping the range.
+    pub fn toggle_visual(&mut self) {
+        if self.visual_anchor.is_some() {
+            self.clear_marks();
+            return;
+        }
+        if let (Some(index), false) = (self.selected_index(), self.picking_mode) {
+            self.visual_anchor = Some(self.todos()[index].id);
+            self.update_visual();
+        }
+    }
+
+    // Mark the rows from where visual mode started to the selected one
+    fn update_visual(&mut self) {
+        let Some(anchor) = self.visual_anchor else {
+            return;
+        };
+        let rows = self.rows();
+        let start = rows
+            .iter()
+            .position(|row| matches!(row, Row::Todo(i) if self.todos()[*i].id == anchor));
+        let (Some(start), Some(end)) = (start, self.state.selected()) else {
+            self.clear_marks();
+            return;
+        };
+        let end = end.min(rows.len() - 1);
+        self.marked = rows[start.min(end)..=start.max(end)]
+            .iter()
+            .filter_map(|row| match row {
+                Row::Todo(i) => Some(self.todos()[*i].id),
+                Row::Group(..) => None,
+            })
+            .collect();
+    }
+
+    // Indices into `todos()` of the marked todos, or of the selected one when
+    // none are marked
+    fn targets(&self) -> Vec<usize> {
+        if self.marked.is_empty() {
+            return self.selected_index().into_iter().collect();
+        }
+        (0..self.todos().len())
+            .filter(|&index| self.marked.contains(&self.todos()[index].id))
+            .collect()
+    }
+
+    // Take the marked todos off the page in their order and clear the marks
+    fn take_marked(&mut self) -> Vec<Todo> {
+        let marked = std::mem::take(&mut self.marked);
+        self.visual_anchor = None;
+        let (taken, kept): (Vec<Todo>, Vec<Todo>) = self
+            .todos_mut()
+            .drain(..)
+            .partition(|todo| marked.contains(&todo.id));
+        *self.todos_mut() = kept;
+        self.clamp_selection();
+        taken
+    }
+
+    // Complete all marked todos, or reopen them when all are completed
+    fn toggle_marked(&mut self) {
+        let targets = self.targets();
+        let complete = targets.iter().any(|&index| !self.todos()[index].completed);
+        // Each note is asked for on its own
+        let page = self.current_page();
+        if complete
+            && targets
+                .iter()
+                .any(|&index| self.needs_completion_note(page, &page.todos[index]))
+        {
+            self.set_status("Todos needing a completion note are checked off one at a time");
+            return;
+        }
+        self.checkpoint("Toggle");
+        for &index in &targets {
+            let todo = &mut self.todos_mut()[index];
+            if todo.completed != complete {
+                todo.set_completed(complete);
+            }
+        }
+        self.clear_marks();
+        if self.current_page().settings.sort == SortOrder::CompletedLast {
+            let selected = self.selected_index().unwrap_or(0);
+            let index = self.sort_completed_last(selected);
+            self.select_index(index);
+        }
+        let todos = if targets.len() == 1 { "todo" } else { "todos" };
+        let done = if complete { "Completed" } else { "Reopened" };
+        self.set_status(format!("{} {} {}", done, targets.len(), todos));
+    }
+
+    // Pick a page for the marked todos, or the selected one, in the page switcher
+    pub fn start_moving_to_page(&mut self) {
+        if !self.targets().is_empty() {
+            self.start_page_switcher();
+            self.moving_to_page = true;
+        }
+    }
+
+    // Move the marked todos, or the selected one, to the end of page `index`
+    fn move_to_page(&mut self, index: usize) {
+        if index == self.current_page_index {
+            return;
+        }
+        self.checkpoint("Move to Page");
+        let moved = if self.marked.is_empty() {
+            self.remove_selected().into_iter().collect()
+        } else {
+            self.take_marked()
+        };
+        let label = self.page_label(&self.pages[index]);
+        match moved.as_slice() {
+            [todo] => self.notify(format!("Moved '{}' to page {}", todo.description, label)),
+            _ => self.notify(format!("Moved {} todos to page {}", moved.len(), label)),
+        }
+        self.pages[index].todos.extend(moved);
+    }
+
+    pub fn start_tagging(&mut self) {
+        if !self.targets().is_empty() {
+            self.current_input.clear();
+            self.input_mode = InputMode::Tag;
+        }
+    }
+
+    // Add the tags typed into the tag popup to the marked todos, or the
+    // selected one, and remove the ones written as `-tag`. Returns false when
+    // a tag is invalid.
+    pub fn apply_tags(&mut self) -> bool {
+        let mut add = Vec::new();
+        let mut remove = Vec::new();
+        for word in self.current_input.split_whitespace() {
+            let (tags, tag) = match word.strip_prefix('-') {
+                Some(tag) => (&mut remove, tag),
+                None => (&mut add, word.strip_prefix('+').unwrap_or(word)),
+            };
+            match parse_tag(&format!("+{}", tag)) {
+                Some(tag) => tags.push(tag),
+                None => {
+                    self.set_status(format!("Invalid tag `{}`", word));
+                    return false;
+                }
+            }
+        }
+        self.current_input.clear();
+        if add.is_empty() && remove.is_empty() {
+            return true;
+        }
+
+        self.checkpoint("Tag");
+        let targets = self.targets();
+        for &index in &targets {
+            let todo = &mut self.todos_mut()[index];
+            todo.tags.retain(|tag| !remove.contains(tag));
+            for tag in &add {
+                if !todo.tags.contains(tag) {
+                    todo.tags.push(tag.clone());
+                }
+            }
+            todo.touch();
+        }
+        self.clear_marks();
+        // Removing the filtered tag hides todos
+        self.clamp_selection();
+        let todos = if targets.len() == 1 { "todo" } else { "todos" };
+        self.set_status(format!("Tagged {} {}", targets.len(), todos));
+        true
+    }
+
+    // Move the selected todo into the page's archive
+    pub fn archive_todo(&mut self) {
+        if self.selected_index().is_some() {
+            self.checkpoint("Archive");
+        }
+        if let Some(todo) = self.remove_selected() {
+            self.set_status(format!("Archived {}", todo.description));
+            self.current_page_mut().archived.push(todo);
+        }
+    }
+
+    // Move every completed todo of the page into its archive
+    pub fn archive_completed(&mut self) {
+        if self.todos().iter().any(|todo| todo.completed) {
+            self.checkpoint("Archive");
+        }
+        let page = self.current_page_mut();
+        let (completed, open): (Vec<Todo>, Vec<Todo>) =
+            page.todos.drain(..).partition(|todo| todo.completed);
+        page.todos = open;
+        let count = completed.len();
+        page.archived.extend(completed);
+        self.clamp_selection();
+        self.set_status(match count {
+            0 => "No completed todos to archive".to_string(),
+            1 => "Archived 1 todo".to_string(),
+            _ => format!("Archived {} todos", count),
+        });
+    }
+
+    pub fn start_archive_browser(&mut self) {
+        if self.current_page().archived.is_empty() {
+            self.set_status("The archive of this page is empty");
+            return;
+        }
+        self.archive_select_state.select(Some(0));
+        self.input_mode = InputMode::Archive;
+    }
+
+    // Index into the page's archive of the highlighted entry, which lists the
+    // most recently archived todo first
+    fn selected_archived(&self) -> Option<usize> {
+        let count = self.current_page().archived.len();
+        let row = self.archive_select_state.selected()?;
+        (row < count).then(|| count - 1 - row)
+    }
+
+    // Put the highlighted archived todo back at the end of the page, or remove
+    // it for good. The browser closes with the last entry.
+    pub fn take_archived(&mut self, restore: bool) {
+        let Some(index) = self.selected_archived() else {
+            return;
+        };
+        self.checkpoint(if restore { "Restore" } else { "Delete" });
+        let mut todo = self.current_page_mut().archived.remove(index);
+        if restore {
+            todo.touch();
+            self.set_status(format!("Restored {}", todo.description));
+            self.todos_mut().push(todo);
+            let index = self.todos().len() - 1;
+            self.select_index(index);
+        } else {
+            self.set_status(format!("Deleted {}", todo.description));
+        }
+
+        let count = self.current_page().archived.len();
+        if count == 0 {
+            self.input_mode = InputMode::Normal;
+        } else {
+            self.archive_select_state.select(
+                self.archive_select_state
+                    .selected()
+                    .map(|i| i.min(count - 1)),
+            );
+        }
+    }
+
+    pub fn toggle_todo(&mut self) {
+        // On the header of a collapsed group it opens the group up
+        if self.selected_index().is_none() && self.selected_group().is_some() {
+            self.toggle_group();
+            return;
+        }
+        if !self.marked.is_empty() {
+            self.toggle_marked();
+            return;
+        }
+        if self.requires_completion_note() {
+            self.input_mode = InputMode::CompletionNote;
+            return;
+        }
+        self.checkpoint("Toggle");
+        self.finish_toggle();
+    }
+
+    fn finish_toggle(&mut self) {
+        if let (Some(row), Some(selected)) = (self.state.selected(), self.selected_index()) {
+            let todo = &mut self.todos_mut()[selected];
+            // Toggle the completion status
+            todo.set_completed(!todo.completed);
+
+            let settings = &self.current_page().settings;
+            let checklist_mode = settings.checklist_mode;
+            let done_section = settings.done_section;
+            let new_index = if settings.sort == SortOrder::CompletedLast {
+                self.sort_completed_last(selected)
+            } else {
+                selected
+            };
+
+            if checklist_mode {
+                // Move on to the next row, stopping at the last one. When sorting
+                // or the done section moved the toggled item away, the next one
+                // already took its place.
+                let next = if new_index == selected && !done_section {
+                    row + 1
+                } else {
+                    row
+                };
+                self.select_row(next);
+            } else if done_section {
+                // The todo went to or left the done section, the next one
+                // takes its row
+                self.select_row(row);
+            } else {
+                self.select_index(new_index);
+            }
+        }
+    }
+
+    // Stable sort of the current page with completed items last, returns the
+    // new position of the todo that was at `index`
+    fn sort_completed_last(&mut self, index: usize) -> usize {
+        let todos = self.todos_mut();
+        let mut order: Vec<usize> = (0..todos.len()).collect();
+        order.sort_by_key(|&i| todos[i].completed);
+
+        let mut old_todos: Vec<Option<Todo>> = todos.drain(..).map(Some).collect();
+        todos.extend(order.iter().filter_map(|&i| old_todos[i].take()));
+
+        order.iter().position(|&i| i == index).unwrap_or(index)
+    }
+
+    pub fn start_editing(&mut self) {
+        if let Some(selected) = self.selected_index() {
+            let quantities = self.current_page().settings.quantities;
+            self.current_input = self.todos()[selected].input_text(quantities);
+            self.input_mode = InputMode::Editing;
+            self.edit_mode = true;
+        }
+    }
+
+    // Open the due date popup for the selected todo
+    pub fn start_editing_due_date(&mut self) {
+        self.start_editing_date(InputMode::DueDate);
+    }
+
+    // Open the scheduled date popup for the selected todo
+    pub fn start_editing_scheduled(&mut self) {
+        self.start_editing_date(InputMode::Scheduled);
+    }
+
+    // Open the snooze popup for the selected todo
+    pub fn start_deferring(&mut self) {
+        self.start_editing_date(InputMode::Defer);
+    }
+
+    // Show or hide snoozed todos, keeping the selected todo selected
+    pub fn toggle_show_deferred(&mut self) {
+        let selected = self.selected_index();
+        self.show_deferred = !self.show_deferred;
+        match selected {
+            Some(index) if self.is_visible(&self.todos()[index]) => self.select_index(index),
+            _ => self.reset_selection(),
+        }
+    }
+
+    pub fn open_help(&mut self) {
+        self.help_scroll = 0;
+        self.input_mode = InputMode::Help;
+    }
+
+    pub fn open_day_summary(&mut self) {
+        self.summary_scroll = 0;
+        self.input_mode = InputMode::DaySummary;
+    }
+
+    // Open the start-of-day summary when it is turned on and hasn't been
+    // shown yet today
+    pub fn summarize_new_day(&mut self, today: NaiveDate) {
+        if !self.config.day_summary || self.summarized.is_some_and(|day| day >= today) {
+            return;
+        }
+        self.summarized = Some(today);
+        self.open_day_summary();
+    }
+
+    pub fn toggle_large_text(&mut self) {
+        self.large_text = !self.large_text;
+        self.set_status(if self.large_text {
+            "Large text on"
+        } else {
+            "Large text off"
+        });
+    }
+
+    // Switch between compact and detailed rows, remembered in the config file
+    pub fn toggle_density(&mut self) {
+        self.config.density = match self.config.density {
+            Density::Compact => Density::Detailed,
+            Density::Detailed => Density::Compact,
+        };
+        let density = self.config.density;
+        self.set_status(match density {
+            Density::Compact => "Compact rows",
+            Density::Detailed => "Detailed rows",
+        });
+        if let Err(err) = self
+            .storage
+            .set_config("density", &format!("\"{}\"", density.name()))
+        {
+            self.notify_error(format!("Could not save the density: {}", err));
+        }
+    }
+
+    // Show only the selected todo, or the first open one when it is done
+    pub fn toggle_focus(&mut self) {
+        if self.focus {
+            self.focus = false;
+            return;
+        }
+        if self.picking_mode {
+            return;
+        }
+        let open = self
+            .selected_index()
+            .is_some_and(|index| !self.todos()[index].completed);
+        if !open {
+            self.first_open();
+        }
+        if self
+            .selected_index()
+            .is_some_and(|index| !self.todos()[index].completed)
+        {
+            self.visual_anchor = None;
+            self.focus = true;
+        }
+    }
+
+    // Complete the focused todo and go on to the next open one
+    pub fn focus_done(&mut self) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        let todo = &self.todos()[index];
+        let id = todo.id;
+        if !todo.completed {
+            if self.requires_completion_note() {
+                self.input_mode = InputMode::CompletionNote;
+                return;
+            }
+            self.checkpoint("Toggle");
+            self.finish_toggle();
+        }
+        self.focus_step(id, true);
+    }
+
+    // Leave the focused todo for the next open one, or the previous one
+    pub fn focus_skip(&mut self, forward: bool) {
+        if let Some(index) = self.selected_index() {
+            self.focus_step(self.todos()[index].id, forward);
+        }
+    }
+
+    // Select the open todo after the todo with this ID in the list, wrapping
+    // around, and leave focus once none is left
+    fn focus_step(&mut self, id: Uuid, forward: bool) {
+        let todos = self.todos();
+        let mut shown = self.visible_indices();
+        let from = shown
+            .iter()
+            .position(|&index| todos[index].id == id)
+            .unwrap_or(0);
+        shown.rotate_left(from);
+        if !forward {
+            shown[1..].reverse();
+        }
+        let next = shown
+            .iter()
+            .skip(1)
+            .copied()
+            .find(|&index| !todos[index].completed);
+        match next {
+            Some(index) => self.select_index(index),
+            None if shown.first().is_some_and(|&index| !todos[index].completed) => {
+                self.set_status("No other open todo here")
+            }
+            None => {
+                self.focus = false;
+                self.set_status("Everything here is done");
+            }
+        }
+    }
+
+    // Open the reminder popup for the selected todo
+    pub fn start_editing_reminder(&mut self) {
+        self.start_editing_date(InputMode::Reminder);
+    }
+
+    fn start_editing_date(&mut self, mode: InputMode) {
+        if let Some(todo) = self.selected_todo() {
+            let date = match mode {
+                InputMode::Scheduled => todo.scheduled,
+                InputMode::Reminder => todo.reminder,
+                InputMode::Defer => todo.deferred_until,
+                _ => todo.due_date,
+            };
+            self.current_input = date.map(format_due_date).unwrap_or_default();
+            self.input_mode = mode;
+        }
+    }
+
+    // Apply the date popup input to the due date, scheduled date, reminder or
+    // snooze, depending on which popup is open. An empty input clears the date. Returns false and
+    // reports on the status line when the date is invalid.
+    pub fn update_date(&mut self) -> bool {
+        let date = if self.current_input.trim().is_empty() {
+            None
+        } else {
+            match parse_due_date(&self.current_input) {
+                Some(date) => Some(date),
+                None => {
+                    self.set_status(
+                        "Invalid date, expected YYYY-MM-DD, +Nd or e.g. tomorrow, fri, in 3 days, optionally with HH:MM",
+                    );
+                    return false;
+                }
+            }
+        };
+
+        if let Some((page, index)) = self.selected_location() {
+            self.checkpoint(match self.input_mode {
+                InputMode::Scheduled => "Schedule",
+                InputMode::Reminder => "Reminder",
+                InputMode::Defer => "Snooze",
+                _ => "Due date",
+            });
+            // Borrow the page directly, the input mode is read while the todo is held
+            let Some(todo) = self
+                .pages
+                .get_mut(page)
+                .and_then(|p| p.todos.get_mut(index))
+            else {
+                return false;
+            };
+            match self.input_mode {
+                InputMode::Scheduled => todo.scheduled = date,
+                InputMode::Reminder => todo.reminder = date,
+                InputMode::Defer => todo.deferred_until = date,
+                _ => todo.due_date = date,
+            }
+            todo.touch();
+
+            if self.view != View::List {
+                self.reselect_agenda((page, index));
+            } else {
+                // A snoozed todo leaves the list, keep the selection in range
+                self.clamp_selection();
+            }
+        }
+        self.current_input.clear();
+        true
+    }
+
+    // Show a desktop notification for every open todo whose reminder is due,
+    // then clear the reminder so it only fires once
+    pub fn fire_reminders(&mut self) {
+        let now = Local::now();
+        let mut failed = None;
+
+        for todo in self.pages.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+            if todo.completed || todo.reminder.is_none_or(|reminder| reminder > now) {
+                continue;
+            }
+
+            if let Err(err) = Notification::new()
+                .summary("RatDo reminder")
+                .body(&todo.description)
+                .show()
+            {
+                failed = Some(err);
+            }
+            todo.reminder = None;
+            todo.touch();
+            self.dirty = true;
+        }
+
+        if let Some(err) = failed {
+            self.set_status(format!("Could not show reminder: {}", err));
+        }
+    }
+
+    // Start or stop the timer of the selected todo. Only one timer runs at a
+    // time, starting one stops any other.
+    pub fn toggle_timer(&mut self) {
+        let Some((page, index)) = self.selected_location() else {
+            return;
+        };
+
+        self.checkpoint("Timer");
+        let Some(todo) = self.todo_at_mut((page, index)) else {
+            return;
+        };
+        if todo.timer_started.is_some() {
+            todo.stop_timer();
+            let message = format!(
+                "Timer stopped, {} tracked",
+                format_duration(todo.time_spent)
+            );
+            self.set_status(message);
+            return;
+        }
+
+        for todo in self.pages.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+            todo.stop_timer();
+        }
+        self.pages[page].todos[index].start_timer();
+    }
+
+    // Open the quick reschedule menu for the selected todo
+    pub fn start_reschedule(&mut self) {
+        if self.selected_location().is_some() {
+            self.input_mode = InputMode::Reschedule;
+        }
+    }
+
+    // Move the selected todo's due date by one of the quick reschedule options,
+    // keeping its time of day
+    pub fn reschedule(&mut self, option: Reschedule) {
+        let Some((page, index)) = self.selected_location() else {
+            return;
+        };
+
+        self.checkpoint("Reschedule");
+        let Some(todo) = self
+            .pages
+            .get_mut(page)
+            .and_then(|p| p.todos.get_mut(index))
+        else {
+            return;
+        };
+        let date = option.apply(todo.due_date, Local::now().date_naive(), &self.holidays);
+        let time = todo.due_date.map_or(NaiveTime::MIN, |due| due.time());
+        todo.due_date = date.and_time(time).and_local_timezone(Local).earliest();
+        todo.touch();
+
+        if self.view != View::List {
+            self.reselect_agenda((page, index));
+        }
+        self.set_status(format!("Due {}", date.format("%a %Y-%m-%d")));
+    }
+
+    // Open the multi-line notes editor for the selected todo
+    pub fn start_editing_notes(&mut self) {
+        if let Some(selected) = self.selected_index() {
+            self.current_input = self.todos()[selected].notes.clone();
+            self.input_mode = InputMode::Notes;
+        }
+    }
+
+    // Whether checking off the selected todo has to be explained with a note
+    fn requires_completion_note(&self) -> bool {
+        let Some((page, index)) = self.selected_location() else {
+            return false;
+        };
+        let (Some(page), Some(todo)) = (self.pages.get(page), self.todo_at((page, index))) else {
+            return false;
+        };
+        self.needs_completion_note(page, todo)
+    }
+
+    // Whether checking off `todo` has to be explained with a note, because of
+    // its page or one of its tags
+    fn needs_completion_note(&self, page: &TodoPage, todo: &Todo) -> bool {
+        !todo.completed
+            && (self
+                .config
+                .completion_note_pages
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&page.name))
+                || self
+                    .config
+                    .completion_note_tags
+                    .iter()
+                    .any(|tag| todo.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))))
+    }
+
+    // Add the completion note popup input to the notes of the selected todo and
+    // complete it. Returns false when the note is empty.
+    pub fn complete_with_note(&mut self) -> bool {
+        let note = self.current_input.trim().to_string();
+        if note.is_empty() {
+            self.set_status("A completion note is required to check off this todo");
+            return false;
+        }
+        self.checkpoint("Complete");
+
+        if let Some((page, index)) = self.selected_location() {
+            let Some(todo) = self.todo_at_mut((page, index)) else {
+                return false;
+            };
+            if !todo.notes.is_empty() {
+                todo.notes.push('\n');
+            }
+            let stamp = Local::now().format("%Y-%m-%d %H:%M");
+            todo.notes.push_str(&format!("Done {}: {}", stamp, note));
+            let id = todo.id;
+
+            match self.view {
+                View::List => {
+                    self.finish_toggle();
+                    // Focus moves on to the next open todo as without a note
+                    if self.focus {
+                        self.focus_step(id, true);
+                    }
+                }
+                View::Agenda | View::Review | View::Calendar | View::Stats => {
+                    self.finish_agenda_completion()
+                }
+            }
+        }
+        self.current_input.clear();
+        true
+    }
+
+    pub fn start_commenting(&mut self) {
+        if self.selected_location().is_some() {
+            self.current_input.clear();
+            self.input_mode = InputMode::Comment;
+        }
+    }
+
+    // Append the comment popup input to the selected todo's comments, signed
+    // with the configured name
+    pub fn add_comment(&mut self) {
+        let text = self.current_input.trim().to_string();
+        let author = self.config.author();
+        if !text.is_empty() && self.selected_location().is_some() {
+            self.checkpoint("Comment");
+        }
+        if let (false, Some(todo)) = (text.is_empty(), self.selected_todo_mut()) {
+            todo.comments.push(Comment {
+                author,
+                created_at: Local::now(),
+                text,
+            });
+            todo.touch();
+        }
+        self.current_input.clear();
+    }
+
+    pub fn start_saving_template(&mut self) {
+        if self.selected_index().is_some() {
+            self.current_input.clear();
+            self.input_mode = InputMode::TemplateName;
+        }
+    }
+
+    // Add the selected todo to the template named in the popup
+    pub fn save_template(&mut self) {
+        let name = self.current_input.trim().to_string();
+        self.current_input.clear();
+        let (false, Some(selected)) = (name.is_empty(), self.selected_index()) else {
+            return;
+        };
+
+        let todo = TemplateTodo::from_todo(&self.todos()[selected]);
+        let result = self.storage.load_templates().and_then(|mut templates| {
+            template::add(&mut templates, &name, todo);
+            self.storage.save_templates(&templates)?;
+            Ok(templates)
+        });
+        match result {
+            Ok(templates) => {
+                let count = templates
+                    .iter()
+                    .find(|t| t.name.eq_ignore_ascii_case(&name))
+                    .map_or(0, |t| t.todos.len());
+                self.set_status(format!("Saved to template {} ({} todos)", name, count));
+            }
+            Err(err) => self.set_status(format!("Could not save template: {}", err)),
+        }
+    }
+
+    pub fn start_template_picker(&mut self) {
+        match self.storage.load_templates() {
+            Ok(templates) if templates.is_empty() => {
+                self.set_status("No templates, press m to save a todo as one")
+            }
+            Ok(templates) => {
+                self.templates = templates;
+                self.template_select_state.select(Some(0));
+                self.input_mode = InputMode::Templates;
+            }
+            Err(err) => self.set_status(format!("Could not read templates: {}", err)),
+        }
+    }
+
+    // Add fresh copies of the highlighted template's todos after the selection
+    pub fn insert_template(&mut self) {
+        let Some(template) = self
+            .template_select_state
+            .selected()
+            .and_then(|i| self.templates.get(i))
+            .cloned()
+        else {
+            return;
+        };
+
+        let insertion_index = match self.selected_index() {
+            Some(index) => index + 1,
+            None => self.todos().len(),
+        };
+        self.checkpoint("Template");
+        for (offset, todo) in template.todos.iter().enumerate() {
+            self.todos_mut()
+                .insert(insertion_index + offset, todo.instantiate());
+        }
+        self.select_index(insertion_index);
+        self.set_status(format!(
+            "Added {} todos from {}",
+            template.todos.len(),
+            template.name
+        ));
+    }
+
+    // Delete the highlighted template, closing the picker with the last one
+    pub fn delete_template(&mut self) {
+        let Some(selected) = self.template_select_state.selected() else {
+            return;
+        };
+        if selected >= self.templates.len() {
+            return;
+        }
+
+        let removed = self.templates.remove(selected);
+        if let Err(err) = self.storage.save_templates(&self.templates) {
+            self.set_status(format!("Could not save templates: {}", err));
+            return;
+        }
+        self.set_status(format!("Deleted template {}", removed.name));
+
+        if self.templates.is_empty() {
+            self.input_mode = InputMode::Normal;
+        } else {
+            self.template_select_state
+                .select(Some(selected.min(self.templates.len() - 1)));
+        }
+    }
+
+    pub fn update_notes(&mut self) {
+        if let Some(selected) = self.selected_index() {
+            let notes = self.current_input.trim_end().to_string();
+            if self.todos()[selected].notes != notes {
+                self.checkpoint("Notes");
+                let todo = &mut self.todos_mut()[selected];
+                todo.notes = notes;
+                todo.touch();
+            }
+        }
+        self.current_input.clear();
+    }
+
+    pub fn update_todo(&mut self) {
+        if let Some(selected) = self.selected_index() {
+            self.checkpoint("Edit");
+            // Parse first to avoid borrowing issues
+            let quantities = self.current_page().settings.quantities;
+            let parsed = self.parsed_input();
+            self.current_input.clear();
+
+            let todo = &mut self.todos_mut()[selected];
+            todo.description = parsed.description;
+            todo.tags = parsed.tags;
+            todo.assignee = parsed.assignee;
+            todo.estimate = parsed.estimate;
+            todo.priority = parsed.priority;
+            // The due date is edited with `t`, a `due:` word only replaces it
+            if parsed.due_date.is_some() {
+                todo.due_date = parsed.due_date;
+            }
+            if quantities {
+                todo.quantity = parsed.quantity;
+            }
+            todo.touch();
+
+            // A `/page` word moves the todo over to that page
+            if let Some(page) = parsed.page.and_then(|name| self.page_for_token(&name)) {
+                if page != self.current_page_index {
+                    let todo = self.todos_mut().remove(selected);
+                    let label = self.page_label(&self.pages[page]);
+                    self.pages[page].todos.push(todo);
+                    self.clamp_selection();
+                    self.notify(format!("Moved to page {}", label));
+                }
+            }
+        }
+    }
+
+    fn get_config_path() -> io::Result<PathBuf> {
+        Ok(config::config_dir()?.join("todos.json"))
+    }
+
+    pub fn load_todos(&mut self) -> io::Result<()> {
+        if let Some(path) = &self.config.holidays {
+            self.holidays = Holidays::load(&config::expand_home(path)?)?;
+        }
+
+        self.stores = vec![Store {
+            name: "global".to_string(),
+            path: Self::get_config_path()?,
+            base: None,
+            hash: None,
+            saved_at: None,
+        }];
+        for store in &self.config.stores {
+            self.stores.push(Store {
+                name: store.name.clone(),
+                path: store.resolved_path()?,
+                base: None,
+                hash: None,
+                saved_at: None,
+            });
+        }
+
+        let mut pages = Vec::new();
+        for (index, store) in self.stores.iter_mut().enumerate() {
+            let mut store_pages = self.storage.load(store)?;
+            store.base = Some(store_pages.clone());
+
+            // Give additional stores a page to add todos to until they have their own
+            if store_pages.is_empty() && index > 0 {
+                store_pages.push(TodoPage::new(store.name.clone()));
+            }
+
+            for mut page in store_pages {
+                page.store = index;
+                pages.push(page);
+            }
+        }
+
+        if !pages.is_empty() {
+            self.pages = pages;
+
+            // Ensure the global store keeps at least one page
+            if !self.pages.iter().any(|p| p.store == 0) {
+                self.pages.insert(0, TodoPage::new("Default".to_string()));
+            }
+
+            let today = Local::now().date_naive();
+            for page in &mut self.pages {
+                page.reset_habits(today);
+                page.auto_archive(today);
+            }
+
+            // Set initial selection
+            self.reset_selection();
+            self.page_select_state.select(Some(0));
+
+            // Reset current page index in case it's invalid
+            self.current_page_index = 0;
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    // Save from the TUI, with the outcome in the status line
+    pub fn save(&mut self) {
+        if !self.storage.persistent() {
+            self.set_status("Ephemeral sessions aren't saved");
+            return;
+        }
+        match self.save_todos() {
+            Ok(()) => {
+                self.dirty = false;
+                self.notify("Saved");
+            }
+            Err(err) => self.notify_error(format!("Could not save: {}", err)),
+        }
+    }
+
+    // Write every store back to its own file, followed by the summary sidecar.
+    // Changes saved elsewhere since a file was read are merged in, the fields
+    // changed on both sides are listed in the conflicts popup.
+    pub fn save_todos(&mut self) -> io::Result<()> {
+        let mut conflicts = Vec::new();
+        for index in 0..self.stores.len() {
+            let pages: Vec<TodoPage> = self
+                .pages
+                .iter()
+                .filter(|p| p.store == index)
+                .cloned()
+                .collect();
+            let store = &self.stores[index];
+            // The page given to an empty store stays unsaved until it's used
+            let empty = store.base.as_ref().is_some_and(|base| base.is_empty());
+            if empty && is_placeholder(&pages, &store.name) {
+                continue;
+            }
+            let (pages, found) = self.storage.save(&mut self.stores[index], pages)?;
+            conflicts.extend(found);
+            self.stores[index].base = Some(pages.clone());
+            self.take_saved_pages(index, pages);
+        }
+        if !conflicts.is_empty() {
+            self.conflicts = conflicts;
+            self.conflict_select_state.select(Some(0));
+            self.input_mode = InputMode::Conflicts;
+        }
+        self.storage.save_summary(&Summary::from_pages(&self.pages))
+    }
+
+    // Take over the pages of a store as saved, with the changes merged in
+    // from elsewhere. They come in the order of ours, new pages last.
+    fn take_saved_pages(&mut self, store: usize, saved: Vec<TodoPage>) {
+        let selected = self.selected_index().map(|index| self.todos()[index].id);
+        let mut saved = saved.into_iter();
+        for page in self.pages.iter_mut().filter(|p| p.store == store) {
+            let Some(saved) = saved.next() else {
+                break;
+            };
+            page.todos = saved.todos;
+            page.settings = saved.settings;
+            page.archived = saved.archived;
+        }
+        for mut page in saved {
+            page.store = store;
+            self.pages.push(page);
+        }
+        match selected.and_then(|id| self.todos().iter().position(|t| t.id == id)) {
+            Some(index) => self.select_index(index),
+            None => self.clamp_selection(),
+        }
+    }
+
+    // Settle the highlighted conflict with our value or theirs, saving again
+    // once none are left
+    pub fn resolve_conflict(&mut self, take_theirs: bool) {
+        let Some(selected) = self.conflict_select_state.selected() else {
+            return;
+        };
+        if selected >= self.conflicts.len() {
+            return;
+        }
+        let conflict = self.conflicts.remove(selected);
+        if conflict.kept_mine == take_theirs {
+            self.checkpoint("Resolve conflict");
+            let value = match take_theirs {
+                true => conflict.theirs,
+                false => conflict.mine,
+            };
+            let todo = self
+                .pages
+                .iter_mut()
+                .flat_map(|page| page.todos.iter_mut())
+                .find(|todo| todo.id == conflict.id);
+            if let Some(todo) = todo {
+                let mut fields = merge::fields(todo);
+                fields.insert(conflict.field, value);
+                if let Ok(mut resolved) = serde_json::from_value::<Todo>(fields.into()) {
+                    resolved.updated_at = Some(Local::now());
+                    *todo = resolved;
+                }
+            }
+        }
+        if self.conflicts.is_empty() {
+            self.input_mode = InputMode::Normal;
+            self.save();
+        } else {
+            self.conflict_select_state
+                .select(Some(selected.min(self.conflicts.len() - 1)));
+        }
+    }
+
+    // Close the conflicts popup, keeping the newest edit of each
+    pub fn keep_newest(&mut self) {
+        let count = self.conflicts.len();
+        self.conflicts.clear();
+        self.input_mode = InputMode::Normal;
+        self.notify(format!(
+            "Kept the newest edit of {} conflicting fields",
+            count
+        ));
+    }
+
+    // Page name prefixed with its store when more than one store is loaded
+    pub fn page_label(&self, page: &TodoPage) -> String {
+        match self.stores.get(page.store) {
+            Some(store) if self.stores.len() > 1 => format!("{}/{}", store.name, page.name),
+            _ => page.name.clone(),
+        }
+    }
+
+    // The page label with the page's icon in front, for display only
+    pub fn page_title(&self, page: &TodoPage) -> String {
+        match &page.settings.icon {
+            Some(icon) => format!("{} {}", icon, self.page_label(page)),
+            None => self.page_label(page),
+        }
+    }
+
+    // Open the icon popup for the page highlighted in the page selector
+    pub fn start_editing_page_icon(&mut self) {
+        let Some(page) = self
+            .page_select_state
+            .selected()
+            .and_then(|i| self.pages.get(i))
+        else {
+            return;
+        };
+        self.current_input = page.settings.icon.clone().unwrap_or_default();
+        self.input_mode = InputMode::PageIcon;
+    }
+
+    // Set the highlighted page's icon from the popup, empty input removes it
+    pub fn set_page_icon(&mut self) {
+        let icon = self.current_input.trim().to_string();
+        self.current_input.clear();
+        self.input_mode = InputMode::PageSelect;
+        if icon.chars().count() > 4 {
+            self.set_status("Icons are at most 4 characters");
+            return;
+        }
+        let Some(selected) = self.page_select_state.selected() else {
+            return;
+        };
+        self.checkpoint("Icon");
+        if let Some(page) = self.pages.get_mut(selected) {
+            page.settings.icon = (!icon.is_empty()).then_some(icon);
+        }
+    }
+
+    // Get a list of page names - helpful for CLI "show" command
+    pub fn page_names(&self) -> Vec<String> {
+        self.pages.iter().map(|p| self.page_label(p)).collect()
+    }
+
+    // Select a page by the name shown for it, "store/page" with several
+    // stores. False when there is no such page.
+    pub fn select_page_by_label(&mut self, label: &str) -> bool {
+        match self.pages.iter().position(|p| self.page_label(p) == label) {
+            Some(index) => {
+                self.select_page(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Create a new page if it doesn't exist and select it
     pub fn create_or_select_page(&mut self, name: &str) {
         if !self.select_page_by_name(name) {
-            self.add_page(name.to_string());
+            self.add_page(name.to_string(), PageType::default());
+        }
+    }
+
+    // Open the journal page of `date`, named like "2025-03-04", creating it
+    pub fn open_journal(&mut self, date: NaiveDate) {
+        self.create_or_select_page(&date.format("%Y-%m-%d").to_string());
+    }
+
+    // Move the open todos of the latest journal page before the current one
+    // into it, for starting the day with what was left over
+    pub fn carry_over(&mut self) {
+        let Some(date) = journal_date(self.current_page()) else {
+            self.set_status("Carrying over only works on journal pages, see ratdo today");
+            return;
+        };
+        let Some(previous) = self
+            .pages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, page)| Some((journal_date(page)?, index)))
+            .filter(|&(d, _)| d < date)
+            .max()
+            .map(|(_, index)| index)
+        else {
+            self.set_status("No earlier journal page");
+            return;
+        };
+
+        let count = self.pages[previous]
+            .todos
+            .iter()
+            .filter(|t| !t.completed)
+            .count();
+        let name = self.pages[previous].name.clone();
+        if count == 0 {
+            self.set_status(format!("Nothing left open on {}", name));
+            return;
+        }
+        self.checkpoint("Carry Over");
+        let (open, completed): (Vec<Todo>, Vec<Todo>) = self.pages[previous]
+            .todos
+            .drain(..)
+            .partition(|todo| !todo.completed);
+        self.pages[previous].todos = completed;
+        self.todos_mut().extend(open);
+        if self.state.selected().is_none() {
+            self.reset_selection();
+        }
+        let todos = if count == 1 { "todo" } else { "todos" };
+        self.set_status(format!("Carried over {} {} from {}", count, todos, name));
+    }
+
+    // Catch up with a date change while running, e.g. at midnight: reset
+    // habit pages, apply the carry-over rule and move the agenda to the new
+    // day, keeping the same todo selected
+    pub fn refresh_date(&mut self, today: NaiveDate) {
+        if self.today == today {
+            return;
+        }
+        let selected = self.selected_agenda_entry();
+        self.today = today;
+        // One undo step for the habits reset and todos archived
+        let before = self.snapshot("New Day");
+        let mut changed = false;
+        for page in &mut self.pages {
+            changed |= page.reset_habits(today);
+            changed |= page.auto_archive(today);
+        }
+        if changed {
+            self.history.record(before);
+            self.dirty = true;
+        }
+        self.carry_over_new_day(today);
+        self.clamp_selection();
+        match selected {
+            Some(entry) => self.reselect_agenda((entry.page, entry.index)),
+            None => self
+                .agenda_state
+                .select((!self.agenda_entries().is_empty()).then_some(0)),
+        }
+    }
+
+    // Apply the configured carry-over rule on the first start of a day: move or
+    // copy the open todos of its pages onto today's journal page, or tag them
+    // +carried where they are. The page shown stays the same.
+    pub fn carry_over_new_day(&mut self, today: NaiveDate) {
+        let Some(rule) = self.config.carry_over.clone() else {
+            return;
+        };
+        if self.carried_over.is_some_and(|day| day >= today) {
+            return;
+        }
+        self.carried_over = Some(today);
+
+        let target = today.format("%Y-%m-%d").to_string();
+        let sources: Vec<usize> = (0..self.pages.len())
+            .filter(|&index| {
+                let page = &self.pages[index];
+                let label = self.page_label(page);
+                page.name != target
+                    && (rule.pages.iter().any(|name| {
+                        name.eq_ignore_ascii_case(&page.name) || name.eq_ignore_ascii_case(&label)
+                    }) || rule.journal && journal_date(page).is_some_and(|date| date < today))
+                    && page.todos.iter().any(|todo| !todo.completed)
+            })
+            .collect();
+        if sources.is_empty() {
+            return;
+        }
+        self.checkpoint("Carry Over");
+
+        let shown = self.current_page_index;
+        let selected = self.state.selected();
+        let mut carried = Vec::new();
+        let mut moved = Vec::new();
+        for index in sources {
+            let page = &mut self.pages[index];
+            let todos = std::mem::take(&mut page.todos);
+            let (open, completed): (Vec<Todo>, Vec<Todo>) =
+                todos.into_iter().partition(|todo| !todo.completed);
+            carried.push((self.page_label(&self.pages[index]), open.len()));
+            let page = &mut self.pages[index];
+            match rule.action {
+                CarryOverAction::Move => {
+                    page.todos = completed;
+                    moved.extend(open);
+                }
+                CarryOverAction::Copy => {
+                    page.todos = open.clone();
+                    page.todos.extend(completed);
+                    moved.extend(open.into_iter().map(|todo| Todo {
+                        id: Uuid::new_v4(),
+                        ..todo
+                    }));
+                }
+                CarryOverAction::Flag => {
+                    page.todos = open;
+                    for todo in &mut page.todos {
+                        if !todo.tags.iter().any(|tag| tag == "carried") {
+                            todo.tags.push("carried".to_string());
+                            todo.touch();
+                        }
+                    }
+                    page.todos.extend(completed);
+                }
+            }
+        }
+        if rule.action != CarryOverAction::Flag {
+            self.create_or_select_page(&target);
+            let copy = rule.action == CarryOverAction::Copy;
+            let todos = self.todos_mut();
+            for mut todo in moved {
+                // Copies already on the page aren't added twice, their
+                // originals stay where they are. Moved todos always arrive,
+                // they are no longer anywhere else.
+                let present = todos
+                    .iter()
+                    .any(|t| t.id == todo.id || copy && t.description == todo.description);
+                if !present {
+                    todo.touch();
+                    todos.push(todo);
+                }
+            }
+            self.current_page_index = shown;
+            self.page_select_state.select(Some(shown));
+            self.state.select(selected);
+            self.clamp_selection();
+        }
+
+        let count: usize = carried.iter().map(|(_, count)| count).sum();
+        let from = carried
+            .iter()
+            .map(|(label, count)| format!("{} from {}", count, label))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let todos = if count == 1 { "todo" } else { "todos" };
+        self.set_status(match rule.action {
+            CarryOverAction::Flag => format!("Tagged {} open {} +carried: {}", count, todos, from),
+            _ => format!("Carried over {} {} to {}: {}", count, todos, target, from),
+        });
+    }
+}
+
+// Date of a journal page, named after it
+pub fn journal_date(page: &TodoPage) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&page.name, "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn parse_date_words_and_offsets() {
+        // A Monday
+        let today = date("2025-03-03");
+        let parse = |input| parse_date(input, today);
+        assert_eq!(parse("2025-12-24"), Some(date("2025-12-24")));
+        assert_eq!(parse("+3d"), Some(date("2025-03-06")));
+        assert_eq!(parse("2w"), Some(date("2025-03-17")));
+        assert_eq!(parse("Tomorrow"), Some(date("2025-03-04")));
+        assert_eq!(parse("yesterday"), Some(date("2025-03-02")));
+        assert_eq!(parse("in a month"), Some(date("2025-04-03")));
+        assert_eq!(parse("in 2 weeks"), Some(date("2025-03-17")));
+        assert_eq!(parse("next fri"), Some(date("2025-03-14")));
+    }
+
+    #[test]
+    fn parse_date_weekdays_wrap_to_next_week() {
+        let sunday = date("2025-03-09");
+        assert_eq!(parse_date("mon", sunday), Some(date("2025-03-10")));
+        assert_eq!(parse_date("next week", sunday), Some(date("2025-03-10")));
+        // The same weekday is a week away, not today
+        assert_eq!(parse_date("sunday", sunday), Some(date("2025-03-16")));
+        // Months are cut short at their last day
+        assert_eq!(
+            parse_date("next month", date("2025-01-31")),
+            Some(date("2025-02-28"))
+        );
+    }
+
+    #[test]
+    fn parse_date_rejects_bad_input() {
+        let today = date("2025-03-03");
+        for input in [
+            "",
+            "  ",
+            "2025-02-30",
+            "su",
+            "next",
+            "in 2 fortnights",
+            "in many days",
+            "+xd",
+        ] {
+            assert_eq!(parse_date(input, today), None, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn shopping_pages_archive_earlier_purchases() {
+        let today = date("2025-03-03");
+        let mut page = TodoPage::new("Groceries".to_string());
+        page.settings = PageSettings::preset(PageType::Shopping);
+        for (description, day, hour) in [("Milk", "2025-03-02", 23), ("Eggs", "2025-03-03", 0)] {
+            let mut todo = Todo::new(description.to_string());
+            todo.set_completed(true);
+            todo.completed_at = date(day)
+                .and_hms_opt(hour, 30, 0)
+                .unwrap()
+                .and_local_timezone(Local)
+                .single();
+            page.todos.push(todo);
+        }
+        page.todos.push(Todo::new("Bread".to_string()));
+
+        assert!(page.auto_archive(today));
+        let names = |todos: &[Todo]| {
+            todos
+                .iter()
+                .map(|t| t.description.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&page.todos), ["Eggs", "Bread"]);
+        assert_eq!(names(&page.archived), ["Milk"]);
+        assert!(!page.auto_archive(today));
+    }
+
+    #[test]
+    fn parse_estimate_sizes_and_durations() {
+        assert_eq!(parse_estimate("~xs"), Some(15));
+        assert_eq!(parse_estimate("~XL"), Some(240));
+        assert_eq!(parse_estimate("~45m"), Some(45));
+        assert_eq!(parse_estimate("~2h"), Some(120));
+        assert_eq!(parse_estimate("~1h30m"), Some(90));
+        for word in [
+            "30m",
+            "~",
+            "~0m",
+            "~0h",
+            "~1h30",
+            "~h",
+            "~xxl",
+            "~-5m",
+            "~99999999h",
+        ] {
+            assert_eq!(parse_estimate(word), None, "{:?}", word);
+        }
+        for minutes in [15, 60, 90, 240] {
+            assert_eq!(
+                parse_estimate(&format!("~{}", format_estimate(minutes))),
+                Some(minutes)
+            );
+        }
+    }
+
+    #[test]
+    fn fuzzy_score_order_and_ties() {
+        assert_eq!(fuzzy_score("", "Work"), Some(0));
+        assert_eq!(fuzzy_score("wk", "Work"), Some(12));
+        assert_eq!(fuzzy_score("WO", "work"), fuzzy_score("wo", "Work"));
+        assert_eq!(fuzzy_score("kw", "Work"), None);
+        assert_eq!(fuzzy_score("works", "Work"), None);
+        assert_eq!(fuzzy_score("a", ""), None);
+
+        // Word starts and runs score higher than scattered letters
+        let score = |name| fuzzy_score("sl", name).unwrap();
+        assert!(score("Shopping list") > score("Sales"));
+        assert!(score("Slides") > score("Sales"));
+        // Names matching equally well tie, and keep the page order
+        assert_eq!(score("Sleep"), score("Slack"));
+        let mut app = App::new();
+        for name in ["Sleep", "Slack", "Shopping list"] {
+            app.add_page(name.to_string(), PageType::default());
         }
+        app.current_input = "sl".to_string();
+        assert_eq!(app.page_matches(), [3, 1, 2]);
     }
 }
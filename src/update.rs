@@ -0,0 +1,143 @@
+use crate::config;
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Only the tag of the latest release is asked for, nothing about the todos or
+// the machine is sent along
+const LATEST_RELEASE: &str = "https://api.github.com/repos/JanLubojacky/RatDo/releases/latest";
+
+// Days a check is reused before GitHub is asked again
+const CHECK_EVERY_DAYS: i64 = 7;
+
+// Outcome of the last check, kept in ~/.config/ratdo/update.json
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateCheck {
+    pub checked_at: DateTime<Local>,
+    pub latest: String,
+}
+
+impl UpdateCheck {
+    fn path() -> io::Result<PathBuf> {
+        Ok(config::config_dir()?.join("update.json"))
+    }
+
+    // Read the last check, None when there is none or it can't be parsed
+    pub fn load() -> io::Result<Option<Self>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?).ok())
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+    }
+}
+
+// Version of the latest release on GitHub, fetched with curl
+pub fn latest_version() -> io::Result<String> {
+    #[derive(Deserialize)]
+    struct Release {
+        tag_name: String,
+    }
+
+    let output = Command::new("curl")
+        .args(["--silent", "--fail", "--location", "--max-time", "10"])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .arg(LATEST_RELEASE)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => io::Error::other("curl is needed to check for updates"),
+            _ => err,
+        })?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "Cannot get the latest release from {}",
+            LATEST_RELEASE
+        )));
+    }
+    let release: Release = serde_json::from_slice(&output.stdout)?;
+    Ok(release.tag_name.trim_start_matches('v').to_string())
+}
+
+// Whether `latest` comes after `current`, comparing the dot separated numbers.
+// A pre-release counts as the release it leads up to.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    let numbers = |version: &str| -> Vec<u64> {
+        version
+            .trim_start_matches('v')
+            .split(['.', '-', '+'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    numbers(latest) > numbers(current)
+}
+
+// Look for a newer release without holding up the TUI, sending its version
+// once found. GitHub is asked at most once a week, in between the last check
+// is used. Failures stay quiet, the next start tries again.
+pub fn check_in_background() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let now = Local::now();
+        let latest = match UpdateCheck::load().ok().flatten() {
+            Some(check) if now - check.checked_at < Duration::days(CHECK_EVERY_DAYS) => {
+                check.latest
+            }
+            _ => {
+                let Ok(latest) = latest_version() else {
+                    return;
+                };
+                let check = UpdateCheck {
+                    checked_at: now,
+                    latest,
+                };
+                let _ = check.save();
+                check.latest
+            }
+        };
+        if is_newer(&latest, VERSION) {
+            let _ = sender.send(latest);
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_versions() {
+        assert!(is_newer("0.10.0", "0.9.3"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0", "0.2.0"));
+
+        // Tags are written with a v
+        assert!(is_newer("v0.2.0", "0.1.0"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+
+        assert!(is_newer("0.3.0-beta.1", "0.2.0"));
+        assert!(!is_newer("0.3.0-beta.1", "0.3.0"));
+
+        // A missing number comes before any
+        assert!(is_newer("1.2.1", "1.2"));
+        assert!(is_newer("1.3", "1.2.9"));
+        assert!(!is_newer("1.2", "1.2.0"));
+    }
+}